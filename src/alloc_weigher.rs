@@ -0,0 +1,57 @@
+//! `alloc-weigher` 特性：按分配器报告的真实分配大小（`malloc_usable_size`）计算条目权重
+//! `alloc-weigher` feature: weigh entries by the allocator-reported usable size (`malloc_usable_size`)
+//!
+//! 基于容量的默认估算忽略了分配器的尺寸分级与对齐，大量小文件时加权总量会明显低于实际 RSS。
+//! The default capacity-based estimate ignores allocator size classes and alignment, so with many small files
+//! the weighted total noticeably undershoots the actual RSS.
+
+use crate::weigh::Weighing;
+use crate::{AsyncLineCache, LineBuffer};
+
+/// `Arc` 控制块（强/弱计数）与结构体本身的大小
+/// Size of the `Arc` control block (strong/weak counts) plus the struct itself
+const ARC_INNER: usize = 2 * std::mem::size_of::<usize>() + std::mem::size_of::<LineBuffer>();
+
+/// 查询一块由系统 malloc 分配的内存的真实可用大小
+/// Query the real usable size of a block allocated by the system malloc
+///
+/// # Safety
+///
+/// `ptr` 必须是当前仍存活、且由系统 malloc 分配的内存块起始地址。
+/// `ptr` must be the start of a live block allocated by the system malloc.
+unsafe fn usable_size(ptr: *const u8) -> usize {
+    // SAFETY: 由调用方保证 | guaranteed by the caller
+    unsafe { libc::malloc_usable_size(ptr.cast_mut().cast()) }
+}
+
+/// 行缓冲区各堆块的真实分配大小之和
+/// Sum of the true allocated sizes of the line buffer's heap blocks
+pub(crate) fn lines_size(v: &LineBuffer) -> usize {
+    // SAFETY: 只有在调用方通过 `with_allocator_weigher` 保证使用系统分配器后才会走到这里；
+    // 这些指针来自条目仍持有的堆块
+    // SAFETY: only reachable after the caller promised the system allocator via `with_allocator_weigher`;
+    // the pointers come from blocks the entry still owns
+    let blocks: usize = v.heap_blocks().map(|ptr| unsafe { usable_size(ptr) }).sum();
+    blocks + ARC_INNER
+}
+
+impl AsyncLineCache {
+    /// 改用分配器真实分配大小作为权重（替换默认的基于容量的估算），容量上限保持不变
+    /// Switch to the allocator's true allocated size as weight (replacing the capacity-based estimate), keeping the capacity limits
+    ///
+    /// 需在放入任何条目之前调用：条目缓存会以新的权重函数重建。
+    /// Call before inserting any entries: the entry cache is rebuilt with the new weigher.
+    ///
+    /// # Safety
+    ///
+    /// 进程必须使用系统分配器（glibc / musl 的 malloc）作为全局分配器；
+    /// 若安装了 jemalloc、mimalloc 等 `#[global_allocator]`，`malloc_usable_size` 的行为未定义。
+    /// The process must use the system allocator (glibc / musl malloc) as its global allocator;
+    /// with a `#[global_allocator]` such as jemalloc or mimalloc installed, `malloc_usable_size` is undefined behavior.
+    #[must_use]
+    pub unsafe fn with_allocator_weigher(mut self) -> Self {
+        self.weighing = Weighing::Allocator;
+        self.rebuild_entries();
+        self
+    }
+}
@@ -0,0 +1,95 @@
+//! 审计日志：以 JSONL 格式逐条追加记录缓存的变更，供合规审查进程接触过哪些数据
+//! Audit log: appends one JSONL record per cache mutation, for compliance review of what data the process touched
+
+use crate::{CacheHooks, FileEntry};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::sync::{Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 把加载、失效、清空与驱逐写入调用方提供的输出的回调，每个事件一行 JSON
+/// Hooks writing loads, invalidations, clears and evictions to a caller-supplied writer, one JSON object per line
+///
+/// - 每行包含 `ts_ms`（Unix 毫秒时间戳）与 `event`；涉及文件的事件带 `file`，加载事件另带 `bytes`、`source` 与 `generation`
+/// - `source` 区分写入路径：`disk`（从磁盘读取）、`loader`（加载器提供）、`virtual`（[`insert_virtual`](crate::AsyncLineCache::insert_virtual) 写入、磁盘上没有同名文件）与 `override`（`insert_virtual` 写入并遮蔽了磁盘上的同名文件）
+/// - 每条记录整行写入后立即 flush，多个任务并发写入时行不会交错；写入失败被忽略，不影响缓存操作
+/// - 通过 [`AsyncLineCache::with_hooks`](crate::AsyncLineCache::with_hooks) 注册，会替换之前注册的回调
+///
+/// - Every line carries `ts_ms` (a Unix timestamp in milliseconds) and `event`; file events add `file`, and loads also add `bytes`, `source` and `generation`
+/// - `source` tells the insert paths apart: `disk` (read from disk), `loader` (provided by a loader), `virtual` (written by [`insert_virtual`](crate::AsyncLineCache::insert_virtual) with no file of that name on disk) and `override` (written by `insert_virtual`, shadowing a file of that name on disk)
+/// - Each record is written as a whole line and flushed at once, so concurrent tasks never interleave lines; write errors are ignored and never affect cache operations
+/// - Register it through [`AsyncLineCache::with_hooks`](crate::AsyncLineCache::with_hooks), which replaces any hooks registered before
+pub struct AuditLog<W> {
+    out: Mutex<W>,
+}
+
+impl<W: Write + Send> AuditLog<W> {
+    /// 把审计记录写入 `out` | Write audit records to `out`
+    pub fn new(out: W) -> Self {
+        Self { out: Mutex::new(out) }
+    }
+
+    /// 取回输出 | Take back the writer
+    pub fn into_inner(self) -> W {
+        self.out.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn record(&self, event: &str, fields: &str) {
+        let ts_ms = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis());
+        let line = format!("{{\"ts_ms\":{ts_ms},\"event\":\"{event}\"{fields}}}\n");
+        let mut out = self.out.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = out.write_all(line.as_bytes()).and_then(|()| out.flush());
+    }
+}
+
+impl<W> std::fmt::Debug for AuditLog<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog").finish_non_exhaustive()
+    }
+}
+
+impl<W: Write + Send> CacheHooks for AuditLog<W> {
+    fn on_load(&self, filename: &str, entry: &FileEntry) {
+        let fields = format!(
+            ",\"file\":{},\"bytes\":{},\"source\":\"{}\",\"generation\":{}",
+            json_string(filename),
+            entry.raw().len(),
+            entry.source().as_str(),
+            entry.generation()
+        );
+        self.record("load", &fields);
+    }
+
+    fn on_invalidate(&self, filename: &str) {
+        self.record("invalidate", &format!(",\"file\":{}", json_string(filename)));
+    }
+
+    fn on_clear(&self) {
+        self.record("clear", "");
+    }
+
+    fn on_evict(&self, filename: &str) {
+        self.record("evict", &format!(",\"file\":{}", json_string(filename)));
+    }
+}
+
+/// 转义为 JSON 字符串字面量 | Escape as a JSON string literal
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
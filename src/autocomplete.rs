@@ -0,0 +1,31 @@
+//! 前缀自动补全：在文件的排序索引上做范围查询
+//! Prefix autocomplete: range queries over the file's sorted index
+
+use crate::AsyncLineCache;
+
+impl AsyncLineCache {
+    /// 返回以 `prefix` 开头的不同行，按字节序最多 `limit` 个，适合关键词文件的即输即补
+    /// Return the distinct lines starting with `prefix`, at most `limit` of them in byte order, for as-you-type suggestions over keyword files
+    ///
+    /// - 复用 [`sorted_view`](Self::sorted_view) 的排序索引：同一版本的文件只排序一次，之后每次查询只需二分定位再顺序读取
+    /// - 区分大小写；重复的行只返回一次
+    ///
+    /// - Reuses the sorted index of [`sorted_view`](Self::sorted_view): a given version of the file is sorted once, after which every query is a binary search plus a sequential read
+    /// - Case-sensitive; duplicate lines are returned once
+    pub async fn autocomplete(&self, filename: &str, prefix: &str, limit: usize) -> std::io::Result<Vec<String>> {
+        let Some((entry, order)) = self.sorted_view(filename).sorted().await? else { return Ok(Vec::new()) };
+        let lines = entry.lines();
+        let line = |idx: usize| lines.get(idx).unwrap_or_default();
+        let start = order.partition_point(|&idx| line(idx) < prefix);
+        let mut suggestions: Vec<String> = Vec::new();
+        for candidate in order[start..].iter().map(|&idx| line(idx)).take_while(|line| line.starts_with(prefix)) {
+            if suggestions.len() == limit {
+                break;
+            }
+            if suggestions.last().is_none_or(|last| last != candidate) {
+                suggestions.push(candidate.to_string());
+            }
+        }
+        Ok(suggestions)
+    }
+}
@@ -0,0 +1,232 @@
+//! 行缓冲区：整份文本存放在一块连续内存中，再加一张行起始偏移索引
+//! Line buffer: the whole text lives in one contiguous allocation, plus an index of line start offsets
+
+use std::fmt;
+
+/// 对象头、对齐等保守估计 | Conservative estimate for object headers/alignment
+#[cfg(any(feature = "async", feature = "sync"))]
+const ENTRY_OVERHEAD: usize = 128;
+
+/// 单个文件解析后的行数据
+/// Parsed lines of a single file
+///
+/// - 原始文本只保存一份（不再为每一行单独分配 `String`），加载峰值内存约等于文件大小
+/// - 一个条目只有两块堆内存（文本 + 偏移索引），相当于按条目整体分配、整体释放的 arena；
+///   百万行的文件也不会产生百万次 malloc 与内存碎片
+/// - 每行只额外占用一个偏移量（文本小于 4GiB 时为 `u32`），按下标访问仍为 O(1)
+/// - 行的切分规则与 `str::lines` 一致（去掉 `\n` / `\r\n`），并保留 Python linecache 的尾随空行规则
+///
+/// - The raw text is stored once (no per-line `String` allocation), so peak load memory is ~1× the file size
+/// - An entry owns just two heap blocks (text + offset index), acting as an arena allocated and freed as a unit;
+///   a file with millions of lines never costs millions of mallocs or the fragmentation that comes with them
+/// - Each line costs one extra offset (`u32` when the text is under 4GiB), and indexed access stays O(1)
+/// - Lines are split like `str::lines` (stripping `\n` / `\r\n`), keeping the Python linecache trailing-empty-line rule
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct LineBuffer {
+    text: String,
+    starts: Offsets,
+}
+
+/// 行起始偏移索引：文本小于 4GiB 时使用 `u32` 存储，索引内存减半
+/// Line start offsets: stored as `u32` when the text is under 4GiB, halving the index memory
+#[derive(Clone, PartialEq, Eq)]
+enum Offsets {
+    Narrow(Vec<u32>),
+    Wide(Vec<usize>),
+}
+
+impl Default for Offsets {
+    fn default() -> Self {
+        Offsets::Narrow(Vec::new())
+    }
+}
+
+impl Offsets {
+    /// 在文本上建立索引：首行从 0 开始，之后每个 '\n' 的下一个字节都是新行的起点
+    /// Index the text: the first line starts at 0, and the byte after every '\n' starts a new line
+    #[allow(clippy::cast_possible_truncation)]
+    fn build(text: &str) -> Self {
+        if text.is_empty() {
+            return Offsets::default();
+        }
+        // 先数出行数，使索引一次分配到位，不留多余容量
+        // Count the lines first so the index is allocated exactly once with no spare capacity
+        let count = text.bytes().filter(|&b| b == b'\n').count() + 1;
+        let starts = std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1));
+        if u32::try_from(text.len()).is_ok() {
+            let mut narrow = Vec::with_capacity(count);
+            narrow.extend(starts.map(|i| i as u32));
+            Offsets::Narrow(narrow)
+        } else {
+            let mut wide = Vec::with_capacity(count);
+            wide.extend(starts);
+            Offsets::Wide(wide)
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Offsets::Narrow(v) => v.len(),
+            Offsets::Wide(v) => v.len(),
+        }
+    }
+
+    fn get(&self, idx: usize) -> Option<usize> {
+        match self {
+            Offsets::Narrow(v) => v.get(idx).map(|&i| i as usize),
+            Offsets::Wide(v) => v.get(idx).copied(),
+        }
+    }
+
+    #[cfg(any(feature = "async", feature = "sync"))]
+    fn heap_size(&self) -> usize {
+        match self {
+            Offsets::Narrow(v) => v.capacity() * std::mem::size_of::<u32>(),
+            Offsets::Wide(v) => v.capacity() * std::mem::size_of::<usize>(),
+        }
+    }
+}
+
+impl LineBuffer {
+    /// 直接在读入的文本上建立行索引（不复制文本）
+    /// Build the line index directly over the text that was read (the text is not copied)
+    pub fn new(text: String) -> Self {
+        // 【关键兼容点】严格模仿 Python linecache 的行为：
+        // 如果文件以 \n 结尾且不为空，必须追加一个空行（其起点即文本末尾，由索引自然产生）
+        // Critical compatibility point: exactly mimic Python linecache behavior:
+        // If file ends with '\n' and is not empty, append an extra empty line (its start is the end of the text, produced naturally by the index)
+        let starts = Offsets::build(&text);
+        Self { text, starts }
+    }
+
+    /// 行数
+    /// Number of lines
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// 不含末尾换行符产生的兼容空行的行数
+    /// Number of lines, leaving out the compatibility empty line after a final newline
+    #[cfg(feature = "async")]
+    pub(crate) fn content_len(&self) -> usize {
+        if self.text.ends_with('\n') {
+            self.len() - 1
+        } else {
+            self.len()
+        }
+    }
+
+    /// 是否没有任何行（空文件）
+    /// Whether there are no lines at all (empty file)
+    pub fn is_empty(&self) -> bool {
+        self.starts.len() == 0
+    }
+
+    /// 获取第 `idx` 行（从 0 开始），不含换行符
+    /// Get the `idx`-th line (0-based), without its separator
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.line_range(idx).map(|range| &self.text[range])
+    }
+
+    /// 按顺序遍历所有行
+    /// Iterate over all lines in order
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &str> + DoubleEndedIterator + '_ {
+        self.iter_range(0, self.len())
+    }
+
+    /// 遍历第 `start..end` 行（从 0 开始，左闭右开；调用方保证不越界）
+    /// Iterate over lines `start..end` (0-based, half-open; the caller guarantees the bounds)
+    pub(crate) fn iter_range(
+        &self,
+        start: usize,
+        end: usize,
+    ) -> impl ExactSizeIterator<Item = &str> + DoubleEndedIterator + '_ {
+        (start..end).map(|idx| &self.text[self.start_of(idx)..self.line_end(idx)])
+    }
+
+    /// 完整的原始文本（含原始换行符）
+    /// The full raw text (with original separators)
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// 复制为独立的 `Vec<String>`
+    /// Copy into an owned `Vec<String>`
+    pub fn to_vec(&self) -> Vec<String> {
+        self.iter().map(String::from).collect()
+    }
+
+    /// 第 `start..end` 行（从 0 开始，左闭右开）在原始文本中的片段，行间保留原始换行符，末行之后不带换行符
+    /// Raw text of lines `start..end` (0-based, half-open) with original separators between them and none after the last
+    ///
+    /// 调用方保证 `start < end <= len()`。
+    /// The caller guarantees `start < end <= len()`.
+    #[cfg(feature = "async")]
+    pub(crate) fn span_text(&self, start: usize, end: usize) -> &str {
+        &self.text[self.start_of(start)..self.line_end(end - 1)]
+    }
+
+    /// 第 `idx` 行（不含换行符）在原始文本中的字节区间
+    /// Byte range of the `idx`-th line (without its separator) in the raw text
+    pub(crate) fn line_range(&self, idx: usize) -> Option<std::ops::Range<usize>> {
+        let start = self.starts.get(idx)?;
+        Some(start..self.line_end(idx))
+    }
+
+    /// 第 `idx` 行的起始字节偏移（调用方保证不越界）
+    /// Start byte offset of line `idx` (the caller guarantees the bound)
+    fn start_of(&self, idx: usize) -> usize {
+        self.starts.get(idx).unwrap_or(self.text.len())
+    }
+
+    /// 第 `idx` 行内容之后（换行符之前）的字节偏移
+    /// Byte offset just past the content of line `idx` (before its separator)
+    fn line_end(&self, idx: usize) -> usize {
+        let Some(next) = self.starts.get(idx + 1) else { return self.text.len() };
+        // next - 1 处是 '\n'；若其前一个字节是 '\r' 也一并去掉
+        // The byte at next - 1 is '\n'; drop a preceding '\r' as well
+        let end = next - 1;
+        if end > self.start_of(idx) && self.text.as_bytes()[end - 1] == b'\r' {
+            end - 1
+        } else {
+            end
+        }
+    }
+
+    /// 本条目持有的堆内存块起始指针（容量为 0 的块不分配，因而不包含在内）
+    /// Start pointers of the heap blocks owned by this entry (zero-capacity blocks are never allocated and are skipped)
+    #[cfg(all(feature = "alloc-weigher", target_os = "linux"))]
+    pub(crate) fn heap_blocks(&self) -> impl Iterator<Item = *const u8> {
+        let index: (*const u8, usize) = match &self.starts {
+            Offsets::Narrow(v) => (v.as_ptr().cast(), v.capacity()),
+            Offsets::Wide(v) => (v.as_ptr().cast(), v.capacity()),
+        };
+        [(self.text.as_ptr(), self.text.capacity()), index]
+            .into_iter()
+            .filter(|&(_, cap)| cap > 0)
+            .map(|(ptr, _)| ptr)
+    }
+
+    /// 实际占用的堆内存（基于容量而非长度），供权重计算使用
+    /// Heap memory actually held (capacity, not length), used for weighting
+    #[cfg(any(feature = "async", feature = "sync"))]
+    pub(crate) fn heap_size(&self) -> usize {
+        self.text.capacity() + self.starts.heap_size()
+    }
+
+    /// 作为缓存条目时的估计大小：堆内存加上对象头、对齐等固定开销
+    /// Estimated size as a cache entry: the heap memory plus a fixed overhead for object headers/alignment
+    #[cfg(any(feature = "async", feature = "sync"))]
+    pub(crate) fn entry_size(&self) -> usize {
+        self.heap_size() + ENTRY_OVERHEAD
+    }
+}
+
+impl fmt::Debug for LineBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LineBuffer")
+            .field("lines", &self.len())
+            .field("bytes", &self.text.len())
+            .finish_non_exhaustive()
+    }
+}
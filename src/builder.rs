@@ -0,0 +1,314 @@
+//! 可配置的缓存构造：内存预算、元数据缓存容量、权重函数、驱逐策略与过期时间
+//! Configurable cache construction: memory budget, metadata cache capacity, weigher, eviction policy and expiration
+
+use crate::clock::SharedClock;
+use crate::env::ENV_CONFIG;
+use crate::hooks::Hooks;
+use crate::namespace::Namespaces;
+use crate::quota::Quotas;
+use crate::stat::RecentStats;
+use crate::sync_point::SyncPoints;
+use crate::weigh::{self, Weighing};
+use crate::{content, dir, dir_sample, AsyncLineCache, RetryPolicy, CacheConfig, CacheHooks, CachedLines, Clock, GateSlot, FileOverrides, FileSet, LoaderRegistry, Quarantine, Served, Tasks, Transforms};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 默认占用的系统内存比例 | Default share of system memory
+#[cfg(feature = "sysinfo")]
+const DEFAULT_FRACTION: f64 = 0.85;
+
+/// 条目缓存在预算不足时选择驱逐对象的策略
+/// How the entry cache picks what to evict when the budget runs out
+///
+/// - 一次性遍历大量文件的扫描负载下，[`TinyLfu`](Self::TinyLfu) 保护常用的热点文件：扫描到的文件访问频率低，通常不被接纳；[`Lru`](Self::Lru) 偏向最近访问的文件
+/// - 底层缓存只提供这两种策略，不开放窗口大小或频率草图等更细的参数
+///
+/// - Under scan-heavy workloads that iterate many files once, [`TinyLfu`](Self::TinyLfu) protects frequently used hot files, since scanned files are rarely frequent enough to be admitted; [`Lru`](Self::Lru) favors whatever was accessed most recently
+/// - The underlying cache offers only these two policies and no finer knobs such as the window size or the frequency sketch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Eviction {
+    /// 按访问频率决定是否接纳新条目、按最近使用驱逐（默认），可抵御一次性扫描冲刷热点数据
+    /// Admit new entries by access frequency and evict by recency (the default), resisting one-off scans flushing hot data
+    #[default]
+    TinyLfu,
+    /// 总是接纳新条目并驱逐最久未使用的条目，适合访问热点随时间平移的负载
+    /// Always admit new entries and evict the least recently used, suited to workloads whose hot set drifts over time
+    Lru,
+}
+
+impl Eviction {
+    pub(crate) fn policy(self) -> moka::policy::EvictionPolicy {
+        match self {
+            Eviction::TinyLfu => moka::policy::EvictionPolicy::tiny_lfu(),
+            Eviction::Lru => moka::policy::EvictionPolicy::lru(),
+        }
+    }
+}
+
+/// 条目缓存的驱逐与过期设置，重建条目缓存时沿用
+/// Eviction and expiration settings of the entry cache, kept for rebuilding it
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct EntryPolicy {
+    pub(crate) eviction: Eviction,
+    pub(crate) time_to_live: Option<Duration>,
+    pub(crate) time_to_idle: Option<Duration>,
+}
+
+/// [`AsyncLineCache`] 的构造器，由 [`AsyncLineCache::builder`] 创建；未设置的项使用 [`AsyncLineCache::new`] 的默认值
+/// Builder for [`AsyncLineCache`], created by [`AsyncLineCache::builder`]; settings left unset use the defaults of [`AsyncLineCache::new`]
+///
+/// - 每个条目只保存一份文本，所有条目共用一个预算，无需在多个缓存之间划分
+/// - 同一进程中运行多个缓存时，为每个缓存设置明确的预算，避免它们各自按系统内存的 85% 估算
+///
+/// - Every entry holds its text once and all entries share one budget, so nothing is split between several caches
+/// - When running several caches in one process, give each an explicit budget so they do not each assume 85% of system memory
+///
+/// 未在构造器中设置的预算与 stat TTL 可以来自环境变量，见 [`AsyncLineCache::new`]。
+/// A budget or stat TTL not set on the builder may come from environment variables, see [`AsyncLineCache::new`].
+#[derive(Debug, Clone, Default)]
+pub struct AsyncLineCacheBuilder {
+    capacity: Option<u64>,
+    #[cfg(feature = "sysinfo")]
+    memory_fraction: Option<f64>,
+    memory_cap: Option<u64>,
+    metadata_capacity: Option<u64>,
+    policy: EntryPolicy,
+    weighing: Weighing,
+    root: Option<PathBuf>,
+    clock: SharedClock,
+    load_timeout: Option<Duration>,
+    retry: Option<RetryPolicy>,
+    hooks: Hooks,
+    config: CacheConfig,
+}
+
+impl AsyncLineCacheBuilder {
+    /// 条目缓存的总内存预算（字节），优先于 [`memory_fraction`](Self::memory_fraction)；设置后不再查询系统内存
+    /// Total memory budget of the entry cache in bytes, taking precedence over [`memory_fraction`](Self::memory_fraction); once set, system memory is never queried
+    #[must_use]
+    pub fn capacity(mut self, bytes: u64) -> Self {
+        self.capacity = Some(bytes);
+        self
+    }
+
+    /// 以系统物理内存的比例设置预算（限制在 `0.0..=1.0`，默认 0.85）；需要 `sysinfo` 特性
+    /// Set the budget as a share of physical memory (clamped to `0.0..=1.0`, 0.85 by default); requires the `sysinfo` feature
+    #[cfg(feature = "sysinfo")]
+    #[must_use]
+    pub fn memory_fraction(mut self, fraction: f64) -> Self {
+        self.memory_fraction = Some(fraction.clamp(0.0, 1.0));
+        self
+    }
+
+    /// 预算的绝对上限（字节）：最终预算取它与按比例或明确设置的预算中较小者
+    /// Absolute cap on the budget in bytes: the final budget is the smaller of this and the fractional or explicit budget
+    ///
+    /// 例如 `.memory_fraction(0.1).memory_cap(2 << 30)` 表示“最多使用 10% 的内存或 2GiB，取较小者”。
+    /// For example, `.memory_fraction(0.1).memory_cap(2 << 30)` means "use at most 10% of RAM or 2GiB, whichever is smaller".
+    #[must_use]
+    pub fn memory_cap(mut self, bytes: u64) -> Self {
+        self.memory_cap = Some(bytes);
+        self
+    }
+
+    /// 每个元数据缓存（stat 微缓存、目录列表、行数记忆与内容哈希）最多记录的项数，命名空间沿用该设置
+    /// Maximum items of each metadata cache (stat micro-cache, directory listings, remembered line counts and content hashes), inherited by namespaces
+    #[must_use]
+    pub fn metadata_capacity(mut self, items: u64) -> Self {
+        self.metadata_capacity = Some(items);
+        self
+    }
+
+    /// 条目缓存的驱逐策略 | Eviction policy of the entry cache
+    #[must_use]
+    pub fn eviction(mut self, eviction: Eviction) -> Self {
+        self.policy.eviction = eviction;
+        self
+    }
+
+    /// 条目写入 `ttl` 之后过期，无论期间是否被访问；过期的文件在下次访问时重新加载
+    /// Entries expire `ttl` after insertion, accessed or not; an expired file is reloaded on its next access
+    #[must_use]
+    pub fn time_to_live(mut self, ttl: Duration) -> Self {
+        self.policy.time_to_live = Some(ttl);
+        self
+    }
+
+    /// 条目连续 `tti` 未被访问后过期，长期运行的进程中不再使用的文件因此自行释放
+    /// Entries expire once unaccessed for `tti`, so files a long-running process no longer uses free themselves
+    ///
+    /// 过期只作用于条目缓存；目录列表与行数记忆本身按 mtime 校验，并受各自的容量限制。
+    /// Expiration applies to the entry cache; directory listings and remembered line counts are validated by mtime and bounded by their own capacity.
+    #[must_use]
+    pub fn time_to_idle(mut self, tti: Duration) -> Self {
+        self.policy.time_to_idle = Some(tti);
+        self
+    }
+
+    /// 以自定义函数计算条目权重（字节），替换基于容量的估算；预算、配额与水位线都按它计量
+    /// Weigh entries with a custom function (in bytes) instead of the capacity-based estimate; the budget, quotas and watermarks all count by it
+    ///
+    /// - 函数收到路径与行数据，应当快速返回；每个条目的内容只有一份（原始内容即行缓冲区的文本），因此一个函数就覆盖了全部内容
+    /// - 权重为 0 的条目不计入预算，只会因过期、失效或清空被移除
+    ///
+    /// - The function gets the path and the lines and should return quickly; each entry holds its content once (the raw content is the line buffer's text), so one function covers all of it
+    /// - Entries weighing 0 do not count against the budget and are only removed by expiration, invalidation or clearing
+    #[must_use]
+    pub fn weigher(mut self, weigher: impl Fn(&str, &CachedLines) -> u32 + Send + Sync + 'static) -> Self {
+        self.weighing = Weighing::Custom(Arc::new(weigher));
+        self
+    }
+
+    /// 沙箱模式：只允许读取位于 `path` 之内的文件与目录，适合把用户提供的文件名直接交给缓存的服务
+    /// Sandbox mode: only files and directories inside `path` may be read, for services passing user-supplied filenames to the cache
+    ///
+    /// - 每次从磁盘加载文件或列出目录前，请求的路径都会被规范化（展开 `..` 与符号链接）；落在根目录之外的路径返回 [`std::io::ErrorKind::PermissionDenied`] 错误，之后打开的正是通过检查的规范化路径
+    /// - 相对路径按根目录解析，而不是当前工作目录
+    /// - 根目录在构造时规范化，因此必须已经存在，否则 [`build`](Self::build) panic；命名空间沿用该设置
+    /// - 不存在的路径不读取磁盘，仍会交给已注册的加载器；[`AsyncLineCache::self_test`] 使用系统临时目录，临时目录不在根目录之内时会失败
+    ///
+    /// - Before any file is loaded from disk or any directory listed, the requested path is canonicalized (`..` and symlinks expanded); paths that land outside the root get a [`std::io::ErrorKind::PermissionDenied`] error, and what is opened afterwards is exactly the canonical path that passed the check
+    /// - Relative paths resolve against the root, not the working directory
+    /// - The root is canonicalized at construction, so it must already exist, or [`build`](Self::build) panics; namespaces inherit the setting
+    /// - Missing paths never touch the disk and are still handed to registered loaders; [`AsyncLineCache::self_test`] uses the system temp directory and fails when it is outside the root
+    #[must_use]
+    pub fn root_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.root = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// stat TTL、按文件 TTL 与访问时间（[`eviction_candidates`](AsyncLineCache::eviction_candidates)）所用的时钟，默认基于 [`std::time::Instant`]
+    /// Clock behind the stat TTL, per-file TTLs and access times ([`eviction_candidates`](AsyncLineCache::eviction_candidates)), [`std::time::Instant`]-based by default
+    ///
+    /// 测试中传入 [`ManualClock`](crate::ManualClock) 即可用 `advance` 代替真实的等待；见 [`Clock`] 了解不受影响的部分。
+    /// Pass a [`ManualClock`](crate::ManualClock) in tests to replace real sleeps with `advance`; see [`Clock`] for what is not affected.
+    #[must_use]
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = SharedClock::new(clock);
+        self
+    }
+
+    /// 单次从磁盘读取文件（打开、stat 与读取内容）的时间上限，超时返回 [`std::io::ErrorKind::TimedOut`] 错误，适合 NFS、FUSE 等可能长时间挂起的文件系统
+    /// Time limit of a single file read from disk (open, stat and reading the content), failing with [`std::io::ErrorKind::TimedOut`]; for NFS, FUSE and other filesystems that can hang for minutes
+    ///
+    /// - 超时后调用立即返回、加载锁与预留的加载额度随之释放；已经交给后台线程的那次系统调用无法中断，会在后台自行结束
+    /// - 只限制磁盘读取，不包括等待加载锁或其他任务的加载；限制整个调用请用 [`AsyncLineCache::get_line_timeout`] 或 [`AsyncLineCache::within_deadline`]
+    ///
+    /// - At the limit the call returns at once, releasing the load lock and the reserved load room; a system call already handed to a background thread cannot be interrupted and finishes there on its own
+    /// - Only the disk read is limited, not waiting for the load lock or another task's load; limit the whole call with [`AsyncLineCache::get_line_timeout`] or [`AsyncLineCache::within_deadline`]
+    #[must_use]
+    pub fn load_timeout(mut self, limit: Duration) -> Self {
+        self.load_timeout = Some(limit);
+        self
+    }
+
+    /// 加载文件与变更检测的 stat 遇到瞬时 I/O 错误时按 `policy` 重试，默认不重试
+    /// Retry file loads and change-detection stats under `policy` on transient I/O errors; nothing is retried by default
+    ///
+    /// 设置了 [`load_timeout`](Self::load_timeout) 时，它限制的是每一次尝试；只有用完尝试次数后错误才会返回给调用方并计入隔离。
+    /// With a [`load_timeout`](Self::load_timeout), it limits each attempt; only once the attempts run out is the error returned to the caller and counted towards quarantine.
+    #[must_use]
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// 生命周期回调，见 [`AsyncLineCache::with_hooks`] | Lifecycle callbacks, see [`AsyncLineCache::with_hooks`]
+    #[must_use]
+    pub fn hooks(mut self, hooks: Arc<dyn CacheHooks>) -> Self {
+        self.hooks = Hooks::new(hooks);
+        self
+    }
+
+    /// 构造完成后应用的配置，见 [`AsyncLineCache::apply_config`]
+    /// A config applied once construction is done, see [`AsyncLineCache::apply_config`]
+    #[must_use]
+    pub fn config(mut self, config: CacheConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 构造缓存 | Build the cache
+    ///
+    /// # Panics
+    ///
+    /// - 未启用 `sysinfo` 特性时，若预算、预算上限与 `LINECACHE_MAX_BYTES` 都没有设置则 panic
+    /// - 设置了 [`root_dir`](Self::root_dir) 但它无法规范化（不存在、无权访问等）时 panic，而不是以未规范化的路径做检查
+    ///
+    /// - Without the `sysinfo` feature, panics if none of the budget, the budget cap and `LINECACHE_MAX_BYTES` is set
+    /// - Panics if a [`root_dir`](Self::root_dir) is set but cannot be canonicalized (missing, no permission and so on), rather than checking against a non-canonical path
+    pub fn build(self) -> AsyncLineCache {
+        let env = &*ENV_CONFIG;
+        let budget = match self.capacity.or(env.max_bytes) {
+            Some(bytes) => bytes,
+            None => self.system_budget(),
+        }
+        .min(self.memory_cap.unwrap_or(u64::MAX));
+        let weighing = self.weighing;
+        let quotas = Quotas::default();
+        let meta = self.metadata_capacity;
+
+        let cache = AsyncLineCache {
+            entries: weigh::entries_cache(budget, &weighing, self.policy, &self.hooks, &quotas),
+            loaders: LoaderRegistry::default(),
+            recent_stats: Arc::new(RecentStats::new(env.stat_ttl.unwrap_or(Duration::ZERO), meta, self.clock.clone())),
+            budget,
+            weighing,
+            policy: self.policy,
+            locks: Arc::default(),
+            load_gate: GateSlot::new(budget),
+            hooks: self.hooks,
+            namespaces: Namespaces::default(),
+            quotas,
+            scope: None,
+            watermarks: Arc::default(),
+            dirs: dir::listings_cache(meta),
+            tasks: Tasks::default(),
+            line_counts: dir_sample::line_counts_cache(meta),
+            indexed: FileSet::default(),
+            folded: FileSet::default(),
+            transforms: Transforms::default(),
+            served: Served::default(),
+            quarantine: Quarantine::default(),
+            by_content: content::by_content_cache(meta),
+            overrides: FileOverrides::default(),
+            sync_points: SyncPoints::default(),
+            latencies: Arc::default(),
+            clock: self.clock,
+            load_timeout: self.load_timeout,
+            retry: self.retry.map(Arc::new),
+            root: self.root.map(|root| {
+                let canonical = std::fs::canonicalize(&root)
+                    .unwrap_or_else(|e| panic!("root_dir {} cannot be canonicalized: {e}", root.display()));
+                canonical.into()
+            }),
+        };
+        cache.apply_config(&self.config);
+        cache
+    }
+}
+
+impl AsyncLineCacheBuilder {
+    /// 按系统内存比例计算的预算 | The budget as a share of system memory
+    #[cfg(feature = "sysinfo")]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    fn system_budget(&self) -> u64 {
+        let fraction = self.memory_fraction.or(ENV_CONFIG.memory_fraction).unwrap_or(DEFAULT_FRACTION);
+        ((*crate::TOTAL_MEMORY as f64) * fraction) as u64
+    }
+
+    /// 没有系统内存探测时只能使用预算上限 | Without system memory detection only the budget cap can serve
+    #[cfg(not(feature = "sysinfo"))]
+    fn system_budget(&self) -> u64 {
+        self.memory_cap.expect("without the `sysinfo` feature, set a capacity, a memory cap or LINECACHE_MAX_BYTES")
+    }
+}
+
+impl AsyncLineCache {
+    /// 创建构造器，用于设置预算、元数据缓存容量与驱逐策略
+    /// Create a builder for setting the budget, metadata cache capacity and eviction policy
+    pub fn builder() -> AsyncLineCacheBuilder {
+        AsyncLineCacheBuilder::default()
+    }
+}
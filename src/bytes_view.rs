@@ -0,0 +1,47 @@
+//! `bytes` 特性：把缓存的行与文件内容零拷贝地导出为 `bytes::Bytes`
+//! `bytes` feature: export cached lines and file content as zero-copy `bytes::Bytes`
+
+use crate::{AsyncLineCache, CachedLines, LineBuffer};
+use bytes::Bytes;
+use std::sync::Arc;
+
+/// 让 `Bytes` 直接持有缓存条目的 `Arc`，导出时无需复制文本
+/// Lets `Bytes` hold the cache entry's `Arc` directly, so exporting never copies the text
+struct SharedText(CachedLines);
+
+impl AsRef<[u8]> for SharedText {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_str().as_bytes()
+    }
+}
+
+impl LineBuffer {
+    /// 整个文件内容的零拷贝 `Bytes` 视图（与缓存共享同一块内存）
+    /// Zero-copy `Bytes` view of the whole file content (sharing memory with the cache)
+    pub fn to_bytes(self: &Arc<Self>) -> Bytes {
+        Bytes::from_owner(SharedText(Arc::clone(self)))
+    }
+
+    /// 第 `idx` 行（从 0 开始，不含换行符）的零拷贝 `Bytes` 视图
+    /// Zero-copy `Bytes` view of the `idx`-th line (0-based, without its separator)
+    pub fn line_bytes(self: &Arc<Self>, idx: usize) -> Option<Bytes> {
+        let range = self.line_range(idx)?;
+        Some(self.to_bytes().slice(range))
+    }
+}
+
+impl AsyncLineCache {
+    /// 同 `get_line`，但返回与缓存共享内存的 `Bytes`（可零拷贝交给 axum/tonic 等框架）
+    /// Same as `get_line`, but returns `Bytes` sharing memory with the cache (zero-copy hand-off to axum/tonic etc.)
+    pub async fn get_line_bytes(&self, filename: &str, lineno: usize) -> std::io::Result<Option<Bytes>> {
+        let lines = self.fresh_lines(filename).await?;
+        Ok(lines.line_bytes(lineno.wrapping_sub(1)))
+    }
+
+    /// 同 `get_content`，但直接从条目的行缓冲区导出零拷贝的 `Bytes`
+    /// Same as `get_content`, but exported zero-copy from the entry's line buffer
+    pub async fn get_content_bytes(&self, filename: &str) -> std::io::Result<Option<Bytes>> {
+        let entry = self.fresh_entry(filename).await?;
+        Ok(entry.map(|e| e.lines().to_bytes()))
+    }
+}
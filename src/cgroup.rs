@@ -0,0 +1,58 @@
+//! 容器内的内存上限：cgroup v2 的 `memory.max` 或 cgroup v1 的 `memory.limit_in_bytes`
+//! The memory limit inside a container: cgroup v2 `memory.max` or cgroup v1 `memory.limit_in_bytes`
+//!
+//! 在容器中 `sysinfo` 报告的是宿主机内存；默认预算按两者中较小者计算，避免超出容器上限被 OOM 终止。
+//! Inside a container `sysinfo` reports the host's memory; the default budget uses the smaller of the two,
+//! so the process is not OOM-killed for outgrowing its container.
+
+use std::path::{Path, PathBuf};
+
+const ROOT: &str = "/sys/fs/cgroup";
+
+/// 当前进程所在 cgroup 的内存上限；没有 cgroup 或不设上限时为 `None`
+/// Memory limit of the current process's cgroup; `None` without a cgroup or without a limit
+pub(crate) fn memory_limit() -> Option<u64> {
+    let membership = std::fs::read_to_string("/proc/self/cgroup").unwrap_or_default();
+    candidates(&membership).into_iter().find_map(|file| read_limit(&file))
+}
+
+/// 当前进程所在 cgroup 距离内存上限还剩的字节数（上限减去当前用量）；没有 cgroup 或不设上限时为 `None`
+/// Bytes left before the current process's cgroup hits its memory limit (limit minus current usage); `None` without a cgroup or without a limit
+pub(crate) fn memory_available() -> Option<u64> {
+    let membership = std::fs::read_to_string("/proc/self/cgroup").unwrap_or_default();
+    candidates(&membership).into_iter().find_map(|file| {
+        let limit = read_limit(&file)?;
+        // v2 的用量在 `memory.current`，v1 在 `memory.usage_in_bytes` | v2 usage lives in `memory.current`, v1 in `memory.usage_in_bytes`
+        let usage_file = if file.ends_with("memory.max") { "memory.current" } else { "memory.usage_in_bytes" };
+        let usage: u64 = std::fs::read_to_string(file.with_file_name(usage_file)).ok()?.trim().parse().ok()?;
+        Some(limit.saturating_sub(usage))
+    })
+}
+
+/// 依次尝试的限制文件：进程自己的 cgroup，其次是挂载点根目录（容器内通常就是进程的 cgroup）
+/// Limit files to try in order: the process's own cgroup, then the mount root (usually the process's cgroup inside a container)
+fn candidates(membership: &str) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for line in membership.lines() {
+        // 格式为 `层级:控制器:路径`；v2 的控制器字段为空 | formatted `hierarchy:controllers:path`; v2 has no controllers
+        let mut fields = line.splitn(3, ':');
+        let (Some(_), Some(controllers), Some(path)) = (fields.next(), fields.next(), fields.next()) else { continue };
+        let path = path.trim_start_matches('/');
+        if controllers.is_empty() {
+            files.push(Path::new(ROOT).join(path).join("memory.max"));
+        } else if controllers.split(',').any(|c| c == "memory") {
+            files.push(Path::new(ROOT).join("memory").join(path).join("memory.limit_in_bytes"));
+        }
+    }
+    files.push(Path::new(ROOT).join("memory.max"));
+    files.push(Path::new(ROOT).join("memory").join("memory.limit_in_bytes"));
+    files
+}
+
+/// 读取一个限制文件；`max` 与 v1 表示“不限制”的巨大数值都视为没有上限
+/// Read one limit file; `max` and the huge value v1 uses for "unlimited" both count as no limit
+fn read_limit(file: &Path) -> Option<u64> {
+    let text = std::fs::read_to_string(file).ok()?;
+    let limit: u64 = text.trim().parse().ok()?;
+    (limit < 1 << 60).then_some(limit)
+}
@@ -0,0 +1,62 @@
+//! 批量变更检测：一次丢弃所有已过期的条目（对应 Python 的 `linecache.checkcache`）
+//! Bulk change detection: drop every stale entry in one pass (Python's `linecache.checkcache`)
+
+use crate::entry::Stamp;
+use crate::sync_point::SyncPoint;
+use crate::{AsyncLineCache, RetryPolicy};
+
+impl AsyncLineCache {
+    /// 立即 stat 文件并丢弃 mtime 或大小已变化（或文件已不存在）的条目，返回丢弃的条目数
+    /// Stat files right away and drop the entries whose mtime or size changed (or whose file is gone), returning how many were dropped
+    ///
+    /// - 给出文件名时只检查该文件，`None` 表示检查全部已缓存的文件
+    /// - 忽略 stat 微缓存 TTL 与变更检测开关，总是访问文件系统；来自加载器的虚拟条目不受影响
+    /// - 检查期间被并发重新加载的条目已是最新，不会被丢弃
+    ///
+    /// - Checks only that file when a filename is given, every cached file for `None`
+    /// - Ignores the stat micro-cache TTL and the change-detection switch, always asking the filesystem; virtual entries from loaders are left alone
+    /// - Entries reloaded concurrently while the check runs are already fresh and are not dropped
+    pub async fn checkcache(&self, filename: Option<&str>) -> usize {
+        let candidates: Vec<(String, u64, Stamp)> = match filename {
+            Some(filename) => self
+                .entries
+                .get(filename)
+                .await
+                .map(|entry| (filename.to_string(), entry.generation(), entry.stamp()))
+                .into_iter()
+                .collect(),
+            None => self
+                .entries
+                .iter()
+                .map(|(key, entry)| (key.to_string(), entry.generation(), entry.stamp()))
+                .collect(),
+        };
+        let mut dropped = 0;
+        for (filename, generation, stamp) in candidates {
+            let Stamp::Disk { mtime, size } = stamp else { continue };
+            let fresh = match RetryPolicy::run(self.retry.as_deref(), || tokio::fs::metadata(self.resolve(&filename))).await {
+                Ok(meta) => meta.modified().is_ok_and(|modified| modified == mtime) && meta.len() == size,
+                Err(_) => false,
+            };
+            if !fresh && self.drop_generation(&filename, generation).await {
+                dropped += 1;
+            }
+        }
+        dropped
+    }
+
+    /// 该文件的条目仍是第 `generation` 代时移除它，返回是否移除
+    /// Remove the file's entry if it is still generation `generation`, returning whether it was removed
+    async fn drop_generation(&self, filename: &str, generation: u64) -> bool {
+        let _guard = self.locks.lock(filename).await;
+        self.sync_points.reach(SyncPoint::Invalidate, filename).await;
+        if self.entries.get(filename).await.is_none_or(|entry| entry.generation() != generation) {
+            return false;
+        }
+        self.remove_entry(filename).await;
+        if let Some(hooks) = self.hooks.get() {
+            hooks.on_invalidate(filename);
+        }
+        true
+    }
+}
@@ -0,0 +1,89 @@
+//! 可注入的时钟：stat TTL、按文件 TTL 与访问时间都从这里读取当前时刻，测试可换成手动推进的时钟而不必真的等待
+//! Injectable clock: the stat TTL, per-file TTLs and access times all read the current moment here, so tests can swap in a manually advanced clock instead of sleeping
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+/// 单调时钟：返回自某个固定起点以来经过的时长，不得倒退
+/// A monotonic clock: returns the time elapsed since some fixed origin, and must never go backwards
+///
+/// - 变更检测比较的是文件 mtime 与加载时记录的 mtime 是否相等，不与当前时刻比较，因此不受墙上时钟偏差影响
+/// - 底层缓存的 `time_to_live` / `time_to_idle` 使用它自己的时钟，不受此设置影响
+///
+/// - Change detection compares a file's mtime for equality with the one recorded at load, never with the current moment, so wall-clock skew does not affect it
+/// - The underlying cache's `time_to_live` / `time_to_idle` run on its own clock and are not affected by this setting
+pub trait Clock: Send + Sync {
+    /// 自固定起点以来经过的时长 | Time elapsed since the fixed origin
+    fn now(&self) -> Duration;
+}
+
+/// 手动推进的时钟，从 0 开始，只在调用 [`advance`](Self::advance) 时前进；用于测试
+/// A manually advanced clock starting at 0 that only moves on [`advance`](Self::advance); for tests
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    nanos: AtomicU64,
+}
+
+impl ManualClock {
+    /// 创建停在 0 的时钟 | Create a clock stopped at 0
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 把时钟向前推进 `by` | Move the clock forward by `by`
+    pub fn advance(&self, by: Duration) {
+        self.nanos.fetch_add(nanos(by), Ordering::Relaxed);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// 默认时钟的起点 | Origin of the default clock
+static EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+/// 基于 [`Instant`] 的默认时钟 | The default clock, backed by [`Instant`]
+struct MonotonicClock;
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Duration {
+        EPOCH.elapsed()
+    }
+}
+
+/// 缓存使用的时钟，由所有克隆出的缓存实例及其命名空间共享
+/// The clock used by the cache, shared by every clone of the cache and its namespaces
+#[derive(Clone)]
+pub(crate) struct SharedClock(Arc<dyn Clock>);
+
+impl SharedClock {
+    pub(crate) fn new(clock: Arc<dyn Clock>) -> Self {
+        Self(clock)
+    }
+
+    pub(crate) fn now(&self) -> Duration {
+        self.0.now()
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self(Arc::new(MonotonicClock))
+    }
+}
+
+impl fmt::Debug for SharedClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedClock").finish_non_exhaustive()
+    }
+}
+
+/// 纳秒数，超出 `u64` 时饱和 | Nanoseconds, saturating at `u64`
+pub(crate) fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
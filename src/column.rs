@@ -0,0 +1,101 @@
+//! 列投影视图：把分隔符文本（TSV/CSV 之类）的某一列呈现为逐行的虚拟文件
+//! Column projection views: present one column of delimited text (TSV/CSV-ish) as a virtual line file
+
+use crate::{AsyncLineCache, FileEntry};
+use rand::Rng;
+use std::ops::Range;
+use std::sync::{Arc, PoisonError};
+
+/// 每行某一列在行内的字节范围 | Byte range of one column within every line
+pub(crate) type ColumnRanges = Arc<[Range<usize>]>;
+
+/// 源文件的第 `col` 列（从 0 开始）组成的虚拟文件，第 `n` 行即源文件第 `n` 行的该列
+/// A virtual file made of column `col` (0-based) of the source, line `n` being that column of source line `n`
+///
+/// - 每行按分隔符切分的结果（行内字节范围）按源文件的缓存条目记忆，同一版本的源文件每列只切分一次
+/// - 只按单个分隔符字符切分，不处理引号与转义；缺少该列的行视为空字符串
+/// - 源文件末尾换行符产生的兼容空行不计入视图
+///
+/// - Each line's split (byte range within the line) is memoized on the source's cache entry, so a given version is split once per column
+/// - Lines are split on a single delimiter character with no quoting or escaping; lines lacking the column read as an empty string
+/// - The compatibility empty line after the source's final newline is not part of the view
+#[derive(Debug, Clone)]
+pub struct ColumnView {
+    cache: AsyncLineCache,
+    source: String,
+    delimiter: char,
+    col: usize,
+}
+
+impl ColumnView {
+    /// 源文件名 | Source filename
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 行数 | Number of lines
+    pub async fn line_count(&self) -> std::io::Result<usize> {
+        Ok(self.fields().await?.map_or(0, |(_, fields)| fields.len()))
+    }
+
+    /// 第 `lineno` 行（从 1 开始）的该列值；超出范围时返回 `None`
+    /// The column's value on line `lineno` (1-based); `None` when out of range
+    pub async fn get_line(&self, lineno: usize) -> std::io::Result<Option<String>> {
+        let Some((entry, fields)) = self.fields().await? else { return Ok(None) };
+        Ok(lineno.checked_sub(1).and_then(|idx| field(&entry, &fields, idx)).map(String::from))
+    }
+
+    /// 随机返回一行的该列值；视图为空时返回 `None`
+    /// Return the column's value on a random line; `None` when the view is empty
+    pub async fn random_line(&self) -> std::io::Result<Option<String>> {
+        let Some((entry, fields)) = self.fields().await? else { return Ok(None) };
+        if fields.is_empty() {
+            return Ok(None);
+        }
+        let idx = rand::thread_rng().gen_range(0..fields.len());
+        Ok(field(&entry, &fields, idx).map(String::from))
+    }
+
+    /// 源文件的最新条目及每行该列的字节范围 | The source's current entry with the column's byte range on every line
+    async fn fields(&self) -> std::io::Result<Option<(FileEntry, ColumnRanges)>> {
+        let Some(entry) = self.cache.fresh_entry(&self.source).await? else { return Ok(None) };
+        let key = (self.delimiter, self.col);
+        let memo = &entry.derived().columns;
+        if let Some(fields) = memo.lock().unwrap_or_else(PoisonError::into_inner).get(&key) {
+            return Ok(Some((entry.clone(), Arc::clone(fields))));
+        }
+        // 切分期间不持锁 | split without holding the lock
+        let lines = entry.lines();
+        let fields: ColumnRanges = (0..lines.content_len())
+            .map(|idx| column_range(lines.get(idx).unwrap_or_default(), self.delimiter, self.col))
+            .collect();
+        let fields = Arc::clone(memo.lock().unwrap_or_else(PoisonError::into_inner).entry(key).or_insert(fields));
+        Ok(Some((entry, fields)))
+    }
+}
+
+/// 第 `idx` 行的该列文本 | The column's text on line `idx`
+fn field<'a>(entry: &'a FileEntry, fields: &[Range<usize>], idx: usize) -> Option<&'a str> {
+    let range = fields.get(idx)?.clone();
+    entry.lines().get(idx).and_then(|line| line.get(range))
+}
+
+/// 行内第 `col` 列的字节范围；缺少该列时为空范围 | Byte range of column `col` within the line; empty when the column is missing
+fn column_range(line: &str, delimiter: char, col: usize) -> Range<usize> {
+    let mut start = 0;
+    for (i, value) in line.split(delimiter).enumerate() {
+        if i == col {
+            return start..start + value.len();
+        }
+        start += value.len() + delimiter.len_utf8();
+    }
+    0..0
+}
+
+impl AsyncLineCache {
+    /// 创建 `filename` 第 `col` 列（从 0 开始，以 `delimiter` 分隔）的列投影视图，见 [`ColumnView`]
+    /// Create a projection of column `col` (0-based, separated by `delimiter`) of `filename`, see [`ColumnView`]
+    pub fn column_view(&self, filename: &str, delimiter: char, col: usize) -> ColumnView {
+        ColumnView { cache: self.clone(), source: filename.to_string(), delimiter, col }
+    }
+}
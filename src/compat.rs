@@ -0,0 +1,65 @@
+//! 与 Python `linecache` 模块同名同义的自由函数，背后是进程级的全局缓存，便于逐行迁移 Python 代码
+//! Free functions named and behaving like those of Python's `linecache` module, backed by one process-global cache, for line-by-line ports of Python code
+//!
+//! - 与 Python 相同，读不到文件时不报错：`getline` 返回空字符串，`getlines` 返回空列表
+//! - 返回的每一行都以 `\n` 结尾（包括文件中没有换行符的最后一行），不包含末尾换行符之后的兼容空行
+//! - 全局缓存在首次调用时按 [`AsyncLineCache::new`] 的默认设置创建；需要不同设置时直接使用 [`AsyncLineCache`]
+//!
+//! - Like Python, unreadable files are not errors: `getline` returns an empty string and `getlines` an empty list
+//! - Every returned line ends with `\n` (including a last line the file leaves unterminated), without the compatibility empty line after a final newline
+//! - The global cache is created on first use with the defaults of [`AsyncLineCache::new`]; use [`AsyncLineCache`] directly for other settings
+
+use crate::{AsyncLineCache, SourceLoader};
+use std::sync::{Arc, LazyLock};
+
+static CACHE: LazyLock<AsyncLineCache> = LazyLock::new(AsyncLineCache::new);
+
+/// 这些函数背后的全局缓存 | The global cache behind these functions
+pub fn global() -> &'static AsyncLineCache {
+    &CACHE
+}
+
+/// 第 `lineno` 行（从 1 开始，带 `\n`）；行号超出范围或文件无法读取时返回空字符串
+/// Line `lineno` (1-based, with its `\n`); an empty string when out of range or when the file cannot be read
+pub async fn getline(filename: &str, lineno: usize) -> String {
+    let Ok(lines) = CACHE.fresh_lines(filename).await else { return String::new() };
+    lineno
+        .checked_sub(1)
+        .filter(|&idx| idx < lines.content_len())
+        .and_then(|idx| lines.get(idx))
+        .map(|line| format!("{line}\n"))
+        .unwrap_or_default()
+}
+
+/// 文件的全部行（每行带 `\n`）；文件无法读取时返回空列表
+/// All lines of the file, each with its `\n`; an empty list when the file cannot be read
+pub async fn getlines(filename: &str) -> Vec<String> {
+    match CACHE.fresh_lines(filename).await {
+        Ok(lines) => lines.iter().take(lines.content_len()).map(|line| format!("{line}\n")).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 丢弃已过期的条目：给出文件名时只检查该文件，`None` 表示全部文件，见 [`AsyncLineCache::checkcache`]
+/// Drop stale entries: only that file when a filename is given, every file for `None`, see [`AsyncLineCache::checkcache`]
+pub async fn checkcache(filename: Option<&str>) {
+    CACHE.checkcache(filename).await;
+}
+
+/// 清空全局缓存 | Clear the global cache
+pub async fn clearcache() {
+    CACHE.clear().await;
+}
+
+/// 为磁盘上不存在的文件登记加载器，首次访问时才调用它取得源码；返回是否登记成功
+/// Register a loader for a file that is not on disk, called for the source only on first access; returns whether it was registered
+///
+/// 与 Python 相同，文件名为空、形如 `<...>`，或文件已在缓存中时不登记并返回 `false`。
+/// Like Python, nothing is registered and `false` is returned when the filename is empty, looks like `<...>`, or the file is already cached.
+pub fn lazycache(filename: &str, loader: Arc<dyn SourceLoader>) -> bool {
+    if filename.is_empty() || (filename.starts_with('<') && filename.ends_with('>')) || CACHE.entries.contains_key(filename) {
+        return false;
+    }
+    CACHE.register_loader(filename, loader);
+    true
+}
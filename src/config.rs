@@ -0,0 +1,171 @@
+//! 运行时配置热更新：在不重建缓存、不丢失已缓存数据的前提下调整参数
+//! Runtime configuration hot-reload: adjust settings without rebuilding the cache or losing cached data
+
+use crate::{AsyncLineCache, LineLengthPolicy};
+use std::time::Duration;
+
+/// 一组可以应用到运行中缓存的配置项；未设置的项保持原值
+/// A set of settings that can be applied to a live cache; settings left unset keep their current value
+///
+/// 总内存预算与权重方式由底层缓存在构造时固定，不能热更新。
+/// The total memory budget and the weighing strategy are fixed by the underlying cache at construction and cannot be hot-reloaded.
+#[derive(Debug, Clone, Default)]
+pub struct CacheConfig {
+    stat_ttl: Option<Duration>,
+    load_memory_limit: Option<u64>,
+    quotas: Vec<(String, u64)>,
+    watermarks: Option<(u64, u64)>,
+    max_entry_bytes: Option<u64>,
+    max_line_length: Option<(usize, LineLengthPolicy)>,
+    sanitize: Option<bool>,
+    revalidate: Option<bool>,
+}
+
+impl CacheConfig {
+    /// 创建空配置（应用后不改变任何设置）
+    /// Create an empty config (applying it changes nothing)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// stat 结果微缓存的 TTL，即重新校验文件变更的间隔；`Duration::ZERO` 关闭微缓存
+    /// TTL of the stat-result micro-cache, i.e. the revalidation interval; `Duration::ZERO` disables it
+    #[must_use]
+    pub fn stat_ttl(mut self, ttl: Duration) -> Self {
+        self.stat_ttl = Some(ttl);
+        self
+    }
+
+    /// 同时加载中的文件总字节数上限 | Cap on the total bytes of files being loaded at once
+    #[must_use]
+    pub fn load_memory_limit(mut self, bytes: u64) -> Self {
+        self.load_memory_limit = Some(bytes);
+        self
+    }
+
+    /// 设置（或替换）某个前缀的字节配额 | Set (or replace) the byte quota of a prefix
+    #[must_use]
+    pub fn quota(mut self, prefix: &str, bytes: u64) -> Self {
+        self.quotas.push((prefix.to_string(), bytes));
+        self
+    }
+
+    /// 软/硬内存水位线（字节），见 [`AsyncLineCache::with_watermarks`]
+    /// Soft and hard memory watermarks in bytes, see [`AsyncLineCache::with_watermarks`]
+    #[must_use]
+    pub fn watermarks(mut self, soft: u64, hard: u64) -> Self {
+        self.watermarks = Some((soft, hard));
+        self
+    }
+
+    /// 单个条目的内容上限（字节），见 [`AsyncLineCache::with_max_entry_bytes`]
+    /// Content limit of a single entry in bytes, see [`AsyncLineCache::with_max_entry_bytes`]
+    #[must_use]
+    pub fn max_entry_bytes(mut self, bytes: u64) -> Self {
+        self.max_entry_bytes = Some(bytes);
+        self
+    }
+
+    /// 单行最大字节数及超长行的处理方式，见 [`AsyncLineCache::with_max_line_length`]
+    /// Maximum line length in bytes and the policy for longer lines, see [`AsyncLineCache::with_max_line_length`]
+    #[must_use]
+    pub fn max_line_length(mut self, max: usize, policy: LineLengthPolicy) -> Self {
+        self.max_line_length = Some((max, policy));
+        self
+    }
+
+    /// 是否对已缓存的文件做变更检测，见 [`AsyncLineCache::with_revalidation`]
+    /// Whether cached files are checked for changes, see [`AsyncLineCache::with_revalidation`]
+    #[must_use]
+    pub fn revalidate(mut self, enabled: bool) -> Self {
+        self.revalidate = Some(enabled);
+        self
+    }
+
+    /// 是否启用加载时清理，见 [`AsyncLineCache::with_sanitize`]
+    /// Whether load-time sanitizing is enabled, see [`AsyncLineCache::with_sanitize`]
+    #[must_use]
+    pub fn sanitize(mut self, enabled: bool) -> Self {
+        self.sanitize = Some(enabled);
+        self
+    }
+}
+
+impl AsyncLineCache {
+    /// 把配置应用到运行中的缓存，所有共享该缓存的克隆立即生效，已缓存的数据保持不变
+    /// Apply a config to the live cache; every clone sharing it sees the change at once, and cached data is kept
+    ///
+    /// - 新的 stat TTL 立即作用于所有已记录的校验结果
+    /// - 新的加载内存上限只约束之后开始的加载，正在进行的加载按原上限完成
+    ///
+    /// - A new stat TTL applies at once to every recorded check
+    /// - A new load memory limit only governs loads that start afterwards; in-flight loads finish under the old one
+    pub fn apply_config(&self, config: &CacheConfig) {
+        if let Some(ttl) = config.stat_ttl {
+            self.recent_stats.set_ttl(ttl);
+        }
+        if let Some(enabled) = config.revalidate {
+            self.recent_stats.set_revalidate(enabled);
+        }
+        if let Some(bytes) = config.load_memory_limit {
+            self.load_gate.set_limit(bytes);
+        }
+        if let Some((soft, hard)) = config.watermarks {
+            self.watermarks.set(soft, hard);
+        }
+        if let Some(bytes) = config.max_entry_bytes {
+            self.watermarks.set_max_entry(bytes);
+        }
+        if let Some((max, policy)) = config.max_line_length {
+            self.transforms.set_max_line(max, policy);
+        }
+        if let Some(enabled) = config.sanitize {
+            self.transforms.set_sanitize(enabled);
+        }
+        for (prefix, bytes) in &config.quotas {
+            self.set_quota(prefix, *bytes);
+        }
+    }
+}
+
+impl AsyncLineCache {
+    /// 检查配置中自相矛盾或会让缓存失去作用的设置，返回 [`std::io::ErrorKind::InvalidInput`] 错误并列出全部问题
+    /// Check a config for contradictory settings or ones that would make the cache useless, returning an
+    /// [`std::io::ErrorKind::InvalidInput`] error listing every problem
+    ///
+    /// 适合在部署启动时、调用 [`AsyncLineCache::apply_config`] 之前使用，尽早发现配置错误。
+    /// Meant for deployment startup, before [`AsyncLineCache::apply_config`], so misconfiguration fails fast.
+    pub fn validate_config(config: &CacheConfig) -> std::io::Result<()> {
+        let mut problems = Vec::new();
+        if let Some(bytes) = config.load_memory_limit.filter(|&bytes| bytes < crate::gate::UNIT) {
+            problems.push(format!(
+                "load_memory_limit is {bytes} bytes, below the {} byte minimum it would be silently raised to",
+                crate::gate::UNIT
+            ));
+        }
+        if let Some((soft, hard)) = config.watermarks {
+            if hard == 0 {
+                problems.push("hard watermark is 0, so nothing can ever be cached".to_string());
+            }
+            if soft > hard {
+                problems.push(format!("soft watermark {soft} is above hard watermark {hard}"));
+            }
+        }
+        if config.max_entry_bytes == Some(0) {
+            problems.push("max_entry_bytes is 0, so nothing can ever be cached".to_string());
+        }
+        if let Some((0, _)) = config.max_line_length {
+            problems.push("max_line_length is 0, so every non-empty line is over the limit".to_string());
+        }
+        for (index, (prefix, _)) in config.quotas.iter().enumerate() {
+            if config.quotas[..index].iter().any(|(earlier, _)| earlier == prefix) {
+                problems.push(format!("quota for prefix {prefix:?} is set more than once"));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, problems.join("; ")))
+        }
+    }
+}
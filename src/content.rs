@@ -0,0 +1,48 @@
+//! 按内容哈希寻址：从文件内容的哈希找到当前缓存着这份内容的路径
+//! Content-addressed lookup: from the hash of a file's content to the path currently caching that content
+
+use crate::exclude::hash_line;
+use crate::{AsyncLineCache, FileEntry};
+use moka::future::Cache;
+
+/// 最多记录的内容哈希数 | Maximum number of content hashes remembered
+const CAPACITY: u64 = 65536;
+
+/// 内容哈希 → 最近一次以该内容写入缓存的路径 | Content hash → the path most recently cached with that content
+pub(crate) fn by_content_cache(capacity: Option<u64>) -> Cache<u64, String> {
+    Cache::new(capacity.unwrap_or(CAPACITY))
+}
+
+/// 条目内容的哈希（首次访问时计算）| Hash of the entry's content, computed on first access
+pub(crate) fn entry_content_hash(entry: &FileEntry) -> u64 {
+    *entry.derived().content_hash.get_or_init(|| hash_line(entry.raw()))
+}
+
+impl AsyncLineCache {
+    /// 文件当前内容的 64 位哈希，可交给 [`get_by_content_hash`](Self::get_by_content_hash) 反查；文件不存在时返回 `None`
+    /// The 64-bit hash of the file's current content, to be resolved by [`get_by_content_hash`](Self::get_by_content_hash); `None` for a missing file
+    ///
+    /// 哈希只在本进程内稳定，不应持久化。| The hash is only stable within this process and should not be persisted.
+    pub async fn content_hash(&self, filename: &str) -> std::io::Result<Option<u64>> {
+        Ok(self.fresh_entry(filename).await?.map(|entry| entry_content_hash(&entry)))
+    }
+
+    /// 当前缓存着哈希为 `hash` 的内容的路径；多个路径内容相同时返回最近写入的一个
+    /// The path currently caching content with hash `hash`; when several paths hold the same content, the most recently inserted one
+    ///
+    /// - 每次写入缓存时登记内容哈希，查找时确认该路径的条目仍然有效且内容未变，否则返回 `None`
+    /// - 只查找已缓存的内容，从不加载文件；条目被驱逐后无法再解析
+    ///
+    /// - The content hash is registered on every insert, and a lookup confirms the path's entry is still valid and unchanged, returning `None` otherwise
+    /// - Only cached content is searched and no file is ever loaded; an evicted entry can no longer be resolved
+    pub async fn get_by_content_hash(&self, hash: u64) -> std::io::Result<Option<String>> {
+        let Some(path) = self.by_content.get(&hash).await else { return Ok(None) };
+        match self.cached_fresh_entry(&path).await? {
+            Some(entry) if entry_content_hash(&entry) == hash => Ok(Some(path)),
+            _ => {
+                self.by_content.remove(&hash).await;
+                Ok(None)
+            }
+        }
+    }
+}
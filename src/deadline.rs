@@ -0,0 +1,33 @@
+//! 按调用方的截止时间放弃读操作
+//! Abandoning reads at the caller's deadline
+
+use crate::AsyncLineCache;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+impl AsyncLineCache {
+    /// 在 `deadline` 之前完成 `read`（本缓存的任意读操作），超时则放弃它并返回 [`std::io::ErrorKind::TimedOut`] 错误
+    /// Finish `read` (any read operation of this cache) before `deadline`, or abandon it with a [`std::io::ErrorKind::TimedOut`] error
+    ///
+    /// - 加载路径是取消安全的：超时时未完成的磁盘读取在当前数据块（至多约 2MiB）读完后停止，预留的加载额度立即归还，缓存状态不受影响
+    /// - 正在等待同一文件加载锁的其他调用不受影响，会自行完成加载
+    ///
+    /// - The load path is cancellation-safe: at the deadline an unfinished disk read stops after its current chunk (about 2MiB at most), the reserved load room is returned at once, and the cache is left untouched
+    /// - Other calls waiting on the same file's load lock are unaffected and finish the load themselves
+    pub async fn within_deadline<T>(
+        deadline: Instant,
+        read: impl Future<Output = std::io::Result<T>>,
+    ) -> std::io::Result<T> {
+        match tokio::time::timeout_at(deadline, read).await {
+            Ok(result) => result,
+            Err(elapsed) => Err(std::io::Error::new(std::io::ErrorKind::TimedOut, elapsed)),
+        }
+    }
+
+    /// 同 `get_line`，但整个调用（包括等待加载锁与加载）超过 `timeout` 时返回 [`std::io::ErrorKind::TimedOut`] 错误
+    /// Same as `get_line`, but fails with [`std::io::ErrorKind::TimedOut`] if the whole call (waiting for the load lock and loading included) takes longer than `timeout`
+    pub async fn get_line_timeout(&self, filename: &str, lineno: usize, timeout: Duration) -> std::io::Result<Option<String>> {
+        Self::within_deadline(Instant::now() + timeout, self.get_line(filename, lineno)).await
+    }
+}
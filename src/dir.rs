@@ -0,0 +1,122 @@
+//! 目录列表缓存：按目录 mtime 校验，与文件条目共用加载锁、stat 微缓存与失效入口
+//! Directory listing cache: validated by the directory's mtime, sharing the load locks, stat micro-cache and invalidation entry points with file entries
+
+use crate::AsyncLineCache;
+use moka::future::Cache;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// 最多缓存的目录数 | Maximum number of directories cached
+const CAPACITY: u64 = 1024;
+
+/// 目录中的一项 | One item of a directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntryInfo {
+    name: String,
+    size: u64,
+    mtime: Option<SystemTime>,
+    is_dir: bool,
+}
+
+impl DirEntryInfo {
+    /// 文件名（不含目录部分）| File name, without the directory part
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 字节数 | Size in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// 最后修改时间（平台不支持时为 `None`）| Last modification time (`None` where unsupported)
+    pub fn mtime(&self) -> Option<SystemTime> {
+        self.mtime
+    }
+
+    /// 是否为子目录 | Whether it is a subdirectory
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+}
+
+/// 一次目录列表及列出时目录本身的 mtime
+/// One directory listing, with the directory's own mtime at listing time
+#[derive(Debug, Clone)]
+pub(crate) struct Listing {
+    mtime: SystemTime,
+    items: Arc<[DirEntryInfo]>,
+}
+
+pub(crate) fn listings_cache(capacity: Option<u64>) -> Cache<String, Listing> {
+    Cache::new(capacity.unwrap_or(CAPACITY))
+}
+
+impl AsyncLineCache {
+    /// 列出目录中的各项（名称、大小、mtime），按名称排序并缓存
+    /// List the items of a directory (names, sizes, mtimes), sorted by name and cached
+    ///
+    /// - 目录的 mtime 变化（增删、重命名文件）时重新列出；启用 stat 微缓存时同样在 TTL 内跳过校验
+    /// - 修改已有文件的内容不会改变目录 mtime，列表中的大小与 mtime 可能滞后，需要时用 [`invalidate`](Self::invalidate) 刷新
+    /// - 符号链接按其指向的目标列出，失效的链接被跳过
+    ///
+    /// - The directory is listed again when its mtime changes (files added, removed or renamed); with the stat micro-cache enabled, checks are skipped within the TTL as well
+    /// - Editing an existing file does not change the directory's mtime, so listed sizes and mtimes may lag; refresh with [`invalidate`](Self::invalidate) when needed
+    /// - Symlinks are listed as their targets, and dangling links are skipped
+    pub async fn list_dir(&self, path: &str) -> std::io::Result<Arc<[DirEntryInfo]>> {
+        if let Some(items) = self.cached_listing(path).await? {
+            return Ok(items);
+        }
+        let _guard = self.locks.lock(path).await;
+        if let Some(items) = self.cached_listing(path).await? {
+            return Ok(items);
+        }
+        let Some(resolved) = self.confine(path).await? else {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("{path} does not exist")));
+        };
+        let listing = read_listing(&resolved).await?;
+        let items = Arc::clone(&listing.items);
+        self.dirs.insert(path.to_string(), listing).await;
+        self.mark_stat_checked(path).await;
+        Ok(items)
+    }
+
+    /// 已缓存且目录未变更的列表 | The cached listing, if the directory is unchanged
+    async fn cached_listing(&self, path: &str) -> std::io::Result<Option<Arc<[DirEntryInfo]>>> {
+        let Some(listing) = self.dirs.get(path).await else { return Ok(None) };
+        if self.recent_stats.is_recent(path).await {
+            return Ok(Some(listing.items));
+        }
+        match tokio::fs::metadata(self.resolve(path)).await {
+            Ok(meta) if meta.modified()? == listing.mtime => {
+                self.mark_stat_checked(path).await;
+                Ok(Some(listing.items))
+            }
+            Ok(_) => Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.dirs.remove(path).await;
+                Err(e)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// 读取目录；目录 mtime 在列出之前取得，列出期间的变更会在下次访问时被发现
+/// Read a directory; its mtime is taken before listing, so changes made meanwhile are caught on the next access
+async fn read_listing(path: &std::path::Path) -> std::io::Result<Listing> {
+    let mtime = tokio::fs::metadata(path).await?.modified()?;
+    let mut dir = tokio::fs::read_dir(path).await?;
+    let mut items = Vec::new();
+    while let Some(item) = dir.next_entry().await? {
+        let Ok(meta) = tokio::fs::metadata(item.path()).await else { continue };
+        items.push(DirEntryInfo {
+            name: item.file_name().to_string_lossy().into_owned(),
+            size: meta.len(),
+            mtime: meta.modified().ok(),
+            is_dir: meta.is_dir(),
+        });
+    }
+    items.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    Ok(Listing { mtime, items: items.into() })
+}
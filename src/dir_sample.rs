@@ -0,0 +1,94 @@
+//! 在目录下的所有行中均匀随机抽取：按每个文件的行数加权选中文件，文件在被选中时才加载
+//! Uniform sampling over every line under a directory: files are picked by their line counts and only loaded once picked
+
+use crate::{AsyncLineCache, DirEntryInfo};
+use moka::future::Cache;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::SystemTime;
+
+/// 最多记忆的文件行数 | Maximum number of line counts remembered
+const CAPACITY: u64 = 65536;
+
+/// 文件在某个大小与 mtime 下的行数；条目被驱逐后仍然保留，用于加权
+/// A file's line count at a given size and mtime; kept after its entry is evicted, for weighting
+#[derive(Debug, Clone)]
+pub(crate) struct LineCount {
+    size: u64,
+    mtime: Option<SystemTime>,
+    lines: usize,
+}
+
+pub(crate) fn line_counts_cache(capacity: Option<u64>) -> Cache<String, LineCount> {
+    Cache::new(capacity.unwrap_or(CAPACITY))
+}
+
+impl AsyncLineCache {
+    /// 从目录下所有文件的所有行中均匀随机返回一行及其所在文件路径（`recursive` 为 `true` 时包括子目录）
+    /// Return a line drawn uniformly from every line of every file under `dir`, with the path of its file (including subdirectories when `recursive`)
+    ///
+    /// - 按每个文件的行数加权选中文件，只加载被选中的文件；行数在文件加载后记住，条目被驱逐后仍然可用
+    /// - 尚未加载过的文件按其大小与已知文件的平均行长估算行数，因此所有文件都被加载过之后抽样才严格均匀
+    /// - 跳过已[隔离](Self::quarantined)的文件；目录中没有任何非空文件时返回 `None`
+    ///
+    /// - Files are picked weighted by their line counts and only the picked file is loaded; counts are remembered once a file loads and outlive its entry
+    /// - Files never loaded are estimated from their size and the average line length of known files, so sampling is exactly uniform once every file has been loaded
+    /// - [Quarantined](Self::quarantined) files are skipped; returns `None` when the directory holds no non-empty file
+    #[allow(clippy::cast_precision_loss)]
+    pub async fn random_line_in_dir(&self, dir: &str, recursive: bool) -> std::io::Result<Option<(String, String)>> {
+        let mut files = self.walk_files(dir, recursive).await?;
+        files.retain(|(path, _)| !self.quarantine.contains(path));
+        let mut counts = Vec::with_capacity(files.len());
+        for (path, item) in &files {
+            counts.push(self.known_line_count(path, item).await);
+        }
+        let (known_bytes, known_lines) = files
+            .iter()
+            .zip(&counts)
+            .filter_map(|((_, item), count)| count.map(|lines| (item.size(), lines)))
+            .fold((0, 0), |(bytes, lines), (b, l)| (bytes + b, lines + l));
+        let bytes_per_line = if known_lines == 0 { 1.0 } else { (known_bytes as f64 / known_lines as f64).max(1.0) };
+        let mut weights: Vec<f64> = files
+            .iter()
+            .zip(&counts)
+            .map(|((_, item), count)| count.map_or(item.size() as f64 / bytes_per_line, |lines| lines as f64))
+            .collect();
+
+        let mut rng = StdRng::from_entropy();
+        loop {
+            let Ok(dist) = WeightedIndex::new(&weights) else { return Ok(None) };
+            let idx = dist.sample(&mut rng);
+            let (path, item) = &files[idx];
+            let lines = match self.fresh_lines(path).await {
+                Ok(lines) => lines,
+                // 刚被隔离的文件从候选中去掉，其余错误照常返回 | a file quarantined just now is dropped, other errors are returned
+                Err(_) if self.quarantine.contains(path) => {
+                    files.swap_remove(idx);
+                    weights.swap_remove(idx);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let count = LineCount { size: item.size(), mtime: item.mtime(), lines: lines.len() };
+            self.line_counts.insert(path.clone(), count).await;
+            if lines.is_empty() {
+                // 空文件或已被删除：从候选中去掉后重抽 | empty or deleted since listing: drop it and draw again
+                files.swap_remove(idx);
+                weights.swap_remove(idx);
+                continue;
+            }
+            let line = lines.get(rng.gen_range(0..lines.len())).unwrap_or_default().to_string();
+            return Ok(Some((files.swap_remove(idx).0, line)));
+        }
+    }
+
+    /// 已知的行数：记忆的行数（大小与 mtime 仍与目录项一致）或已缓存条目的行数
+    /// The known line count: a remembered one still matching the item's size and mtime, or that of the cached entry
+    async fn known_line_count(&self, path: &str, item: &DirEntryInfo) -> Option<usize> {
+        match self.line_counts.get(path).await {
+            Some(count) if count.size == item.size() && count.mtime == item.mtime() => Some(count.lines),
+            _ => self.entries.get(path).await.map(|entry| entry.lines().len()),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! 递归发现语料文件：按扩展名、文件名通配与大小过滤，可选地在后台预加载
+//! Recursive corpus discovery: filtered by extension, file-name glob and size, optionally preloaded in the background
+
+use crate::{AsyncLineCache, DirEntryInfo};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// [`discover`](AsyncLineCache::discover) 的过滤条件；未设置的条件不做限制
+/// Filters for [`discover`](AsyncLineCache::discover); conditions left unset match everything
+#[derive(Debug, Clone, Default)]
+pub struct DiscoverFilter {
+    extensions: Vec<String>,
+    pattern: Option<String>,
+    min_size: u64,
+    max_size: Option<u64>,
+    preload: bool,
+}
+
+impl DiscoverFilter {
+    /// 创建不做任何过滤的条件 | Create a filter that matches every file
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 允许的扩展名（如 `txt` 或 `.txt`，不区分大小写），可多次调用
+    /// An allowed extension (e.g. `txt` or `.txt`, case-insensitive); may be called repeatedly
+    #[must_use]
+    pub fn extension(mut self, ext: &str) -> Self {
+        self.extensions.push(ext.trim_start_matches('.').to_string());
+        self
+    }
+
+    /// 文件名通配（`*` 匹配任意个字符，`?` 匹配一个字符），只匹配文件名部分
+    /// File-name glob (`*` matches any run of characters, `?` exactly one), matched against the file name only
+    #[must_use]
+    pub fn pattern(mut self, glob: &str) -> Self {
+        self.pattern = Some(glob.to_string());
+        self
+    }
+
+    /// 文件至少 `bytes` 字节 | Files of at least `bytes` bytes
+    #[must_use]
+    pub fn min_size(mut self, bytes: u64) -> Self {
+        self.min_size = bytes;
+        self
+    }
+
+    /// 文件至多 `bytes` 字节 | Files of at most `bytes` bytes
+    #[must_use]
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// 发现后在后台按顺序预加载所有匹配的文件 | Preload every match in order in the background after discovery
+    #[must_use]
+    pub fn preload(mut self, preload: bool) -> Self {
+        self.preload = preload;
+        self
+    }
+
+    fn matches(&self, name: &str, size: u64) -> bool {
+        let ext = Path::new(name).extension().and_then(|ext| ext.to_str()).unwrap_or("");
+        (self.extensions.is_empty() || self.extensions.iter().any(|allowed| allowed.eq_ignore_ascii_case(ext)))
+            && self.pattern.as_deref().is_none_or(|glob| glob_match(glob, name))
+            && size >= self.min_size
+            && self.max_size.is_none_or(|max| size <= max)
+    }
+}
+
+impl AsyncLineCache {
+    /// 递归遍历 `root`，返回所有通过 `filter` 的文件路径（按路径排序）
+    /// Walk `root` recursively and return the path of every file passing `filter`, sorted
+    ///
+    /// - 目录通过 [`list_dir`](Self::list_dir) 列出，重复发现同一棵树时复用目录列表缓存
+    /// - 跟随符号链接，但每个目录只访问一次，链接成环也不会死循环
+    /// - 启用预加载时，文件在后台逐个载入缓存，调用立即返回；[`shutdown`](Self::shutdown) 会停止未完成的预加载
+    ///
+    /// - Directories are listed through [`list_dir`](Self::list_dir), so discovering the same tree again reuses the listing cache
+    /// - Symlinks are followed, but every directory is visited once, so link cycles cannot loop forever
+    /// - With preloading enabled, files are loaded into the cache one by one in the background and the call returns at once; [`shutdown`](Self::shutdown) stops unfinished preloading
+    pub async fn discover(&self, root: &str, filter: &DiscoverFilter) -> std::io::Result<Vec<String>> {
+        let mut found: Vec<String> = self
+            .walk_files(root, true)
+            .await?
+            .into_iter()
+            .filter(|(_, item)| filter.matches(item.name(), item.size()))
+            .map(|(path, _)| path)
+            .collect();
+        found.sort_unstable();
+        if filter.preload {
+            let cache = self.clone();
+            let queue = found.clone();
+            self.tasks.spawn(async move {
+                for path in queue {
+                    if cache.quarantine.contains(&path) {
+                        continue;
+                    }
+                    // 预加载尽力而为：单个文件失败不影响其余文件 | best effort: one failing file does not stop the rest
+                    let _ = cache.fresh_entry(&path).await;
+                }
+            });
+        }
+        Ok(found)
+    }
+}
+
+impl AsyncLineCache {
+    /// 列出 `root` 下的所有文件及其目录项（`recursive` 为 `false` 时只列出顶层），顺序不定
+    /// List every file under `root` with its directory item (only the top level when `recursive` is `false`), in no particular order
+    pub(crate) async fn walk_files(
+        &self,
+        root: &str,
+        recursive: bool,
+    ) -> std::io::Result<Vec<(String, DirEntryInfo)>> {
+        let mut files = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![root.to_string()];
+        while let Some(dir) = pending.pop() {
+            // 设置了根目录时按根目录解析并先做检查，根目录之外的路径不会被 stat
+            // with a root, the path resolves against it and is checked first, so paths outside are never stat'ed
+            let canonical = match self.confine(&dir).await? {
+                Some(checked) if self.root.is_some() => checked,
+                Some(path) => tokio::fs::canonicalize(path).await?,
+                None if dir == root => {
+                    return Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("{root} does not exist")));
+                }
+                // 遍历期间被删除的子目录 | a subdirectory removed during the walk
+                None => continue,
+            };
+            if !visited.insert(canonical) {
+                continue;
+            }
+            for item in self.list_dir(&dir).await?.iter() {
+                let path = Path::new(&dir).join(item.name()).to_string_lossy().into_owned();
+                if !item.is_dir() {
+                    files.push((path, item.clone()));
+                } else if recursive {
+                    pending.push(path);
+                }
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// 通配匹配：`*` 匹配任意个字符，`?` 匹配一个字符；按字节偏移原地匹配，不分配内存
+/// Glob match: `*` matches any run of characters, `?` exactly one; matches in place by byte offset, without allocating
+pub(crate) fn glob_match(glob: &str, name: &str) -> bool {
+    let (mut g, mut n) = (0, 0);
+    // 最近一个 `*` 的位置及其当前匹配到的文件名位置，失配时回溯 | last `*` and where it resumes, for backtracking
+    let mut star: Option<(usize, usize)> = None;
+    while let Some(c) = name[n..].chars().next() {
+        match glob[g..].chars().next() {
+            Some('*') => {
+                star = Some((g, n));
+                g += 1;
+            }
+            Some(p) if p == '?' || p == c => {
+                g += p.len_utf8();
+                n += c.len_utf8();
+            }
+            _ => match star {
+                Some((star_g, star_n)) => {
+                    let resume = star_n + name[star_n..].chars().next().map_or(1, char::len_utf8);
+                    star = Some((star_g, resume));
+                    g = star_g + 1;
+                    n = resume;
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].bytes().all(|b| b == b'*')
+}
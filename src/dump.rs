@@ -0,0 +1,70 @@
+//! 调试用字节转储：通过缓存查看某一行附近的原始字节、偏移量与转义后的控制字符
+//! Debugging byte dump: inspect the raw bytes, offsets and escaped control characters around a line through the cache
+
+use crate::AsyncLineCache;
+use std::fmt::Write as _;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+impl AsyncLineCache {
+    /// 转储文件第 `lineno` 行（从 1 开始）及其前后各 `radius` 行的原始字节，每行一条记录
+    /// Dump the raw bytes of line `lineno` (1-based) of the file and `radius` lines on either side, one record per line
+    ///
+    /// - 每条记录依次为：标记（`>` 表示请求的行）、行号、该行在文件中的起始字节偏移（十六进制）、含换行符在内的十六进制字节、转义了控制字符的文本（无效的 UTF-8 字节写作 `\xNN`）
+    /// - 转储的是磁盘上的字节（路径同样经过根目录检查），即解码、清理、行长度限制等加载时处理之前的内容；行按文件中的 `\n` 划分
+    /// - 只读取到所需的最后一行为止；行号超出范围、文件不存在或条目来自加载器（没有对应的文件）时返回 `None`
+    ///
+    /// - Each record holds: a marker (`>` for the requested line), the line number, the line's starting byte offset in the file (hex), its bytes in hex including the separator, and its text with control characters escaped (invalid UTF-8 bytes written as `\xNN`)
+    /// - What is dumped is the bytes on disk (the path passes the root-directory check as well), i.e. the content before decoding, sanitizing, line-length limits and the other load-time processing; lines are split at the file's `\n`
+    /// - Reading stops at the last line needed; returns `None` when the line number is out of range, the file is missing, or the entry comes from a loader (with no file behind it)
+    pub async fn dump_region(&self, filename: &str, lineno: usize, radius: usize) -> std::io::Result<Option<String>> {
+        let Some(target) = lineno.checked_sub(1) else { return Ok(None) };
+        let Some(path) = self.confine(filename).await? else { return Ok(None) };
+        let file = match tokio::fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let mut reader = BufReader::new(file);
+        let (first, last) = (target.saturating_sub(radius), target.saturating_add(radius));
+        let (mut idx, mut offset, mut raw, mut out) = (0, 0u64, Vec::new(), String::new());
+        while idx <= last {
+            raw.clear();
+            if reader.read_until(b'\n', &mut raw).await? == 0 {
+                break;
+            }
+            if idx >= first {
+                let marker = if idx == target { '>' } else { ' ' };
+                let _ = write!(out, "{marker} {:>6}  {offset:#010x} ", idx + 1);
+                for byte in &raw {
+                    let _ = write!(out, " {byte:02x}");
+                }
+                out.push_str("  ");
+                escape_into(&mut out, &raw);
+                out.push('\n');
+            }
+            offset += raw.len() as u64;
+            idx += 1;
+        }
+        Ok((idx > target).then_some(out))
+    }
+}
+
+/// 以带引号的形式写出字节：有效的 UTF-8 按 `str` 的 `Debug` 规则转义，无效的字节写作 `\xNN`
+/// Write the bytes quoted: valid UTF-8 escaped as by `str`'s `Debug`, invalid bytes written as `\xNN`
+fn escape_into(out: &mut String, raw: &[u8]) {
+    out.push('"');
+    for chunk in raw.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            // `str` 的 `Debug` 不转义单引号 | `str`'s `Debug` leaves single quotes alone
+            if c == '\'' {
+                out.push(c);
+            } else {
+                let _ = write!(out, "{}", c.escape_debug());
+            }
+        }
+        for byte in chunk.invalid() {
+            let _ = write!(out, "\\x{byte:02x}");
+        }
+    }
+    out.push('"');
+}
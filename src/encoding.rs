@@ -0,0 +1,113 @@
+//! PEP 263 编码声明检测：按 `# -*- coding: … -*-` 解码 Python/Ruby 源文件
+//! PEP 263 coding-cookie detection: decode Python/Ruby source files by their `# -*- coding: … -*-` declaration
+
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+/// 按文件设置强制使用的编码 | The encoding forced by per-file options
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Forced {
+    /// 已识别的编码 | A recognized encoding
+    Known(&'static encoding_rs::Encoding),
+    /// 无法识别的标签 | An unrecognized label
+    Unknown,
+}
+
+impl Forced {
+    /// 按 WHATWG 规则识别编码标签 | Recognize an encoding label by WHATWG rules
+    pub(crate) fn from_label(label: &str) -> Self {
+        encoding_rs::Encoding::for_label(label.trim().as_bytes()).map_or(Self::Unknown, Self::Known)
+    }
+}
+
+/// 会检查编码声明的源文件扩展名
+/// Source file extensions whose coding cookie is honoured
+const SOURCE_EXTENSIONS: &[&str] = &["py", "pyw", "pyi", "rb"];
+
+/// 将文件字节解码为文本：源文件按编码声明解码，其余文件（或没有声明时）按 UTF-8 严格解码
+/// Decode file bytes into text: source files follow their coding cookie, everything else (or no cookie) is strict UTF-8
+///
+/// `forced` 来自按文件设置的 [`FileOptions::encoding`](crate::FileOptions::encoding)，优先于编码声明。
+/// `forced` comes from the per-file [`FileOptions::encoding`](crate::FileOptions::encoding) and wins over any cookie.
+pub(crate) fn decode_source(
+    filename: &str,
+    bytes: Vec<u8>,
+    forced: Option<Forced>,
+) -> std::io::Result<String> {
+    match forced {
+        Some(Forced::Known(encoding)) => return Ok(encoding.decode(&bytes).0.into_owned()),
+        Some(Forced::Unknown) => return Err(Error::new(ErrorKind::InvalidData, "unknown encoding in file options")),
+        None => {}
+    }
+    let is_source = Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext));
+
+    if is_source {
+        if let Some(label) = coding_cookie(&bytes) {
+            let encoding = encoding_rs::Encoding::for_label(normalize_label(label).as_bytes()).ok_or_else(|| {
+                Error::new(ErrorKind::InvalidData, format!("unknown encoding in coding cookie: {label}"))
+            })?;
+            if encoding != encoding_rs::UTF_8 {
+                let (text, _, _) = encoding.decode(&bytes);
+                return Ok(text.into_owned());
+            }
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// 将 Python 风格的编码名（如 `latin-1`、`utf_8`）转换为 WHATWG 标签
+/// Map Python-style codec names (e.g. `latin-1`, `utf_8`) onto WHATWG labels
+///
+/// 注意 WHATWG 把 latin-1 视为 windows-1252，二者仅在 0x80~0x9F 区间不同。
+/// Note that WHATWG treats latin-1 as windows-1252; the two only differ in 0x80~0x9F.
+fn normalize_label(label: &str) -> String {
+    let label = label.to_ascii_lowercase().replace('_', "-");
+    match label.as_str() {
+        "latin-1" | "iso-latin-1" | "latin" | "l1" => "latin1".to_string(),
+        "utf-8-sig" | "utf" => "utf-8".to_string(),
+        _ => label,
+    }
+}
+
+/// 在前两行中查找编码声明（规则同 `CPython`：第一行必须是注释或空行才会检查第二行）
+/// Find the coding cookie on the first two lines (as in `CPython`: line 2 is checked only if line 1 is a comment or blank)
+fn coding_cookie(bytes: &[u8]) -> Option<&str> {
+    for line in bytes.split(|&b| b == b'\n').take(2) {
+        let line = line.trim_ascii_start();
+        if line.first() != Some(&b'#') {
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            return None;
+        }
+        if let Some(label) = cookie_in_comment(line) {
+            return Some(label);
+        }
+    }
+    None
+}
+
+/// 匹配 `coding[:=]\s*([-\w.]+)`
+/// Match `coding[:=]\s*([-\w.]+)`
+fn cookie_in_comment(line: &[u8]) -> Option<&str> {
+    const NEEDLE: &[u8] = b"coding";
+    let mut rest = line;
+    while let Some(pos) = rest.windows(NEEDLE.len()).position(|w| w == NEEDLE) {
+        rest = &rest[pos + NEEDLE.len()..];
+        if let [b':' | b'=', tail @ ..] = rest {
+            let tail = tail.trim_ascii_start();
+            let len = tail
+                .iter()
+                .take_while(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+                .count();
+            if len > 0 {
+                return std::str::from_utf8(&tail[..len]).ok();
+            }
+        }
+    }
+    None
+}
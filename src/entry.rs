@@ -0,0 +1,222 @@
+//! 统一缓存条目：一个文件的行、原始内容与元数据保存在同一个条目中
+//! Unified cache entry: a file's lines, raw content and metadata live in one entry
+
+use crate::column::ColumnRanges;
+use crate::fold::Folded;
+use crate::index::InvertedIndex;
+use crate::ngram::NgramCounts;
+use crate::transform::LoadStats;
+use crate::CachedLines;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, RwLock};
+use std::time::{Duration, SystemTime};
+
+/// 下一个条目的代号；每次加载都会构建新条目，因此同一文件的代号随重新加载严格递增
+/// Generation of the next entry; every load builds a new entry, so a file's generation strictly increases with each reload
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+/// 缓存条目的来源戳，用于变更检测
+/// Origin stamp of a cache entry, used for change detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Stamp {
+    /// 来自磁盘文件：修改时间 + 大小
+    /// Loaded from a file on disk: mtime + size
+    Disk { mtime: SystemTime, size: u64 },
+    /// 来自加载器等非文件来源，永不做 mtime 校验（同 Python 中 mtime 为 `None` 的条目）
+    /// From a loader or other non-file source; never mtime-checked (like Python entries whose mtime is `None`)
+    Virtual,
+}
+
+/// 条目经由哪条写入路径得到，供审计日志区分 | Which insert path produced an entry, told apart by the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Source {
+    /// 从磁盘读取 | Read from disk
+    Disk,
+    /// 由加载器提供 | Provided by a loader
+    Loader,
+    /// 由 [`insert_virtual`](crate::AsyncLineCache::insert_virtual) 直接写入，磁盘上没有同名文件 | Written by [`insert_virtual`](crate::AsyncLineCache::insert_virtual) with no file of that name on disk
+    Virtual,
+    /// 由 [`insert_virtual`](crate::AsyncLineCache::insert_virtual) 直接写入，遮蔽了磁盘上的同名文件 | Written by [`insert_virtual`](crate::AsyncLineCache::insert_virtual), shadowing a file of that name on disk
+    Override,
+}
+
+impl Source {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Source::Disk => "disk",
+            Source::Loader => "loader",
+            Source::Virtual => "virtual",
+            Source::Override => "override",
+        }
+    }
+}
+
+/// 单个文件的缓存条目：行、原始内容与元数据总是一起写入、一起驱逐、一起失效
+/// Cache entry of a single file: lines, raw content and metadata are always inserted, evicted and invalidated together
+///
+/// 原始内容就是行缓冲区本身的文本，不会再单独保存一份。
+/// The raw content is the line buffer's own text; it is never stored a second time.
+#[derive(Debug, Clone)]
+pub struct FileEntry {
+    lines: CachedLines,
+    stamp: Stamp,
+    generation: u64,
+    loaded_at: Duration,
+    load_stats: LoadStats,
+    source: Source,
+    derived: Arc<Derived>,
+}
+
+/// 按需从条目计算并随条目一起缓存的派生数据；条目被替换时一起丢弃
+/// Data derived from an entry on demand and cached alongside it; dropped together when the entry is replaced
+///
+/// - 大多数派生数据在条目写入之后才生成，不计入条目权重
+/// - 倒排索引与大小写折叠副本只为登记的文件在写入之前构建，其大小记在 `prebuilt_bytes` 中并计入条目权重；写入之后不再补建，权重因此保持不变
+///
+/// - Most derived data is produced after the entry is inserted and does not count towards the entry's weight
+/// - The inverted index and the case-folded copy are built only for registered files, before the insert, with its size recorded in `prebuilt_bytes` and counted in the entry's weight; nothing is added after the insert, so the weight never changes
+#[derive(Debug, Default)]
+pub(crate) struct Derived {
+    /// 按 n 记忆的 n-gram 计数 | N-gram counts memoized by n
+    pub(crate) ngrams: Mutex<HashMap<usize, Arc<NgramCounts>>>,
+
+    /// 逐行哈希 | Per-line hashes
+    pub(crate) line_hashes: OnceLock<Arc<Vec<u64>>>,
+
+    /// 去重后的行哈希集合（不含兼容空行）| Distinct line hashes, without the compatibility empty line
+    pub(crate) line_set: OnceLock<Arc<HashSet<u64>>>,
+
+    /// 按视图记忆的过滤结果（匹配行的下标）| Filter results memoized by view (indices of matching lines)
+    pub(crate) filtered: Mutex<HashMap<u64, Arc<[usize]>>>,
+
+    /// 按字节序排列的行下标 | Line indices in byte order
+    pub(crate) sorted: OnceLock<Arc<[usize]>>,
+
+    /// 按 `(分隔符, 列)` 记忆的每行列范围 | Per-line column ranges memoized by `(delimiter, column)`
+    pub(crate) columns: Mutex<HashMap<(char, usize), ColumnRanges>>,
+
+    /// 倒排索引（只在写入之前构建）| Inverted index, only ever built before the insert
+    pub(crate) index: OnceLock<Arc<InvertedIndex>>,
+
+    /// 写入之前构建的派生数据估计占用的字节数 | Estimated bytes of the derived data built before the insert
+    pub(crate) prebuilt_bytes: AtomicU64,
+
+    /// 大小写折叠副本（只在写入之前构建）| Case-folded copy, only ever built before the insert
+    pub(crate) folded: OnceLock<Arc<Folded>>,
+
+    /// 内容哈希 | Content hash
+    pub(crate) content_hash: OnceLock<u64>,
+
+    /// 最近一次写入或命中的时钟时刻（纳秒）| Clock time of the last insert or hit, in nanoseconds
+    pub(crate) last_access: AtomicU64,
+
+    /// 随机接口的逐行出现次数 | Per-line counts of lines served by the random APIs
+    pub(crate) served: OnceLock<crate::served::ServedCounts>,
+
+    /// 检测到的主要文字系统 | Detected dominant script
+    #[cfg(feature = "detect-language")]
+    pub(crate) script: OnceLock<Option<crate::script::Script>>,
+}
+
+impl FileEntry {
+    pub(crate) fn new(lines: CachedLines, stamp: Stamp, loaded_at: Duration) -> Self {
+        Self {
+            lines,
+            stamp,
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
+            loaded_at,
+            load_stats: LoadStats::default(),
+            source: match stamp {
+                Stamp::Disk { .. } => Source::Disk,
+                Stamp::Virtual => Source::Loader,
+            },
+            derived: Arc::default(),
+        }
+    }
+
+    /// 解析后的行 | Parsed lines
+    pub fn lines(&self) -> &CachedLines {
+        &self.lines
+    }
+
+    /// 完整的原始文件内容（含原始换行符）
+    /// The full raw file content (with original separators)
+    pub fn raw(&self) -> &str {
+        self.lines.as_str()
+    }
+
+    /// 加载时记录的文件修改时间；来自加载器的条目返回 `None`
+    /// File modification time recorded at load; `None` for entries that came from a loader
+    pub fn mtime(&self) -> Option<SystemTime> {
+        match self.stamp {
+            Stamp::Disk { mtime, .. } => Some(mtime),
+            Stamp::Virtual => None,
+        }
+    }
+
+    /// 条目的代号：同一文件每次重新加载后严格递增（不保证连续）
+    /// The entry's generation: strictly increases each time the same file is reloaded (not necessarily by one)
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub(crate) fn with_load_stats(mut self, stats: LoadStats) -> Self {
+        self.load_stats = stats;
+        self
+    }
+
+    pub(crate) fn with_source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub(crate) fn source(&self) -> Source {
+        self.source
+    }
+
+    /// 加载时处理的统计 | Statistics of load-time processing
+    pub fn load_stats(&self) -> LoadStats {
+        self.load_stats
+    }
+
+    /// 到时钟时刻 `now` 为止条目构建以来经过的时间 | Time elapsed since the entry was built, as of clock time `now`
+    pub(crate) fn age(&self, now: Duration) -> Duration {
+        now.saturating_sub(self.loaded_at)
+    }
+
+    pub(crate) fn stamp(&self) -> Stamp {
+        self.stamp
+    }
+
+    pub(crate) fn derived(&self) -> &Derived {
+        &self.derived
+    }
+
+    /// 同一版本内容、但派生数据为空的副本，用于补建写入之前构建的派生数据
+    /// A copy of the same version with empty derived data, for adding the data built before an insert
+    pub(crate) fn rederived(&self) -> Self {
+        Self { derived: Arc::default(), ..self.clone() }
+    }
+}
+
+/// 登记为需要在每次加载时构建某种派生数据的文件，由所有克隆出的缓存实例共享
+/// Files registered for some derived data built on every load, shared by every clone of the cache
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FileSet {
+    files: Arc<RwLock<HashSet<String>>>,
+}
+
+impl FileSet {
+    pub(crate) fn contains(&self, filename: &str) -> bool {
+        self.files.read().unwrap_or_else(PoisonError::into_inner).contains(filename)
+    }
+
+    pub(crate) fn insert(&self, filename: &str) {
+        self.files.write().unwrap_or_else(PoisonError::into_inner).insert(filename.to_string());
+    }
+
+    pub(crate) fn remove(&self, filename: &str) -> bool {
+        self.files.write().unwrap_or_else(PoisonError::into_inner).remove(filename)
+    }
+}
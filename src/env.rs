@@ -0,0 +1,41 @@
+//! 从环境变量读取的默认配置，在首次构造缓存时读取一次
+//! Default settings read from environment variables, once, when the first cache is built
+//!
+//! 优先级：构造器或 [`CacheConfig`](crate::CacheConfig) 中明确设置的值 > 环境变量 > 内置默认值。
+//! 无法解析的值被忽略，如同未设置。
+//! Precedence: values set explicitly on the builder or in a [`CacheConfig`](crate::CacheConfig) > environment variables > built-in defaults.
+//! Values that fail to parse are ignored, as if unset.
+//!
+//! - `LINECACHE_MAX_BYTES`：总内存预算（字节），设置后不再查询系统内存
+//! - `LINECACHE_MEM_PERCENT`：按系统内存百分比设置预算（`0`~`100`，可带小数；需要 `sysinfo` 特性）
+//! - `LINECACHE_STAT_TTL_MS`：stat 微缓存 TTL，即重新校验文件变更的间隔（毫秒）
+//!
+//! - `LINECACHE_MAX_BYTES`: total memory budget in bytes; once set, system memory is never queried
+//! - `LINECACHE_MEM_PERCENT`: budget as a percentage of system memory (`0`~`100`, fractions allowed; requires the `sysinfo` feature)
+//! - `LINECACHE_STAT_TTL_MS`: stat micro-cache TTL, i.e. the revalidation interval, in milliseconds
+
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// 环境变量中的设置；未设置或无法解析的项为 `None`
+/// Settings found in the environment; unset or unparsable ones are `None`
+#[derive(Debug, Default)]
+pub(crate) struct EnvConfig {
+    pub(crate) max_bytes: Option<u64>,
+    #[cfg(feature = "sysinfo")]
+    pub(crate) memory_fraction: Option<f64>,
+    pub(crate) stat_ttl: Option<Duration>,
+}
+
+pub(crate) static ENV_CONFIG: LazyLock<EnvConfig> = LazyLock::new(|| EnvConfig {
+    max_bytes: parse("LINECACHE_MAX_BYTES"),
+    #[cfg(feature = "sysinfo")]
+    memory_fraction: parse::<f64>("LINECACHE_MEM_PERCENT")
+        .filter(|percent| (0.0..=100.0).contains(percent))
+        .map(|percent| percent / 100.0),
+    stat_ttl: parse("LINECACHE_STAT_TTL_MS").map(Duration::from_millis),
+});
+
+fn parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+    std::env::var(name).ok()?.trim().parse().ok()
+}
@@ -0,0 +1,51 @@
+//! 驱逐预估：按最近访问时间列出最可能被驱逐的条目
+//! Eviction preview: the entries most likely to be evicted next, by last access time
+
+use crate::clock::nanos;
+use crate::{AsyncLineCache, FileEntry};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// 记录条目在时钟时刻 `now` 刚被写入或命中 | Record that the entry was just inserted or hit at clock time `now`
+pub(crate) fn touch(entry: &FileEntry, now: Duration) {
+    entry.derived().last_access.store(nanos(now), Ordering::Relaxed);
+}
+
+impl AsyncLineCache {
+    /// 最可能被下一次驱逐选中的至多 `n` 个文件，最久未访问的在前
+    /// At most `n` files most likely to be picked by the next eviction, least recently accessed first
+    ///
+    /// - 按每个条目最近一次写入或命中的时间排序，与条目缓存按最近使用选择驱逐对象的方式一致；这是一个估计，访问频率与并发写入都可能改变实际顺序
+    /// - 可在大批量预加载之前查看哪些重要文件即将被挤出
+    ///
+    /// - Ordered by each entry's last insert or hit, matching how the entry cache picks victims by recency; it is an estimate, and access frequency or concurrent inserts may change the actual order
+    /// - Useful before a big preload to see which important files are about to be displaced
+    pub async fn eviction_candidates(&self, n: usize) -> Vec<String> {
+        self.entries.run_pending_tasks().await;
+        self.entries_by_recency().into_iter().take(n).map(|(key, _)| key).collect()
+    }
+
+    /// 全部条目，最久未访问的在前；遍历不计为访问，不影响驱逐策略
+    /// Every entry, least recently accessed first; iterating does not count as an access, leaving the eviction policy alone
+    pub(crate) fn entries_by_recency(&self) -> Vec<(String, FileEntry)> {
+        let mut entries: Vec<(u64, String, FileEntry)> = self
+            .entries
+            .iter()
+            .map(|(key, entry)| (entry.derived().last_access.load(Ordering::Relaxed), key.as_str().to_string(), entry))
+            .collect();
+        entries.sort_unstable_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+        entries.into_iter().map(|(_, key, entry)| (key, entry)).collect()
+    }
+
+    /// 在写入 `holder` 的路径上（调用方已持有其加载锁）移除 `victim`，返回被移除的条目
+    /// Remove `victim` on the insert path of `holder` (whose load lock the caller holds), returning the removed entry
+    ///
+    /// 按与其他移除路径相同的规则持有 `victim` 的加载锁；该锁正被占用（`victim` 正在加载或失效）时不等待，直接跳过它，
+    /// 因此两个同时写入的加载不会互相等待而死锁。
+    /// Holds `victim`'s load lock like every other removal path; when that lock is taken (`victim` is being loaded or
+    /// invalidated) it is skipped instead of waited for, so two concurrent inserts can never deadlock on each other.
+    pub(crate) async fn evict_for(&self, holder: &str, victim: &str) -> Option<FileEntry> {
+        let _guard = if self.locks.same_stripe(holder, victim) { None } else { Some(self.locks.try_lock(victim)?) };
+        self.remove_entry(victim).await
+    }
+}
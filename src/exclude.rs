@@ -0,0 +1,65 @@
+//! 随机抽样时需要跳过的行集合
+//! Sets of lines to skip while sampling at random
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+/// 由调用方维护的排除集合：可按行号排除，也可按行内容（哈希）排除
+/// Exclusion set maintained by the caller: lines can be excluded by number or by content (hash)
+///
+/// 按内容排除只保存 64 位哈希而不是行文本本身，因此集合大小与行长度无关。
+/// Content exclusions only keep a 64-bit hash rather than the line text, so the set size does not depend on line length.
+#[derive(Debug, Clone, Default)]
+pub struct ExclusionSet {
+    linenos: HashSet<usize>,
+    hashes: HashSet<u64>,
+}
+
+impl ExclusionSet {
+    /// 创建空集合 | Create an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 排除第 `lineno` 行（从 1 开始）| Exclude line `lineno` (1-based)
+    pub fn exclude_lineno(&mut self, lineno: usize) -> &mut Self {
+        self.linenos.insert(lineno);
+        self
+    }
+
+    /// 排除内容等于 `line` 的所有行 | Exclude every line whose content equals `line`
+    pub fn exclude_line(&mut self, line: &str) -> &mut Self {
+        self.hashes.insert(hash_line(line));
+        self
+    }
+
+    /// 第 `lineno` 行（内容为 `line`）是否被排除
+    /// Whether line `lineno` with content `line` is excluded
+    pub fn contains(&self, lineno: usize, line: &str) -> bool {
+        self.linenos.contains(&lineno) || (!self.hashes.is_empty() && self.hashes.contains(&hash_line(line)))
+    }
+
+    /// 集合中的排除项数量 | Number of exclusions in the set
+    pub fn len(&self) -> usize {
+        self.linenos.len() + self.hashes.len()
+    }
+
+    /// 集合是否为空 | Whether the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.linenos.is_empty() && self.hashes.is_empty()
+    }
+
+    /// 清空所有排除项 | Remove every exclusion
+    pub fn clear(&mut self) {
+        self.linenos.clear();
+        self.hashes.clear();
+    }
+}
+
+/// 行内容的 64 位哈希（固定种子，同一构建的多次运行之间一致）| 64-bit hash of a line's content (fixed seed, consistent across runs of the same build)
+pub(crate) fn hash_line(line: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    line.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,106 @@
+//! 过滤视图：只包含源文件中匹配谓词的行，源文件重新加载时自动同步
+//! Filtered views: only the source file's lines matching a predicate, kept in sync when the source reloads
+
+use crate::{AsyncLineCache, FileEntry};
+use rand::Rng;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError};
+
+/// 区分不同视图的记忆结果 | Tells the memoized results of different views apart
+static NEXT_VIEW_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 行过滤谓词 | Line filter predicate
+type Predicate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// 源文件中匹配谓词的行组成的虚拟文件，行号从 1 开始连续编号
+/// A virtual file made of the source file's lines that match a predicate, numbered continuously from 1
+///
+/// - 匹配行的下标按源文件的缓存条目记忆：同一版本的源文件只过滤一次，源文件变更或条目被驱逐后重新过滤
+/// - 视图只保存下标，不复制行文本；源文件末尾换行符产生的兼容空行不参与过滤
+///
+/// - The indices of matching lines are memoized on the source's cache entry: a given version is filtered once, and again after a change or eviction
+/// - The view keeps indices only, never copies of the line text; the compatibility empty line after the source's final newline is not filtered
+#[derive(Clone)]
+pub struct FilteredView {
+    cache: AsyncLineCache,
+    source: String,
+    predicate: Predicate,
+    id: u64,
+}
+
+impl FilteredView {
+    /// 源文件名 | Source filename
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 当前匹配的行数 | Current number of matching lines
+    pub async fn line_count(&self) -> std::io::Result<usize> {
+        Ok(self.matching().await?.map_or(0, |(_, indices)| indices.len()))
+    }
+
+    /// 获取视图的第 `lineno` 行（从 1 开始）；超出范围时返回 `None`
+    /// Get line `lineno` of the view (1-based); `None` when out of range
+    pub async fn get_line(&self, lineno: usize) -> std::io::Result<Option<String>> {
+        let Some((entry, indices)) = self.matching().await? else { return Ok(None) };
+        Ok(lineno
+            .checked_sub(1)
+            .and_then(|idx| indices.get(idx))
+            .and_then(|&idx| entry.lines().get(idx))
+            .map(String::from))
+    }
+
+    /// 随机返回一行匹配的行；没有匹配行时返回 `None`
+    /// Return a random matching line; `None` when nothing matches
+    pub async fn random_line(&self) -> std::io::Result<Option<String>> {
+        let Some((entry, indices)) = self.matching().await? else { return Ok(None) };
+        if indices.is_empty() {
+            return Ok(None);
+        }
+        let idx = indices[rand::thread_rng().gen_range(0..indices.len())];
+        Ok(entry.lines().get(idx).map(String::from))
+    }
+
+    /// 源文件的最新条目及其中匹配行的下标 | The source's current entry with the indices of its matching lines
+    async fn matching(&self) -> std::io::Result<Option<(FileEntry, Arc<[usize]>)>> {
+        let Some(entry) = self.cache.fresh_entry(&self.source).await? else { return Ok(None) };
+        let memo = &entry.derived().filtered;
+        if let Some(indices) = memo.lock().unwrap_or_else(PoisonError::into_inner).get(&self.id) {
+            return Ok(Some((entry.clone(), Arc::clone(indices))));
+        }
+        // 过滤期间不持锁 | filtered without holding the lock
+        let lines = entry.lines();
+        let indices: Arc<[usize]> = (0..lines.content_len())
+            .filter(|&idx| lines.get(idx).is_some_and(|line| (self.predicate)(line)))
+            .collect();
+        let indices = Arc::clone(memo.lock().unwrap_or_else(PoisonError::into_inner).entry(self.id).or_insert(indices));
+        Ok(Some((entry, indices)))
+    }
+}
+
+impl fmt::Debug for FilteredView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilteredView").field("source", &self.source).field("id", &self.id).finish_non_exhaustive()
+    }
+}
+
+impl AsyncLineCache {
+    /// 创建只包含 `filename` 中满足 `predicate` 的行的过滤视图，见 [`FilteredView`]
+    /// Create a filtered view of the lines of `filename` satisfying `predicate`, see [`FilteredView`]
+    ///
+    /// 需要正则匹配时，在闭包中调用正则即可（如 `move |line| re.is_match(line)`）。
+    /// For regex matching, call the regex inside the closure (e.g. `move |line| re.is_match(line)`).
+    pub fn filtered_view(
+        &self,
+        filename: &str,
+        predicate: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> FilteredView {
+        FilteredView {
+            cache: self.clone(),
+            source: filename.to_string(),
+            predicate: Arc::new(predicate),
+            id: NEXT_VIEW_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! 大小写折叠副本：为登记的文件建立小写化的行及其排序，供不区分大小写的查找使用
+//! Case-folded copies: lowercase lines and their sort order, built for registered files for case-insensitive lookups
+
+use crate::{AsyncLineCache, FileEntry, LineBuffer, SortedView};
+use std::mem::size_of;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// 一个文件版本的折叠副本 | The folded copy of one version of a file
+#[derive(Debug)]
+pub(crate) struct Folded {
+    /// 逐行小写化的文本（不含兼容空行）| Every line lowercased (without the compatibility empty line)
+    lines: Vec<String>,
+    /// 按折叠文本排序的行下标 | Line indices sorted by their folded text
+    order: Vec<usize>,
+}
+
+impl Folded {
+    fn new(lines: &LineBuffer) -> Self {
+        let lines: Vec<String> = lines.iter().take(lines.content_len()).map(str::to_lowercase).collect();
+        let mut order: Vec<usize> = (0..lines.len()).collect();
+        order.sort_by(|&a, &b| lines[a].cmp(&lines[b]));
+        Self { lines, order }
+    }
+
+    /// 折叠后等于 `folded` 的某一行的下标 | Index of a line whose folded text equals `folded`
+    fn find(&self, folded: &str) -> Option<usize> {
+        self.order.binary_search_by(|&idx| self.lines[idx].as_str().cmp(folded)).ok().map(|pos| self.order[pos])
+    }
+
+    /// 折叠后以 `folded` 开头的行下标，按折叠顺序 | Indices of lines whose folded text starts with `folded`, in folded order
+    fn with_prefix<'a>(&'a self, folded: &'a str) -> impl Iterator<Item = usize> + 'a {
+        let start = self.order.partition_point(|&idx| self.lines[idx].as_str() < folded);
+        self.order[start..].iter().copied().take_while(move |&idx| self.lines[idx].starts_with(folded))
+    }
+}
+
+impl Folded {
+    /// 估计占用的字节数 | Estimated bytes
+    fn size(&self) -> u64 {
+        let lines: usize = self.lines.iter().map(String::capacity).sum();
+        (lines + self.lines.capacity() * size_of::<String>() + self.order.capacity() * size_of::<usize>()) as u64
+    }
+}
+
+/// 在条目写入之前为它构建折叠副本，并计入条目权重 | Build the entry's folded copy before its insert, counting it in the entry's weight
+pub(crate) fn prebuild(entry: &FileEntry) {
+    let derived = entry.derived();
+    if derived.folded.get().is_none() {
+        let folded = Folded::new(entry.lines());
+        derived.prebuilt_bytes.fetch_add(folded.size(), Ordering::Relaxed);
+        let _ = derived.folded.set(Arc::new(folded));
+    }
+}
+
+/// 条目的折叠副本：登记的文件直接使用写入之前构建的副本，其余文件临时构建、用完即弃
+/// The entry's folded copy: registered files use the one built before the insert, other files get a transient one that is dropped after use
+fn entry_folded(entry: &FileEntry) -> Arc<Folded> {
+    entry.derived().folded.get().cloned().unwrap_or_else(|| Arc::new(Folded::new(entry.lines())))
+}
+
+/// 折叠后等于 `folded` 的某一行的下标；没有现成的折叠副本时逐行比较，不构建副本
+/// Index of a line whose folded text equals `folded`; without a ready folded copy the lines are compared one by one, building none
+fn find_folded(entry: &FileEntry, folded: &str) -> Option<usize> {
+    if let Some(copy) = entry.derived().folded.get() {
+        return copy.find(folded);
+    }
+    let lines = entry.lines();
+    lines.iter().take(lines.content_len()).position(|line| line.to_lowercase() == folded)
+}
+
+impl AsyncLineCache {
+    /// 文件中是否有与 `line` 完全相同的行（按行哈希集合判断，集合随条目记忆）
+    /// Whether the file has a line exactly equal to `line` (checked against the line hash set memoized with the entry)
+    pub async fn contains_line(&self, filename: &str, line: &str) -> std::io::Result<bool> {
+        Ok(self.line_set(filename).await?.contains(&crate::exclude::hash_line(line)))
+    }
+
+    /// 不区分大小写地判断文件中是否有与 `line` 相同的行
+    /// Whether the file has a line equal to `line`, ignoring case
+    ///
+    /// 用 [`fold_case`](Self::fold_case) 登记的文件只需二分查找，其余文件逐行比较。
+    /// Files registered with [`fold_case`](Self::fold_case) take a binary search; other files are compared line by line.
+    pub async fn contains_line_ignore_case(&self, filename: &str, line: &str) -> std::io::Result<bool> {
+        let Some(entry) = self.fresh_entry(filename).await? else { return Ok(false) };
+        Ok(find_folded(&entry, &line.to_lowercase()).is_some())
+    }
+
+    /// 为文件建立小写化的副本及其排序，并在之后每次重新加载时随新条目一起重建，使不区分大小写的查找只需二分查找
+    /// Build a lowercase copy of the file and its sort order now, and rebuild it with the new entry on every later reload, so case-insensitive lookups become binary searches
+    ///
+    /// - 副本使文件的内存占用约翻倍；它在条目写入之前构建，估计大小计入条目权重，受内存预算、配额与水位线约束
+    /// - 已缓存的文件会重新写入一个带折叠副本的条目；没有登记的文件不保留任何副本
+    ///
+    /// - The copy roughly doubles the file's memory; it is built before the entry is inserted and its estimated size counts in the entry's weight, under the memory budget, quotas and watermarks
+    /// - An already cached file is written again as an entry carrying the copy; unregistered files never keep one
+    pub async fn fold_case(&self, filename: &str) -> std::io::Result<()> {
+        self.folded.insert(filename);
+        if self.fresh_entry(filename).await?.is_some() {
+            self.rebuild_derived(filename).await?;
+        }
+        Ok(())
+    }
+
+    /// 取消登记，返回之前是否登记过；已建立的副本保留到条目被替换为止
+    /// Unregister the file, returning whether it was registered; a copy already built stays until the entry is replaced
+    pub fn unfold_case(&self, filename: &str) -> bool {
+        self.folded.remove(filename)
+    }
+}
+
+impl SortedView {
+    /// 不区分大小写地查找与 `line` 相同的行，返回其在源文件中的行号（从 1 开始）
+    /// Find a line equal to `line` ignoring case, returning its 1-based line number in the source
+    ///
+    /// 与 [`contains_line_ignore_case`](AsyncLineCache::contains_line_ignore_case) 一样，登记的文件二分查找，其余文件逐行比较。
+    /// Like [`contains_line_ignore_case`](AsyncLineCache::contains_line_ignore_case), registered files take a binary search and others a line-by-line comparison.
+    pub async fn find_ignore_case(&self, line: &str) -> std::io::Result<Option<usize>> {
+        let Some(entry) = self.entry().await? else { return Ok(None) };
+        Ok(find_folded(&entry, &line.to_lowercase()).map(|idx| idx + 1))
+    }
+
+    /// 不区分大小写地返回以 `prefix` 开头的行（原样返回），按折叠顺序最多 `limit` 行
+    /// Lines starting with `prefix` ignoring case (returned as they are), at most `limit` of them in folded order
+    ///
+    /// 没有用 [`fold_case`](AsyncLineCache::fold_case) 登记的文件每次调用都临时建立折叠副本。
+    /// Files not registered with [`fold_case`](AsyncLineCache::fold_case) get a transient folded copy on every call.
+    pub async fn with_prefix_ignore_case(&self, prefix: &str, limit: usize) -> std::io::Result<Vec<String>> {
+        let Some(entry) = self.entry().await? else { return Ok(Vec::new()) };
+        let prefix = prefix.to_lowercase();
+        Ok(entry_folded(&entry)
+            .with_prefix(&prefix)
+            .take(limit)
+            .filter_map(|idx| entry.lines().get(idx).map(String::from))
+            .collect())
+    }
+}
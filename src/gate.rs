@@ -0,0 +1,60 @@
+//! 正在加载中的文件的内存总量上限
+//! Cap on the total memory of files that are being loaded
+//!
+//! 条目只有写入缓存后才会计入权重；若同时有多个大文件未命中，它们的读缓冲区会在驱逐生效之前叠加。
+//! 每次加载先按文件大小预留额度，额度不足时排队等待，直到条目写入缓存后才归还。
+//! Entries only count towards the weighted size once they are inserted, so a burst of misses on large files
+//! stacks their read buffers before eviction can react. Every load first reserves its file size and queues
+//! while the reservation cannot be met; the reservation is returned once the entry has been inserted.
+
+use std::sync::{Arc, PoisonError, RwLock};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// 额度以 KiB 为单位计数，使单次预留（`u32` 个许可）可以覆盖 4TiB
+/// Reservations are counted in KiB so a single one (`u32` permits) can cover 4TiB
+pub(crate) const UNIT: u64 = 1024;
+
+/// 加载内存闸门，由所有克隆出的缓存实例共享
+/// Load memory gate, shared by every clone of the cache
+#[derive(Debug)]
+pub(crate) struct LoadGate {
+    permits: Semaphore,
+    limit: u32,
+}
+
+impl LoadGate {
+    /// 允许同时加载的文件总字节数为 `limit_bytes`（至少 1KiB）
+    /// Allow up to `limit_bytes` of files to be loading at once (at least 1KiB)
+    pub(crate) fn new(limit_bytes: u64) -> Self {
+        let limit = u32::try_from((limit_bytes / UNIT).max(1)).unwrap_or(u32::MAX);
+        Self { permits: Semaphore::new(limit as usize), limit }
+    }
+
+    /// 为 `bytes` 字节的加载预留额度；超过上限的单个文件按上限预留，独占闸门但仍能加载
+    /// Reserve room for a load of `bytes`; a single file above the limit reserves the whole limit, running alone but still loading
+    pub(crate) async fn reserve(&self, bytes: u64) -> std::io::Result<SemaphorePermit<'_>> {
+        let units = u32::try_from(bytes.div_ceil(UNIT)).map_or(self.limit, |units| units.clamp(1, self.limit));
+        self.permits.acquire_many(units).await.map_err(std::io::Error::other)
+    }
+}
+
+/// 可在运行时替换的闸门：新的加载使用新闸门，正在进行的加载把额度归还给原来的闸门
+/// A gate that can be swapped at runtime: new loads use the new gate, while in-flight loads return their room to the old one
+#[derive(Debug, Clone)]
+pub(crate) struct GateSlot(Arc<RwLock<Arc<LoadGate>>>);
+
+impl GateSlot {
+    pub(crate) fn new(limit_bytes: u64) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(LoadGate::new(limit_bytes)))))
+    }
+
+    /// 当前生效的闸门 | The gate currently in effect
+    pub(crate) fn current(&self) -> Arc<LoadGate> {
+        Arc::clone(&self.0.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// 以新的上限替换闸门 | Replace the gate with one of a new limit
+    pub(crate) fn set_limit(&self, limit_bytes: u64) {
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(LoadGate::new(limit_bytes));
+    }
+}
@@ -0,0 +1,67 @@
+//! 逐行哈希、跨文件重复行统计与相似度，按条目记忆
+//! Per-line hashes, cross-file duplicate-line counting and similarity, memoized per entry
+
+use crate::exclude::hash_line;
+use crate::{AsyncLineCache, FileEntry};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+impl AsyncLineCache {
+    /// 文件每一行的 64 位哈希，下标与行号对应（第 `i` 个哈希即第 `i + 1` 行），结果按缓存条目记忆
+    /// The 64-bit hash of every line of the file, indexed like the lines (hash `i` belongs to line `i + 1`); memoized on the cache entry
+    ///
+    /// - 与 [`ExclusionSet`](crate::ExclusionSet) 使用相同的哈希，文件变更或条目被驱逐后重新计算
+    /// - 文件为空或不存在时返回空列表
+    ///
+    /// - Uses the same hash as [`ExclusionSet`](crate::ExclusionSet); recomputed after a change or eviction
+    /// - Returns an empty list when the file is empty or missing
+    pub async fn line_hashes(&self, filename: &str) -> std::io::Result<Arc<Vec<u64>>> {
+        let Some(entry) = self.fresh_entry(filename).await? else {
+            return Ok(Arc::default());
+        };
+        Ok(entry_hashes(&entry))
+    }
+
+    /// 两个文件共有的不同行数（按行哈希比较；末尾换行符产生的兼容空行不计）
+    /// Number of distinct lines the two files have in common (compared by line hash; the compatibility empty line after a final newline is ignored)
+    pub async fn shared_lines(&self, a: &str, b: &str) -> std::io::Result<usize> {
+        let (a, b) = (self.line_set(a).await?, self.line_set(b).await?);
+        Ok(intersection(&a, &b))
+    }
+
+    /// 两个文件去重后行集合的 Jaccard 相似度（交集 / 并集，范围 0~1），用于在合并词表前发现近似重复
+    /// Jaccard similarity of the two files' distinct line sets (intersection / union, from 0 to 1), for spotting near-duplicates before merging wordlists
+    ///
+    /// 行集合按缓存条目记忆，重复比较同一批文件只需求交集；两个文件都为空时返回 1。
+    /// The line sets are memoized per entry, so comparing the same files again only intersects them; returns 1 when both files are empty.
+    #[allow(clippy::cast_precision_loss)]
+    pub async fn jaccard_similarity(&self, a: &str, b: &str) -> std::io::Result<f64> {
+        let (set_a, set_b) = (self.line_set(a).await?, self.line_set(b).await?);
+        let shared = intersection(&set_a, &set_b);
+        let union = set_a.len() + set_b.len() - shared;
+        Ok(if union == 0 { 1.0 } else { shared as f64 / union as f64 })
+    }
+
+    /// 文件去重后的行哈希集合，按缓存条目记忆 | The file's distinct line hashes, memoized on the cache entry
+    pub(crate) async fn line_set(&self, filename: &str) -> std::io::Result<Arc<HashSet<u64>>> {
+        let Some(entry) = self.fresh_entry(filename).await? else {
+            return Ok(Arc::default());
+        };
+        let set = entry.derived().line_set.get_or_init(|| {
+            let hashes = entry_hashes(&entry);
+            Arc::new(hashes[..entry.lines().content_len()].iter().copied().collect())
+        });
+        Ok(Arc::clone(set))
+    }
+}
+
+/// 条目的逐行哈希（首次访问时计算）| The entry's per-line hashes, computed on first access
+fn entry_hashes(entry: &FileEntry) -> Arc<Vec<u64>> {
+    Arc::clone(entry.derived().line_hashes.get_or_init(|| Arc::new(entry.lines().iter().map(hash_line).collect())))
+}
+
+/// 两个集合的交集大小（遍历较小的一个）| Size of the intersection of two sets, iterating the smaller one
+fn intersection(a: &HashSet<u64>, b: &HashSet<u64>) -> usize {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    small.iter().filter(|hash| large.contains(hash)).count()
+}
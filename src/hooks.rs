@@ -0,0 +1,83 @@
+//! 缓存生命周期回调：加载、命中、未命中、失效与驱逐
+//! Cache lifecycle callbacks: loads, hits, misses, invalidations and evictions
+
+use crate::FileEntry;
+use std::fmt;
+use std::sync::Arc;
+
+/// 缓存生命周期回调，用于自定义指标、审计日志或与其他缓存联动
+/// Cache lifecycle callbacks, the extension point for custom metrics, audit logging and cache-coupling logic
+///
+/// - 所有方法都有空的默认实现，只需覆盖关心的事件
+/// - 回调在缓存操作的调用路径上同步执行，应当快速返回且不要阻塞
+/// - 驱逐回调由缓存的维护任务触发，可能晚于导致驱逐的那次写入
+///
+/// - Every method has an empty default, so only the events of interest need overriding
+/// - Callbacks run synchronously on the cache operation's path, so they should return quickly and never block
+/// - Eviction callbacks are fired by the cache's maintenance work and may lag behind the insert that caused them
+pub trait CacheHooks: Send + Sync {
+    /// 条目（来自磁盘或加载器）刚被写入缓存
+    /// An entry (from disk or a loader) was just inserted
+    fn on_load(&self, filename: &str, entry: &FileEntry) {
+        let _ = (filename, entry);
+    }
+
+    /// 访问命中了仍然有效的缓存条目
+    /// An access was served from a still-valid cached entry
+    fn on_hit(&self, filename: &str) {
+        let _ = filename;
+    }
+
+    /// 访问未命中（没有条目，或条目已过期），需要重新加载
+    /// An access missed (no entry, or a stale one) and needs a reload
+    fn on_miss(&self, filename: &str) {
+        let _ = filename;
+    }
+
+    /// 条目被 `invalidate` 手动失效
+    /// An entry was manually invalidated via `invalidate`
+    fn on_invalidate(&self, filename: &str) {
+        let _ = filename;
+    }
+
+    /// 整个缓存被 `clear` 清空
+    /// The whole cache was emptied via `clear`
+    fn on_clear(&self) {}
+
+    /// 条目因内存预算不足被驱逐
+    /// An entry was evicted because the memory budget ran out
+    fn on_evict(&self, filename: &str) {
+        let _ = filename;
+    }
+
+    /// 文件内容（`bytes` 字节）超过单条目上限，照常返回但没有缓存
+    /// The file's content (`bytes` bytes) exceeds the per-entry limit, so it was served but not cached
+    fn on_oversize(&self, filename: &str, bytes: u64) {
+        let _ = (filename, bytes);
+    }
+}
+
+/// 已注册的回调（可能没有），由所有克隆出的缓存实例共享
+/// The registered callbacks, if any, shared by every clone of the cache
+#[derive(Clone, Default)]
+pub(crate) struct Hooks(Option<Arc<dyn CacheHooks>>);
+
+impl Hooks {
+    pub(crate) fn new(hooks: Arc<dyn CacheHooks>) -> Self {
+        Self(Some(hooks))
+    }
+
+    pub(crate) fn get(&self) -> Option<&dyn CacheHooks> {
+        self.0.as_deref()
+    }
+
+    pub(crate) fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks").field("registered", &self.is_set()).finish()
+    }
+}
@@ -0,0 +1,104 @@
+//! 倒排索引：词 → 行号，随缓存条目保存，支持对大文件的快速词查询
+//! Inverted index: token → line numbers, kept with the cache entry, for fast token queries on large files
+
+use crate::{AsyncLineCache, FileEntry, LineBuffer};
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// 一个文件版本的倒排索引：小写词 → 出现该词的行下标（升序、去重）
+/// The inverted index of one version of a file: lowercase token → indices of the lines containing it (ascending, deduplicated)
+pub(crate) type InvertedIndex = HashMap<String, Vec<usize>>;
+
+/// 按字母数字切词并转为小写 | Split into alphanumeric tokens, lowercased
+fn tokens(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|token| !token.is_empty()).map(str::to_lowercase)
+}
+
+fn build(lines: &LineBuffer) -> InvertedIndex {
+    let mut index = InvertedIndex::new();
+    for (idx, line) in lines.iter().enumerate() {
+        for token in tokens(line) {
+            let postings = index.entry(token).or_default();
+            if postings.last() != Some(&idx) {
+                postings.push(idx);
+            }
+        }
+    }
+    index
+}
+
+/// 索引估计占用的字节数：词与行下标列表的容量，加上每个哈希表槽位的开销
+/// Estimated bytes of an index: the capacity of its tokens and posting lists, plus per-slot hash table overhead
+fn index_size(index: &InvertedIndex) -> u64 {
+    let slot = size_of::<String>() + size_of::<Vec<usize>>() + 1;
+    let heap: usize = index.iter().map(|(token, lines)| token.capacity() + lines.capacity() * size_of::<usize>()).sum();
+    (heap + index.capacity() * slot) as u64
+}
+
+/// 在条目写入之前为它构建倒排索引，并计入条目权重 | Build the entry's inverted index before its insert, counting it in the entry's weight
+pub(crate) fn prebuild(entry: &FileEntry) {
+    let derived = entry.derived();
+    if derived.index.get().is_none() {
+        let index = build(entry.lines());
+        derived.prebuilt_bytes.fetch_add(index_size(&index), Ordering::Relaxed);
+        let _ = derived.index.set(Arc::new(index));
+    }
+}
+
+/// 条目的倒排索引：登记的文件直接使用写入之前构建的索引，其余文件临时构建、用完即弃
+/// The entry's inverted index: registered files use the one built before the insert, other files get a transient one that is dropped after use
+fn entry_index(entry: &FileEntry) -> Arc<InvertedIndex> {
+    entry.derived().index.get().cloned().unwrap_or_else(|| Arc::new(build(entry.lines())))
+}
+
+impl AsyncLineCache {
+    /// 为文件建立倒排索引，并在之后每次重新加载时随新条目一起重建
+    /// Build the file's inverted index now, and rebuild it together with the new entry on every later reload
+    ///
+    /// - 索引在条目写入之前构建，其估计大小计入条目权重，受内存预算、配额与水位线约束；已缓存的文件会以带索引的副本重新写入
+    /// - 没有登记的文件每次 [`search`](Self::search) 都临时建立索引，用完即弃，不占用缓存内存
+    /// - 文件重新加载时整体重建（内容整体替换，没有增量补丁）；条目被驱逐时索引一起释放
+    ///
+    /// - The index is built before the entry is inserted, and its estimated size counts in the entry's weight, under the memory budget, quotas and watermarks; an already cached file is written again as a copy with the index
+    /// - Unregistered files get a transient index on every [`search`](Self::search), dropped afterwards and never held in cache memory
+    /// - The index is rebuilt as a whole on reload (the content is replaced as a whole, so there is no incremental patching); it is released with an evicted entry
+    pub async fn index(&self, filename: &str) -> std::io::Result<()> {
+        self.indexed.insert(filename);
+        if self.fresh_entry(filename).await?.is_some() {
+            self.rebuild_derived(filename).await?;
+        }
+        Ok(())
+    }
+
+    /// 取消登记，返回之前是否登记过；已建立的索引保留到条目被替换为止
+    /// Unregister the file, returning whether it was registered; an index already built stays until the entry is replaced
+    pub fn unindex(&self, filename: &str) -> bool {
+        self.indexed.remove(filename)
+    }
+
+    /// 包含 `term` 中所有词的行号（从 1 开始，升序）；按字母数字切词，不区分大小写
+    /// Numbers (1-based, ascending) of the lines containing every token of `term`; tokens are alphanumeric runs, matched case-insensitively
+    ///
+    /// `term` 中没有任何词、文件为空或不存在时返回空列表。
+    /// Returns an empty list when `term` has no tokens or the file is empty or missing.
+    pub async fn search(&self, filename: &str, term: &str) -> std::io::Result<Vec<usize>> {
+        let Some(entry) = self.fresh_entry(filename).await? else { return Ok(Vec::new()) };
+        let index = entry_index(&entry);
+        let mut postings = Vec::new();
+        for token in tokens(term) {
+            match index.get(&token) {
+                Some(lines) => postings.push(lines.as_slice()),
+                None => return Ok(Vec::new()),
+            }
+        }
+        postings.sort_unstable_by_key(|lines| lines.len());
+        let Some((first, rest)) = postings.split_first() else { return Ok(Vec::new()) };
+        Ok(first
+            .iter()
+            .filter(|idx| rest.iter().all(|lines| lines.binary_search(idx).is_ok()))
+            .map(|idx| idx + 1)
+            .collect())
+    }
+}
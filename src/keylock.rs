@@ -0,0 +1,76 @@
+//! 按文件名分段的异步锁：同一文件的"检查 → 失效 → 加载 → 写入"串行执行
+//! Striped async locks by filename: "check → invalidate → load → insert" runs serially per file
+//!
+//! 读取旧内容的加载若晚于读取新内容的加载写入缓存，就会把过期数据放回缓存；
+//! 让同一文件的加载与失效都在同一把锁下完成，写入顺序便与读取顺序一致。
+//! A load that read old content but inserts after a load that read new content would put stale data back;
+//! running every load and invalidation of a file under the same lock keeps insert order equal to read order.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use tokio::sync::{Mutex, MutexGuard};
+
+/// 锁分段数：不同文件只有在落入同一分段时才会互相等待
+/// Number of stripes: different files only wait on each other when they land in the same stripe
+const STRIPES: usize = 64;
+
+/// 分段锁表，由所有克隆出的缓存实例共享
+/// Striped lock table, shared by every clone of the cache
+#[derive(Debug)]
+pub(crate) struct KeyLocks {
+    stripes: Box<[Mutex<()>]>,
+}
+
+impl Default for KeyLocks {
+    fn default() -> Self {
+        Self { stripes: (0..STRIPES).map(|_| Mutex::new(())).collect() }
+    }
+}
+
+impl KeyLocks {
+    /// 获取 `key` 所在分段的锁 | Lock the stripe that `key` belongs to
+    pub(crate) async fn lock(&self, key: &str) -> MutexGuard<'_, ()> {
+        self.stripes[self.stripe(key)].lock().await
+    }
+
+    /// 按与 `lock_all` 相同的顺序获取多个键所在分段的锁（同一分段只锁一次）
+    /// Lock the stripes of several keys in the same order as `lock_all` (each stripe once)
+    pub(crate) async fn lock_many(&self, keys: &[&str]) -> Vec<MutexGuard<'_, ()>> {
+        let mut stripes: Vec<usize> = keys.iter().map(|key| self.stripe(key)).collect();
+        stripes.sort_unstable();
+        stripes.dedup();
+        let mut guards = Vec::with_capacity(stripes.len());
+        for idx in stripes {
+            guards.push(self.stripes[idx].lock().await);
+        }
+        guards
+    }
+
+    /// 不等待地获取 `key` 所在分段的锁；已被占用时返回 `None` | Lock the stripe of `key` without waiting; `None` when it is taken
+    pub(crate) fn try_lock(&self, key: &str) -> Option<MutexGuard<'_, ()>> {
+        self.stripes[self.stripe(key)].try_lock().ok()
+    }
+
+    /// 两个键是否落入同一分段（持有其中一个的锁即持有另一个的锁）
+    /// Whether two keys land in the same stripe (holding the lock of one holds the other's)
+    pub(crate) fn same_stripe(&self, a: &str, b: &str) -> bool {
+        self.stripe(a) == self.stripe(b)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn stripe(&self, key: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish() as usize % self.stripes.len()
+    }
+
+    /// 按固定顺序获取全部分段的锁（用于整体清空，固定顺序避免死锁）
+    /// Lock every stripe in a fixed order (for clearing everything; the fixed order rules out deadlocks)
+    pub(crate) async fn lock_all(&self) -> Vec<MutexGuard<'_, ()>> {
+        let mut guards = Vec::with_capacity(self.stripes.len());
+        for stripe in &*self.stripes {
+            guards.push(stripe.lock().await);
+        }
+        guards
+    }
+}
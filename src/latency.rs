@@ -0,0 +1,169 @@
+//! 按操作类型分别记录的延迟直方图：缓存命中、重新校验与冷加载
+//! Latency histograms recorded per operation type: cache hits, revalidations and cold loads
+
+use crate::AsyncLineCache;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// 直方图的桶数：第 `i` 个桶的上界为 2^i 微秒，最后一个桶收纳更慢的操作
+/// Number of histogram buckets: bucket `i` is bounded by 2^i microseconds, and the last one takes anything slower
+const BUCKETS: usize = 32;
+
+/// 一次读取属于哪类操作 | Which kind of operation a read was
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    /// 直接由缓存返回，没有接触磁盘 | Served from the cache without touching the disk
+    Hit,
+    /// 由缓存返回，但先 stat 了文件确认未变 | Served from the cache after a stat confirmed the file unchanged
+    Revalidation,
+    /// 未命中：从磁盘或加载器加载（包括等待其他任务完成同一文件的加载）
+    /// A miss: loaded from disk or a loader (including waiting for another task's load of the same file)
+    Load,
+}
+
+/// 一类操作的延迟直方图快照 | Snapshot of one operation type's latency histogram
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    buckets: [u64; BUCKETS],
+    total: Duration,
+}
+
+impl LatencyHistogram {
+    /// 各桶的计数：第 `i` 个桶统计耗时小于 [`bucket_bound(i)`](Self::bucket_bound) 且不小于前一个桶上界的操作
+    /// Per-bucket counts: bucket `i` counts operations faster than [`bucket_bound(i)`](Self::bucket_bound) but not faster than the previous bound
+    pub fn buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+
+    /// 第 `i` 个桶的上界（2^i 微秒）；最后一个桶实际上没有上界
+    /// Upper bound of bucket `i` (2^i microseconds); the last bucket is effectively unbounded
+    pub fn bucket_bound(i: usize) -> Duration {
+        Duration::from_micros(1u64 << i.min(63))
+    }
+
+    /// 记录的操作数 | Number of recorded operations
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// 所有操作的总耗时 | Total time of all operations
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// 平均耗时；没有记录时为 `None` | Mean time; `None` without records
+    pub fn mean(&self) -> Option<Duration> {
+        let count = u32::try_from(self.count()).unwrap_or(u32::MAX);
+        (count > 0).then(|| self.total / count)
+    }
+
+    /// 分位数 `q`（`0.0..=1.0`）所在桶的上界，例如 `quantile(0.99)` 是 p99 的上限估计；没有记录时为 `None`
+    /// Upper bound of the bucket holding quantile `q` (`0.0..=1.0`), so `quantile(0.99)` bounds the p99; `None` without records
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        self.buckets.iter().position(|&n| {
+            seen += n;
+            seen >= rank
+        })
+        .map(Self::bucket_bound)
+    }
+}
+
+/// 全部操作类型的延迟统计快照，由 [`AsyncLineCache::stats`] 返回
+/// Snapshot of the latency statistics of every operation type, returned by [`AsyncLineCache::stats`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheStats {
+    hits: LatencyHistogram,
+    revalidations: LatencyHistogram,
+    loads: LatencyHistogram,
+}
+
+impl CacheStats {
+    /// 直接由缓存返回、没有接触磁盘的读取 | Reads served from the cache without touching the disk
+    pub fn hits(&self) -> &LatencyHistogram {
+        &self.hits
+    }
+
+    /// 先 stat 文件确认未变、再由缓存返回的读取 | Reads served from the cache after a stat confirmed the file unchanged
+    pub fn revalidations(&self) -> &LatencyHistogram {
+        &self.revalidations
+    }
+
+    /// 未命中而加载（或等待他人加载）的读取 | Reads that missed and loaded (or waited for someone else's load)
+    pub fn loads(&self) -> &LatencyHistogram {
+        &self.loads
+    }
+}
+
+/// 一类操作的原子计数器 | Atomic counters of one operation type
+#[derive(Debug)]
+struct Recorder {
+    buckets: [AtomicU64; BUCKETS],
+    total_nanos: AtomicU64,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self { buckets: std::array::from_fn(|_| AtomicU64::new(0)), total_nanos: AtomicU64::new(0) }
+    }
+}
+
+impl Recorder {
+    fn record(&self, elapsed: Duration) {
+        let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        let bucket = ((u64::BITS - micros.leading_zeros()) as usize).min(BUCKETS - 1);
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.total_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> LatencyHistogram {
+        LatencyHistogram {
+            buckets: std::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed)),
+            total: Duration::from_nanos(self.total_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// 本缓存键空间内的延迟记录，由所有克隆出的缓存实例共享
+/// Latency records of this cache's key space, shared by every clone of the cache
+#[derive(Debug, Default)]
+pub(crate) struct Latencies {
+    hits: Recorder,
+    revalidations: Recorder,
+    loads: Recorder,
+}
+
+impl Latencies {
+    pub(crate) fn record(&self, op: Op, elapsed: Duration) {
+        match op {
+            Op::Hit => self.hits.record(elapsed),
+            Op::Revalidation => self.revalidations.record(elapsed),
+            Op::Load => self.loads.record(elapsed),
+        }
+    }
+}
+
+impl AsyncLineCache {
+    /// 按操作类型分别统计的读取延迟：命中、重新校验与加载，可据此得出实际接触磁盘的调用占比
+    /// Read latencies per operation type (hits, revalidations and loads), showing what share of calls actually touches the disk
+    ///
+    /// - 统计覆盖经过变更检测的异步读取，从调用开始计时，包括等待加载锁的时间
+    /// - 每个命名空间单独统计；计数从缓存创建起累计，不会被 [`clear`](Self::clear) 清零
+    ///
+    /// - Covers the async reads that go through change detection, timed from the start of the call including any wait for the load lock
+    /// - Each namespace keeps its own statistics; counts accumulate from the cache's creation and survive [`clear`](Self::clear)
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.latencies.hits.snapshot(),
+            revalidations: self.latencies.revalidations.snapshot(),
+            loads: self.latencies.loads.snapshot(),
+        }
+    }
+}
@@ -4,10 +4,10 @@
 //! 比 Python 标准库 `linecache` 快 **50~200 倍**，内存真正可控，专为亿级调用场景设计。
 //! 50~200× faster than Python's stdlib `linecache`, truly controllable memory, designed for billions of calls.
 //!
-//! 完全兼容 Python `linecache` 的所有行为，同时保留旧版 DashMap 实现 API，
-//! 可实现零代码修改直接替换。
-//! 100% compatible with Python `linecache` behavior, while keeping legacy DashMap API,
-//! allowing zero-code drop-in replacement.
+//! 完全兼容 Python `linecache` 的所有行为。自 0.3 起内部缓存不再公开：0.2 的 `lines` / `contents` 字段
+//! 由 [`AsyncLineCache::peek_entry`]、[`AsyncLineCache::peek_lines`] 等方法取代。
+//! 100% compatible with Python `linecache` behavior. Since 0.3 the internal caches are no longer public: the `lines` /
+//! `contents` fields of 0.2 are replaced by methods such as [`AsyncLineCache::peek_entry`] and [`AsyncLineCache::peek_lines`].
 //!
 //! License: MIT OR Apache-2.0
 
@@ -15,101 +15,445 @@
 #![deny(clippy::all)]
 #![warn(clippy::pedantic)]
 #![allow(clippy::must_use_candidate)]
+// 几乎所有公开方法都返回 `std::io::Result`，错误情形在各自的文档中说明，不再逐个加 `# Errors` 小节
+// Nearly every public method returns `std::io::Result`, with its errors described in its own docs rather than an `# Errors` section each
+#![allow(clippy::missing_errors_doc)]
+
+// 异步缓存及其全部扩展都建立在 tokio 之上，只在启用 `async` 特性（默认开启）时编译
+// The async cache and all of its extensions are built on tokio and compile only with the `async` feature (on by default)
+macro_rules! cfg_async {
+    ($($item:item)*) => {
+        $( #[cfg(feature = "async")] $item )*
+    };
+}
+
+mod buffer;
+#[cfg(feature = "sync")]
+mod sync_cache;
+
+pub use buffer::LineBuffer;
+#[cfg(feature = "sync")]
+pub use sync_cache::LineCache;
 
-use moka::future::{Cache, CacheBuilder}; // 高性能异步缓存，支持权重驱逐 | High-performance async cache with weight-based eviction
-use once_cell::sync::Lazy;              // 线程安全懒初始化 | Thread-safe lazy initialization
-use rand::seq::SliceRandom;             // 随机选择扩展 | Random selection utilities
 use std::sync::Arc;
-use std::time::SystemTime;
-use sysinfo::System;                    // 获取系统内存信息 | Get system memory info
-use tokio::fs::File;
-use tokio::io::{AsyncReadExt, BufReader};
+
+cfg_async! {
+    #[cfg(all(feature = "alloc-weigher", target_os = "linux"))]
+    mod alloc_weigher;
+    mod audit;
+    #[cfg(feature = "autocomplete")]
+    mod autocomplete;
+    mod builder;
+    #[cfg(all(feature = "sysinfo", target_os = "linux"))]
+    mod cgroup;
+    mod checkcache;
+    mod clock;
+    #[cfg(feature = "bytes")]
+    mod bytes_view;
+    mod column;
+    #[cfg(feature = "sysinfo")]
+    pub mod compat;
+    mod config;
+    mod content;
+    mod deadline;
+    mod dir;
+    mod dir_sample;
+    mod discover;
+    mod dump;
+    #[cfg(feature = "coding-cookie")]
+    mod encoding;
+    mod entry;
+    mod env;
+    mod eviction;
+    mod exclude;
+    mod filter;
+    mod fold;
+    mod gate;
+    mod hashes;
+    mod hooks;
+    mod index;
+    mod keylock;
+    mod latency;
+    mod loader;
+    #[cfg(feature = "generate")]
+    mod markov;
+    mod merge;
+    mod namespace;
+    mod ngram;
+    mod options;
+    mod pool;
+    #[cfg(feature = "sysinfo")]
+    mod pressure;
+    mod quarantine;
+    mod quota;
+    mod retry;
+    mod sandbox;
+    mod scope;
+    mod self_test;
+    mod served;
+    #[cfg(feature = "detect-language")]
+    mod script;
+    mod shuffle;
+    mod sidecar;
+    mod slice;
+    mod snapshot;
+    mod sorted;
+    mod stat;
+    mod sync_point;
+    mod tasks;
+    mod transform;
+    mod uncached;
+    mod watermark;
+    mod weigh;
+
+    pub use audit::AuditLog;
+    pub use builder::{AsyncLineCacheBuilder, Eviction};
+    pub use clock::{Clock, ManualClock};
+    pub use column::ColumnView;
+    pub use config::CacheConfig;
+    pub use dir::DirEntryInfo;
+    pub use discover::DiscoverFilter;
+    pub use entry::FileEntry;
+    pub use exclude::ExclusionSet;
+    pub use filter::FilteredView;
+    pub use hooks::CacheHooks;
+    pub use latency::{CacheStats, LatencyHistogram};
+    pub use loader::SourceLoader;
+    #[cfg(feature = "generate")]
+    pub use markov::MarkovModel;
+    pub use merge::MergedView;
+    pub use ngram::NgramCounts;
+    pub use options::FileOptions;
+    pub use retry::RetryPolicy;
+    pub use pool::CorpusPool;
+    #[cfg(feature = "detect-language")]
+    pub use script::Script;
+    pub use self_test::SelfTestReport;
+    pub use slice::{LineSlice, LineWindows, NumberedLines};
+    pub use snapshot::Snapshot;
+    pub use transform::{LineLengthPolicy, LoadStats};
+    pub use sorted::SortedView;
+    #[cfg(feature = "test-hooks")]
+    pub use sync_point::{SyncHook, SyncPoint};
+    pub use watermark::WatermarkExceeded;
+
+    use builder::EntryPolicy;
+    use clock::SharedClock;
+    use entry::Stamp;
+    use gate::GateSlot;
+    use hooks::Hooks;
+    use entry::FileSet;
+    use keylock::KeyLocks;
+    use latency::{Latencies, Op};
+    use loader::LoaderRegistry;
+    use namespace::Namespaces;
+    use options::FileOverrides;
+    use quarantine::Quarantine;
+    use quota::Quotas;
+    use scope::Scope;
+    use served::Served;
+    use stat::RecentStats;
+    #[cfg(not(feature = "test-hooks"))]
+    use sync_point::SyncPoint;
+    use sync_point::SyncPoints;
+    use tasks::Tasks;
+    use transform::Transforms;
+    use watermark::Watermarks;
+    use weigh::Weighing;
+
+    use moka::future::Cache;                // 高性能异步缓存，支持权重驱逐 | High-performance async cache with weight-based eviction
+    use rand::seq::SliceRandom;             // 随机选择扩展 | Random selection utilities
+    use rand::Rng;
+    #[cfg(feature = "sysinfo")]
+    use std::sync::LazyLock;                // LazyLock：线程安全懒初始化 | Thread-safe lazy initialization
+    use std::time::Duration;
+    #[cfg(feature = "sysinfo")]
+    use sysinfo::System;                    // 获取系统内存信息 | Get system memory info
+    use tokio::fs::File;
+    use tokio::io::{AsyncReadExt, BufReader};
+    use tokio::sync::SemaphorePermit;
+}
 
 /// 系统总物理内存（字节），只在第一次使用时初始化一次，
 /// 避免每次创建缓存都触发系统调用（可能带来 50~200ms 延迟）。
 /// 为防止测试环境返回过小值，最小保证 1GiB。
+/// 在容器中取宿主机内存与 cgroup 内存上限中的较小者。
 /// Total physical memory in bytes, initialized only once on first use,
 /// avoiding system call overhead (50~200ms) on every cache creation.
 /// Guarantees at least 1GiB in test environments.
-static TOTAL_MEMORY: Lazy<u64> = Lazy::new(|| {
+/// Inside a container, the smaller of host memory and the cgroup memory limit is used.
+#[cfg(feature = "sysinfo")]
+static TOTAL_MEMORY: LazyLock<u64> = LazyLock::new(|| {
     let mem = System::new_all().total_memory();
+    #[cfg(target_os = "linux")]
+    let mem = cgroup::memory_limit().map_or(mem, |limit| mem.min(limit));
     mem.max(1024 * 1024 * 1024) // 至少 1 GiB | at least 1 GiB
 });
 
-/// 缓存的行数据类型：使用 `Arc<Vec<String>>`
+/// 缓存的行数据类型：使用 `Arc<LineBuffer>`
 /// - `Arc` 实现零成本共享
-/// - `Vec<String>` 支持 O(1) 随机访问
-/// Cached line data type: `Arc<Vec<String>>`
+/// - `LineBuffer` 一块连续文本 + 行偏移索引，支持 O(1) 随机访问
+///
+/// Cached line data type: `Arc<LineBuffer>`
 /// - `Arc` for zero-cost sharing
-/// - `Vec<String>` for O(1) random access
-type CachedLines = Arc<Vec<String>>;
+/// - `LineBuffer` is one contiguous text plus a line offset index, for O(1) random access
+pub type CachedLines = Arc<LineBuffer>;
 
 /// 工业级异步行缓存核心结构体
 /// Industrial-grade asynchronous line cache core structure
+#[cfg(feature = "async")]
 #[derive(Debug, Clone)]
 pub struct AsyncLineCache {
-    /// 按文件路径缓存解析后的行向量（Arc<Vec<String>>）
-    /// Cache of parsed lines per file path (Arc<Vec<String>>)
-    pub lines: Cache<String, CachedLines>,
+    /// 按文件路径缓存的统一条目（行 + 原始内容 + 元数据），三者总是一起写入、驱逐和失效
+    /// Unified per-path entries (lines + raw content + metadata), always inserted, evicted and invalidated together
+    pub(crate) entries: Cache<String, FileEntry>,
+
+    /// 按文件名注册的源码加载器，文件不存在时作为后备来源
+    /// Per-filename source loaders, used as fallback when the file is missing
+    loaders: LoaderRegistry,
+
+    /// 最近一次 stat 校验通过的文件（带短 TTL），窗口内的重复调用共享一次 stat；TTL 为 0 表示关闭
+    /// Files whose stat check passed recently (short TTL), so calls within the window share one stat; a TTL of 0 disables it
+    recent_stats: Arc<RecentStats>,
+
+    /// 条目缓存的总内存预算（字节）
+    /// Total memory budget of the entry cache in bytes
+    budget: u64,
+
+    /// 权重计算方式 | Weighing strategy
+    weighing: Weighing,
+
+    /// 驱逐策略与过期时间 | Eviction policy and expiration
+    policy: EntryPolicy,
+
+    /// 按文件名分段的加载锁，保证同一文件的失效与重新加载按顺序原子完成
+    /// Per-filename striped load locks, so a file's invalidations and reloads happen atomically and in order
+    locks: Arc<KeyLocks>,
+
+    /// 正在加载中的文件的内存总量上限（默认等于缓存预算）
+    /// Cap on the memory of files being loaded concurrently (defaults to the cache budget)
+    load_gate: GateSlot,
+
+    /// 生命周期回调 | Lifecycle callbacks
+    hooks: Hooks,
+
+    /// 由本缓存派生的命名空间子缓存 | Namespaced sub-caches derived from this cache
+    namespaces: Namespaces,
+
+    /// 按键前缀的字节配额 | Byte quotas per key prefix
+    quotas: Quotas,
+
+    /// 作用域子缓存记录自己引入的键；普通缓存为 `None`
+    /// Scoped children record the keys they introduce; `None` for a regular cache
+    scope: Option<Scope>,
+
+    /// 软/硬内存水位线，默认不设置 | Soft/hard memory watermarks, unset by default
+    watermarks: Arc<Watermarks>,
+
+    /// 目录列表缓存 | Directory listing cache
+    dirs: Cache<String, dir::Listing>,
+
+    /// 后台任务（如预加载队列）| Background tasks (such as preload queues)
+    tasks: Tasks,
+
+    /// 记住的文件行数，用于跨文件均匀抽样 | Remembered file line counts, for uniform sampling across files
+    line_counts: Cache<String, dir_sample::LineCount>,
+
+    /// 登记为需要维护倒排索引的文件 | Files registered for inverted-index maintenance
+    indexed: FileSet,
+
+    /// 登记为需要维护大小写折叠副本的文件 | Files registered for case-folded copies
+    folded: FileSet,
+
+    /// 加载时的内容处理 | Load-time content processing
+    transforms: Transforms,
+
+    /// 登记为需要统计出现次数的文件 | Files registered for serve counting
+    served: Served,
+
+    /// 反复加载失败而被隔离的文件 | Files quarantined after repeated load failures
+    quarantine: Quarantine,
+
+    /// 内容哈希 → 缓存着该内容的路径 | Content hash → the path caching that content
+    by_content: Cache<u64, String>,
+
+    /// 按路径或模式覆盖的设置 | Settings overridden per path or pattern
+    overrides: FileOverrides,
 
-    /// 按文件路径缓存完整文件内容（用于兼容旧版 API）
-    /// Cache of full file content (for legacy API compatibility)
-    pub contents: Cache<String, String>,
+    /// 测试用同步点 | Test sync points
+    sync_points: SyncPoints,
 
-    /// 文件元数据缓存（修改时间 + 大小），用于自动检测文件变更
-    /// File metadata cache (mtime + size) for automatic change detection
-    metadata: Cache<String, (SystemTime, u64)>,
+    /// 沙箱根目录（已规范化）；为 `None` 时不限制路径
+    /// Sandbox root directory (canonicalized); `None` leaves paths unrestricted
+    root: Option<Arc<std::path::Path>>,
+
+    /// 按操作类型的读取延迟 | Read latencies per operation type
+    latencies: Arc<Latencies>,
+
+    /// stat TTL、按文件 TTL 与访问时间所用的时钟 | Clock behind the stat TTL, per-file TTLs and access times
+    clock: SharedClock,
+
+    /// 单次从磁盘读取文件的时间上限；为 `None` 时不限制
+    /// Time limit of a single file read from disk; `None` leaves it unlimited
+    load_timeout: Option<Duration>,
+
+    /// 瞬时 I/O 错误的重试策略；为 `None` 时不重试 | Retry policy for transient I/O errors; `None` never retries
+    retry: Option<Arc<RetryPolicy>>,
 }
 
+#[cfg(feature = "async")]
 impl AsyncLineCache {
     /// 创建一个推荐用于生产环境的实例
     /// Create a new instance with production-recommended configuration
     ///
-    /// - 总缓存大小限制为系统内存的 85%
-    /// - 行缓存与内容缓存各占一半
+    /// - 总缓存大小限制为系统内存的 85%（容器中以 cgroup 内存上限为准）
     /// - 使用精确的内存权重计算，防止 OOM
-    /// - Total cache size limited to 85% of system memory
-    /// - Lines cache and contents cache each take half
+    ///
+    /// - Total cache size limited to 85% of system memory (the cgroup memory limit inside a container)
     /// - Precise memory weighting to prevent OOM
+    ///
+    /// 无法修改构造调用处时，可以在首次构造缓存之前通过环境变量调整默认值（之后的修改不再生效）：
+    /// When the call sites cannot be changed, environment variables adjust the defaults, read before the first cache is built (later changes have no effect):
+    ///
+    /// - `LINECACHE_MAX_BYTES`：总内存预算（字节），不再查询系统内存
+    /// - `LINECACHE_MEM_PERCENT`：按系统内存百分比设置预算（`0`~`100`）
+    /// - `LINECACHE_STAT_TTL_MS`：stat 微缓存 TTL（毫秒），见 [`with_stat_ttl`](Self::with_stat_ttl)
+    /// - 构造器与配置中明确设置的值优先于环境变量；无法解析的值被忽略
+    ///
+    /// - `LINECACHE_MAX_BYTES`: total memory budget in bytes, without querying system memory
+    /// - `LINECACHE_MEM_PERCENT`: budget as a percentage of system memory (`0`~`100`)
+    /// - `LINECACHE_STAT_TTL_MS`: stat micro-cache TTL in milliseconds, see [`with_stat_ttl`](Self::with_stat_ttl)
+    /// - Values set explicitly on the builder or in a config take precedence over the environment; unparsable values are ignored
+    #[cfg(feature = "sysinfo")]
     pub fn new() -> Self {
-        // 总可用缓存大小 = 系统总内存 × 85%
-        // Total available cache size = system memory × 85%
-        let total_limit = ((*TOTAL_MEMORY as f64) * 0.85) as u64;
-        // 两个主要缓存平分限额
-        // Two main caches split the quota equally
-        let per_cache_limit = total_limit / 2;
-
-        // 计算 Vec<String> 实际占用的内存（基于容量而非长度）
-        // Calculate actual memory usage of Vec<String> (based on capacity, not length)
-        let lines_weigher = |_k: &String, v: &CachedLines| -> u32 {
-            let vec_cap = v.capacity() * std::mem::size_of::<String>();
-            let str_cap: usize = v.iter().map(|s| s.capacity()).sum();
-            let overhead = 128; // 对象头、对齐等保守估计 | conservative estimate for object headers/alignment
-            ((vec_cap + str_cap + overhead) as u64)
-                .min(u32::MAX as u64) as u32
-        };
+        Self::builder().build()
+    }
 
-        // 计算完整文件内容字符串的内存占用
-        // Calculate memory usage of full file content string
-        let content_weigher = |_k: &String, s: &String| -> u32 {
-            (s.capacity() as u64 + 128).min(u32::MAX as u64) as u32
-        };
+    /// 以明确的内存预算（字节）创建实例，完全不查询系统内存
+    /// Create an instance with an explicit memory budget in bytes, never querying system memory
+    ///
+    /// 首次查询系统内存可能耗时数十到数百毫秒；已知预算时（无服务器冷启动、短命的命令行程序）用它跳过这一步。
+    /// The first system memory query can take tens to hundreds of milliseconds; when the budget is known
+    /// (serverless cold starts, short-lived CLI runs), use this to skip it.
+    pub fn new_with_capacity(bytes: u64) -> Self {
+        Self::builder().capacity(bytes).build()
+    }
 
-        Self {
-            // 行缓存：使用精确权重驱逐
-            // Lines cache: precise weight-based eviction
-            lines: CacheBuilder::new(per_cache_limit)
-                .weigher(lines_weigher)
-                .build(),
-            // 内容缓存：同样使用权重
-            // Contents cache: also weighted
-            contents: CacheBuilder::new(per_cache_limit)
-                .weigher(content_weigher)
-                .build(),
-            // 元数据缓存：条目极小，固定 8192 条足够
-            // Metadata cache: entries are tiny, 8192 is more than enough
-            metadata: Cache::new(8192),
-        }
+    /// 注册生命周期回调（加载、命中、未命中、失效、清空与驱逐），替换之前注册的回调
+    /// Register lifecycle callbacks (load, hit, miss, invalidate, clear and evict), replacing any registered before
+    ///
+    /// 需在放入任何条目之前调用：条目缓存会重建以接入驱逐通知。
+    /// Call before inserting any entries: the entry cache is rebuilt to wire up eviction notifications.
+    #[must_use]
+    pub fn with_hooks(mut self, hooks: Arc<dyn CacheHooks>) -> Self {
+        self.hooks = Hooks::new(hooks);
+        self.rebuild_entries();
+        self
+    }
+
+    /// 限制同时加载中的文件总字节数，防止一批大文件同时未命中时在驱逐生效前瞬间超出内存预算
+    /// Limit the total bytes of files being loaded at once, so a burst of misses on large files cannot overshoot the budget before eviction reacts
+    ///
+    /// - 默认等于缓存的内存预算；超出额度的加载会排队等待
+    /// - 单个大于该上限的文件仍可加载，只是加载期间独占闸门
+    ///
+    /// - Defaults to the cache's memory budget; loads beyond it queue up
+    /// - A single file larger than the limit still loads, just with the gate to itself
+    #[must_use]
+    pub fn with_load_memory_limit(self, bytes: u64) -> Self {
+        self.load_gate.set_limit(bytes);
+        self
+    }
+
+    /// 启用 stat 结果微缓存：同一文件在 `ttl` 内的重复访问只做一次元数据系统调用
+    /// Enable stat-result micro-caching: repeated accesses to a file within `ttl` share one metadata syscall
+    ///
+    /// - 推荐 50~500ms；代价是文件变更最多延迟 `ttl` 才被发现
+    /// - `Duration::ZERO` 关闭微缓存（默认），每次访问都会 stat
+    ///
+    /// - 50~500ms is recommended; the trade-off is that changes may go unnoticed for up to `ttl`
+    /// - `Duration::ZERO` disables it (the default), so every access stats the file
+    #[must_use]
+    pub fn with_stat_ttl(self, ttl: Duration) -> Self {
+        self.recent_stats.set_ttl(ttl);
+        self
+    }
+
+    /// 开启或关闭已缓存文件的变更检测（默认开启）
+    /// Turn change detection for cached files on or off (on by default)
+    ///
+    /// - 关闭后命中不再 stat，条目一直使用到被 [`invalidate`](Self::invalidate)、驱逐或清空为止；适合内容不会变化的语料
+    /// - 文件首次加载或被驱逐后重新加载时仍然读取磁盘
+    ///
+    /// - With it off, hits no longer stat the file, and an entry is used until it is [`invalidate`](Self::invalidate)d, evicted or cleared; suited to corpora that never change
+    /// - A file's first load, and reloads after eviction, still read the disk
+    #[must_use]
+    pub fn with_revalidation(self, enabled: bool) -> Self {
+        self.recent_stats.set_revalidate(enabled);
+        self
+    }
+
+    /// 只从缓存同步读取第 `lineno` 行（从 1 开始），从不做 IO，也从不等待
+    /// Read the `lineno`-th line (1-based) from the cache only, synchronously, never doing IO or waiting
+    ///
+    /// - 文件未缓存、行号超出范围，或缓存暂时无法立即应答时返回 `None`，调用方可以退回默认值或改用 [`get_line`](Self::get_line)
+    /// - 不做变更检测：返回的可能是文件修改前的内容，直到下一次异步访问发现变更为止
+    ///
+    /// - Returns `None` when the file is not cached, the line is out of range, or the cache cannot answer at once, so callers can fall back to a default or to [`get_line`](Self::get_line)
+    /// - No change detection is done: the line may predate an edit of the file until the next async access notices it
+    pub fn try_get_line(&self, filename: &str, lineno: usize) -> Option<String> {
+        let entry = poll_now(self.entries.get(filename))??;
+        eviction::touch(&entry, self.clock.now());
+        entry.lines().get(lineno.wrapping_sub(1)).map(String::from)
+    }
+
+    /// 文件的行数，即 [`get_line`](Self::get_line) 能返回内容的最大行号；文件不存在时为 0
+    /// Number of lines in the file, i.e. the largest line number [`get_line`](Self::get_line) answers; 0 when the file does not exist
+    ///
+    /// 与 `get_line` 一致，以换行符结尾的文件末尾的兼容空行也计入。
+    /// As with `get_line`, the compatibility empty line after a final newline counts too.
+    pub async fn line_count(&self, filename: &str) -> std::io::Result<usize> {
+        Ok(self.fresh_lines(filename).await?.len())
+    }
+
+    /// 只从缓存同步读取文件的行数，从不做 IO，也不做变更检测；文件未缓存时返回 `None`，见 [`try_get_line`](Self::try_get_line)
+    /// The file's line count from the cache only, synchronously, without IO or change detection; `None` when the file is not cached, see [`try_get_line`](Self::try_get_line)
+    pub fn try_line_count(&self, filename: &str) -> Option<usize> {
+        let entry = poll_now(self.entries.get(filename))??;
+        eviction::touch(&entry, self.clock.now());
+        Some(entry.lines().len())
+    }
+
+    /// 文件此刻是否已缓存；只查询缓存，从不加载，也不做变更检测
+    /// Whether the file is cached right now; consults the cache only, never loading and never checking for changes
+    pub fn contains(&self, filename: &str) -> bool {
+        self.entries.contains_key(filename)
+    }
+
+    /// 已缓存的行（可能早于文件的最近一次修改）；文件未缓存时返回 `None`，从不加载
+    /// The cached lines, which may predate the file's latest edit; `None` when the file is not cached, never loading
+    ///
+    /// 供监控代码使用：不做 IO，不触发命中回调，也不刷新 [`eviction_candidates`](Self::eviction_candidates) 所用的访问时刻。
+    /// 但底层缓存仍把它记为一次读取，与普通访问一样刷新条目在驱逐策略中的新近度与频率，被查看的条目因此更不容易被驱逐。
+    /// Meant for monitoring code: no IO is done, no hit hook fires, and the access time behind [`eviction_candidates`](Self::eviction_candidates) is left as is.
+    /// The underlying cache still records it as a read, though, refreshing the entry's recency and frequency in the eviction policy like any access, so peeked entries become less likely to be evicted.
+    pub fn peek_lines(&self, filename: &str) -> Option<CachedLines> {
+        self.peek_entry(filename).map(|entry| entry.lines().clone())
+    }
+
+    /// 已缓存的完整条目（行、原始内容与元数据）；文件未缓存时返回 `None`
+    /// The whole cached entry (lines, raw content and metadata); `None` when the file is not cached
+    ///
+    /// 与 [`peek_lines`](Self::peek_lines) 一样不做 IO、不触发回调，但仍在驱逐策略中记为一次读取。
+    /// Like [`peek_lines`](Self::peek_lines), it does no IO and fires no hook, but still counts as a read in the eviction policy.
+    pub fn peek_entry(&self, filename: &str) -> Option<FileEntry> {
+        poll_now(self.entries.get(filename))?
+    }
+
+    /// 立即执行条目缓存积压的维护工作（驱逐、移除通知与加权占用的更新），之后 [`memory_usage`](Self::memory_usage) 与 [`quota_usage`](Self::quota_usage) 不再滞后
+    /// Run the entry cache's pending maintenance (evictions, removal notices and weighted-size updates) now, after which [`memory_usage`](Self::memory_usage) and [`quota_usage`](Self::quota_usage) no longer lag
+    pub async fn run_pending_tasks(&self) {
+        self.entries.run_pending_tasks().await;
     }
 
     /// 获取指定文件的第 `lineno` 行（从 1 开始计数）
@@ -119,36 +463,75 @@ impl AsyncLineCache {
     /// - `Ok(Some(line))`：成功获取行
     /// - `Ok(None)`：行号超出范围或空文件
     /// - `Err(io_error)`：IO 错误
+    ///
     /// Return value:
     /// - `Ok(Some(line))`: line retrieved successfully
     /// - `Ok(None)`: line number out of range or empty file
     /// - `Err(io_error)`: I/O error
     pub async fn get_line(&self, filename: &str, lineno: usize) -> std::io::Result<Option<String>> {
-        if self.is_file_modified(filename).await? {
-            self.invalidate(filename).await;
-        }
-        let lines = self.load_or_get_lines(filename).await?;
-        Ok(lines.get(lineno.wrapping_sub(1)).cloned())
+        let lines = self.fresh_lines(filename).await?;
+        Ok(lines.get(lineno.wrapping_sub(1)).map(String::from))
     }
 
     /// 随机返回文件中任意一行（零分配，极快）
     /// Randomly return any line from the file (zero allocation, extremely fast)
     pub async fn random_line(&self, filename: &str) -> std::io::Result<Option<String>> {
-        if self.is_file_modified(filename).await? {
-            self.invalidate(filename).await;
+        let Some(entry) = self.fresh_entry(filename).await? else { return Ok(None) };
+        let Some(idx) = random_index(entry.lines()) else { return Ok(None) };
+        self.served.record(filename, &entry, [idx]);
+        Ok(entry.lines().get(idx).map(String::from))
+    }
+
+    /// 随机返回一行未被 `excluded` 排除的行及其行号（从 1 开始），不复制文件做过滤
+    /// Return a random line not excluded by `excluded`, with its 1-based number, without copying the file to filter it
+    ///
+    /// - 先做少量拒绝采样（排除项较少时几乎总能命中），都被排除时再扫描一遍剩余行均匀抽取
+    /// - 所有行都被排除、文件为空或不存在时返回 `None`
+    ///
+    /// - Starts with a few rejection-sampling rounds (almost always enough when few lines are excluded), then scans the remaining lines and picks uniformly
+    /// - Returns `None` when every line is excluded, or the file is empty or missing
+    pub async fn random_line_excluding(
+        &self,
+        filename: &str,
+        excluded: &ExclusionSet,
+    ) -> std::io::Result<Option<(usize, String)>> {
+        const REJECTION_ROUNDS: usize = 32;
+
+        let Some(entry) = self.fresh_entry(filename).await? else { return Ok(None) };
+        let lines = entry.lines();
+        if lines.is_empty() {
+            return Ok(None);
         }
-        if let Some(lines) = self.lines.get(filename).await {
-            if lines.is_empty() {
-                Ok(None)
-            } else {
-                Ok(lines.choose(&mut rand::thread_rng()).cloned())
-            }
-        } else {
-            // 缓存未命中时触发加载
-            // Trigger loading when cache miss
-            let lines = self.load_or_get_lines(filename).await?;
-            Ok(lines.choose(&mut rand::thread_rng()).cloned())
+        let mut rng = rand::thread_rng();
+        let allowed = |idx: usize| lines.get(idx).filter(|line| !excluded.contains(idx + 1, line));
+
+        let picked = (0..REJECTION_ROUNDS)
+            .map(|_| rng.gen_range(0..lines.len()))
+            .find(|&idx| allowed(idx).is_some())
+            .or_else(|| {
+                let remaining: Vec<usize> = (0..lines.len()).filter(|&idx| allowed(idx).is_some()).collect();
+                remaining.choose(&mut rng).copied()
+            });
+        let Some(idx) = picked else { return Ok(None) };
+        self.served.record(filename, &entry, [idx]);
+        Ok(allowed(idx).map(|line| (idx + 1, line.to_string())))
+    }
+
+    /// 有放回地随机抽取 `n` 行，整批只做一次变更检测与缓存查找
+    /// Sample `n` lines with replacement, sharing one change check and cache lookup across the batch
+    ///
+    /// 空文件、文件不存在或 `n` 为 0 时返回空列表。
+    /// Returns an empty list for an empty or missing file, or when `n` is 0.
+    pub async fn random_lines(&self, filename: &str, n: usize) -> std::io::Result<Vec<String>> {
+        let Some(entry) = self.fresh_entry(filename).await? else { return Ok(Vec::new()) };
+        let lines = entry.lines();
+        if lines.is_empty() {
+            return Ok(Vec::new());
         }
+        let mut rng = rand::thread_rng();
+        let picked: Vec<usize> = (0..n).map(|_| rng.gen_range(0..lines.len())).collect();
+        self.served.record(filename, &entry, picked.iter().copied());
+        Ok(picked.into_iter().filter_map(|idx| lines.get(idx).map(String::from)).collect())
     }
 
     /// 随机返回文件中任意一个 Unicode 字符（正确按码点切分）
@@ -165,67 +548,414 @@ impl AsyncLineCache {
         Ok(self.random_sign_char(filename).await?.map(|c| c.to_string()))
     }
 
-    /// 获取文件全部行（完全兼容旧版 DashMap 实现）
-    /// Get all lines of the file (fully compatible with legacy DashMap implementation)
+    /// 获取文件全部行（完全兼容旧版 `DashMap` 实现）
+    /// Get all lines of the file (fully compatible with legacy `DashMap` implementation)
     ///
     /// - 空文件返回 `None`（与 Python linecache 行为一致）
     /// - Empty file returns `None` (same as Python linecache)
     pub async fn get_lines(&self, filename: &str) -> std::io::Result<Option<Vec<String>>> {
-        if self.is_file_modified(filename).await? {
-            self.invalidate(filename).await;
-        }
-        let lines = self.load_or_get_lines(filename).await?;
+        let lines = self.fresh_lines(filename).await?;
         if lines.is_empty() {
             Ok(None)
         } else {
-            Ok(Some((*lines).clone())) // Arc 解引用后 clone 出 owned Vec
+            Ok(Some(lines.to_vec())) // 从共享缓冲区复制出 owned Vec
         }
     }
 
+    /// 同 [`get_lines`](Self::get_lines)，但文件不存在时可通过加载器获取源码（对应 Python 的 `getlines(filename, module_globals)`）
+    /// Same as [`get_lines`](Self::get_lines), but falls back to a loader when the file is missing (Python's `getlines(filename, module_globals)`)
+    ///
+    /// - 优先使用传入的 `loader`，否则使用 [`register_loader`](Self::register_loader) 为该文件注册的加载器
+    /// - 通过加载器得到的条目不做 mtime 校验，直到被 `invalidate` / `clear` 清除
+    ///
+    /// - The given `loader` takes precedence, otherwise the one registered via [`register_loader`](Self::register_loader) is used
+    /// - Entries obtained from a loader are never mtime-checked until removed by `invalidate` / `clear`
+    pub async fn get_lines_with_loader(
+        &self,
+        filename: &str,
+        loader: Option<&dyn SourceLoader>,
+    ) -> std::io::Result<Option<Vec<String>>> {
+        let mut entry = self.fresh_entry(filename).await?;
+        if entry.is_none() {
+            // 文件不存在（而不是空文件）时才询问加载器
+            // Only ask the loader when the file is missing (not merely empty)
+            if let Some(loader) = loader {
+                let _guard = self.locks.lock(filename).await;
+                entry = self.load_from_loader(filename, loader).await?;
+            }
+        }
+        Ok(entry.map(|e| e.lines().to_vec()).filter(|lines| !lines.is_empty()))
+    }
+
+    /// 为指定文件名注册源码加载器；该文件在磁盘上不存在时由它提供内容
+    /// Register a source loader for a filename; it provides the content whenever the file is missing on disk
+    pub fn register_loader(&self, filename: &str, loader: Arc<dyn SourceLoader>) {
+        self.loaders.register(filename, loader);
+    }
+
+    /// 为指定文件名注册异步惰性加载器（对应 Python 的 `linecache.lazycache`）；首次访问时才调用它产生源码
+    /// Register an async lazy loader for a filename (Python's `linecache.lazycache`); it is only called for the source on first access
+    ///
+    /// - 与 [`register_loader`](Self::register_loader) 相同，只在磁盘上不存在该文件时使用；产生的内容作为虚拟条目缓存，不做 mtime 校验
+    /// - 条目被驱逐或失效后，下次访问会再次调用加载器；同时注册了同步加载器时以同步加载器为准
+    ///
+    /// - Like [`register_loader`](Self::register_loader), it is only used while the file is missing on disk; the content is cached as a virtual entry, never mtime-checked
+    /// - After the entry is evicted or invalidated, the next access calls the loader again; a sync loader registered for the same name takes precedence
+    pub fn register_lazy<F, Fut>(&self, filename: &str, loader: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = std::io::Result<String>> + Send + 'static,
+    {
+        self.loaders.register_lazy(filename, Arc::new(move || Box::pin(loader())));
+    }
+
+    /// 取消注册指定文件名的加载器（包括惰性加载器），返回之前是否已注册
+    /// Unregister the loaders of a filename (lazy ones included), returning whether any was registered
+    pub fn unregister_loader(&self, filename: &str) -> bool {
+        self.loaders.unregister(filename)
+    }
+
+    /// 为以 `prefix` 开头的所有文件名（如 `"<frozen "` 或 URI 方案 `"mem://"`）设置加载器，替换同一前缀之前的设置
+    /// Set the loader for every filename starting with `prefix` (such as `"<frozen "` or the URI scheme `"mem://"`), replacing an earlier one for the same prefix
+    ///
+    /// - 与 [`register_loader`](Self::register_loader) 相同，只在磁盘上不存在该文件时使用；按文件名注册的加载器优先
+    /// - 多个前缀都匹配时使用最长的一个
+    ///
+    /// - Like [`register_loader`](Self::register_loader), it is only used while the file is missing on disk; loaders registered for the exact filename take precedence
+    /// - When several prefixes match, the longest one is used
+    pub fn set_loader(&self, prefix: &str, loader: Arc<dyn SourceLoader>) {
+        self.loaders.set_prefix(prefix, loader);
+    }
+
+    /// 移除 `prefix` 的加载器，返回之前是否设置过 | Remove the loader of `prefix`, returning whether one was set
+    pub fn unset_loader(&self, prefix: &str) -> bool {
+        self.loaders.unset_prefix(prefix)
+    }
+
+    /// 以给定文本直接建立文件名的条目，磁盘上无需存在该文件（REPL 片段、生成的模板等）
+    /// Build the filename's entry straight from the given text, with no file needed on disk (REPL snippets, generated templates, ...)
+    ///
+    /// - 条目是虚拟的：从不做 mtime 校验，同名的磁盘文件在条目失效之前被遮蔽；加载时处理与水位线照常生效
+    /// - 与其他条目一样可能被驱逐，之后按普通文件重新解析；需要长期可用时改用 [`register_lazy`](Self::register_lazy)
+    ///
+    /// - The entry is virtual: never mtime-checked, and a disk file of the same name is shadowed until the entry is invalidated; load-time processing and watermarks apply as usual
+    /// - It can be evicted like any other entry, after which the name resolves as an ordinary file again; use [`register_lazy`](Self::register_lazy) when it must stay available
+    pub async fn insert_virtual(&self, filename: &str, content: impl Into<String>) -> std::io::Result<()> {
+        let _guard = self.locks.lock(filename).await;
+        let shadows = tokio::fs::try_exists(self.resolve(filename)).await.unwrap_or(false);
+        let source = if shadows { entry::Source::Override } else { entry::Source::Virtual };
+        let entry = self.build_entry(filename, content.into(), Stamp::Virtual)?.with_source(source);
+        self.publish(filename, Some(entry)).await
+    }
+
     /// 获取文件完整内容（兼容旧版 API）
     /// Get full file content (compatible with legacy API)
     ///
     /// - 文件不存在返回 `None`
     /// - File not found returns `None`
     pub async fn get_content(&self, filename: &str) -> std::io::Result<Option<String>> {
-        if self.is_file_modified(filename).await? {
-            self.invalidate(filename).await;
+        // 内容直接取自条目的行缓冲区 | the content comes straight from the entry's line buffer
+        Ok(self.fresh_entry(filename).await?.map(|entry| entry.raw().to_string()))
+    }
+
+    /// 获取第 `start` 到第 `end` 行（从 1 开始，闭区间）拼接成的原始文本片段
+    /// Get the raw text snippet of lines `start..=end` (1-based, inclusive)
+    ///
+    /// - 行与行之间保留文件中的原始换行符（`\n` 或 `\r\n`），末行之后不带换行符
+    /// - 区间会被截断到文件实际行数；区间为空或越界时返回 `None`
+    ///
+    /// - Lines are joined with their original separators (`\n` or `\r\n`), without a trailing one
+    /// - The range is clamped to the file; an empty or out-of-range span returns `None`
+    pub async fn get_span_text(&self, filename: &str, start: usize, end: usize) -> std::io::Result<Option<String>> {
+        let lines = self.fresh_lines(filename).await?;
+        let start = start.max(1);
+        let end = end.min(lines.len());
+        if start > end {
+            return Ok(None);
+        }
+        // 直接从连续缓冲区截取 | sliced straight from the contiguous buffer
+        Ok(Some(lines.span_text(start - 1, end).to_string()))
+    }
+
+    /// 获取第 `start` 到第 `end` 行（从 1 开始，闭区间）的零拷贝视图，适合渲染堆栈跟踪的上下文
+    /// Get a zero-copy view of lines `start..=end` (1-based, inclusive), e.g. for rendering stack-trace context
+    ///
+    /// - 区间会被截断到文件实际行数；区间为空、越界或文件不存在时返回空视图
+    /// - 视图直接引用缓存的行缓冲区，不复制任何行
+    ///
+    /// - The range is clamped to the file; an empty or out-of-range span, or a missing file, yields an empty view
+    /// - The view refers straight to the cached line buffer; no line is copied
+    pub async fn get_lines_range(&self, filename: &str, start: usize, end: usize) -> std::io::Result<LineSlice> {
+        let lines = self.fresh_lines(filename).await?;
+        let end = end.min(lines.len());
+        let start = start.max(1).min(end + 1);
+        Ok(LineSlice::new(lines, start - 1, end))
+    }
+
+    /// 文件的前 `n` 行（零拷贝视图）；不足 `n` 行时返回全部行
+    /// The first `n` lines of the file as a zero-copy view; every line when there are fewer than `n`
+    ///
+    /// 末尾换行符产生的兼容空行不计入，与 `head` 命令一致。
+    /// The compatibility empty line after a final newline is not counted, as with the `head` command.
+    pub async fn head(&self, filename: &str, n: usize) -> std::io::Result<LineSlice> {
+        let lines = self.fresh_lines(filename).await?;
+        let end = n.min(lines.content_len());
+        Ok(LineSlice::new(lines, 0, end))
+    }
+
+    /// 文件的最后 `n` 行（零拷贝视图）；不足 `n` 行时返回全部行
+    /// The last `n` lines of the file as a zero-copy view; every line when there are fewer than `n`
+    ///
+    /// 末尾换行符产生的兼容空行不计入，因此以换行符结尾的日志返回的是最后 `n` 条真实记录，与 `tail` 命令一致。
+    /// The compatibility empty line after a final newline is not counted, so a newline-terminated log yields its last `n` real records, as with the `tail` command.
+    pub async fn tail(&self, filename: &str, n: usize) -> std::io::Result<LineSlice> {
+        let lines = self.fresh_lines(filename).await?;
+        let end = lines.content_len();
+        Ok(LineSlice::new(lines, end.saturating_sub(n), end))
+    }
+
+    /// 以 `n` 行为窗口、步长为 1，遍历文件中相互重叠的行窗口
+    /// Iterate over overlapping `n`-line windows of the file with a stride of 1
+    ///
+    /// - 每个窗口都是共享缓存行的零拷贝 [`LineSlice`]，无需克隆整个行向量
+    /// - `n` 为 0 或大于总行数时不产生任何窗口
+    ///
+    /// - Every window is a zero-copy [`LineSlice`] over the cached lines; the full vector is never cloned
+    /// - Yields no windows when `n` is 0 or larger than the line count
+    pub async fn windows(&self, filename: &str, n: usize) -> std::io::Result<LineWindows> {
+        let lines = self.fresh_lines(filename).await?;
+        Ok(LineWindows::new(lines, n))
+    }
+
+    /// 按顺序遍历文件的 `(行号, 行内容)`，行号从 1 开始，与 `get_line` 的编号一致
+    /// Iterate over `(lineno, line)` pairs of the file in order, numbered from 1 like `get_line`
+    pub async fn numbered_lines(&self, filename: &str) -> std::io::Result<NumberedLines> {
+        let lines = self.fresh_lines(filename).await?;
+        Ok(NumberedLines::new(lines))
+    }
+
+    /// 按页读取文件：返回第 `page_no` 页（从 1 开始，每页 `page_size` 行）的零拷贝视图与总页数
+    /// Read the file by page: returns a zero-copy view of page `page_no` (1-based, `page_size` lines each) with the total page count
+    ///
+    /// - 页码被限制在 `1..=总页数` 内：0 视为第 1 页，超出末页时返回末页
+    /// - 文件为空、不存在或 `page_size` 为 0 时返回空视图与 0 页
+    ///
+    /// - The page number is clamped to `1..=total pages`: 0 means the first page, and anything past the end returns the last page
+    /// - An empty or missing file, or a `page_size` of 0, returns an empty view and 0 pages
+    pub async fn page(&self, filename: &str, page_no: usize, page_size: usize) -> std::io::Result<(LineSlice, usize)> {
+        let lines = self.fresh_lines(filename).await?;
+        let total = lines.len();
+        if page_size == 0 || total == 0 {
+            return Ok((LineSlice::new(lines, 0, 0), 0));
         }
+        let pages = total.div_ceil(page_size);
+        let start = (page_no.clamp(1, pages) - 1) * page_size;
+        let end = (start + page_size).min(total);
+        Ok((LineSlice::new(lines, start, end), pages))
+    }
 
-        let key = filename.to_string();
+    /// 返回位于相对位置 `fraction`（`0.0` 为首行，`1.0` 为末行，`0.5` 为中位行）的行及其行号（从 1 开始）
+    /// Return the line at relative position `fraction` (`0.0` is the first line, `1.0` the last, `0.5` the median) with its 1-based number
+    ///
+    /// `fraction` 被限制在 `0.0..=1.0` 内，位置四舍五入到最近的行；文件为空、不存在或 `fraction` 为 NaN 时返回 `None`。
+    /// `fraction` is clamped to `0.0..=1.0` and the position rounds to the nearest line; returns `None` for an empty or missing file, or a NaN `fraction`.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    pub async fn line_at_fraction(&self, filename: &str, fraction: f64) -> std::io::Result<Option<(usize, String)>> {
+        let lines = self.fresh_lines(filename).await?;
+        let len = lines.content_len();
+        if len == 0 || fraction.is_nan() {
+            return Ok(None);
+        }
+        let idx = (fraction.clamp(0.0, 1.0) * (len - 1) as f64).round() as usize;
+        Ok(lines.get(idx).map(|line| (idx + 1, line.to_string())))
+    }
 
-        if let Some(content) = self.contents.get(&key).await {
-            return Ok(Some(content));
+    /// 将文件的行切分为 `parts` 个互不相交、行数大致相等的区间，便于把同一文件分发给多个 worker
+    /// Split the file's lines into `parts` disjoint ranges of roughly equal line count, for fanning one file out to workers
+    ///
+    /// - 区间为从 1 开始的左闭右开行号（如 `1..4` 表示第 1~3 行），按顺序首尾相接覆盖全部行
+    /// - 行数少于 `parts` 时部分区间为空；`parts` 为 0 时返回空列表
+    ///
+    /// - Ranges are 1-based, half-open line numbers (`1..4` means lines 1~3), contiguous and covering every line
+    /// - Some ranges are empty when there are fewer lines than `parts`; `parts == 0` returns an empty list
+    pub async fn partition(&self, filename: &str, parts: usize) -> std::io::Result<Vec<std::ops::Range<usize>>> {
+        let lines = self.fresh_lines(filename).await?;
+        let total = lines.len();
+        Ok((0..parts)
+            .map(|i| (i * total / parts + 1)..((i + 1) * total / parts + 1))
+            .collect())
+    }
+
+    /// 同 [`partition`](Self::partition)，但按字节数（行长 + 换行符）而不是行数均分
+    /// Same as [`partition`](Self::partition), but balances by bytes (line length + separator) instead of line count
+    pub async fn partition_by_bytes(&self, filename: &str, parts: usize) -> std::io::Result<Vec<std::ops::Range<usize>>> {
+        let lines = self.fresh_lines(filename).await?;
+        if parts == 0 {
+            return Ok(Vec::new());
         }
+        let total_bytes: usize = lines.iter().map(|l| l.len() + 1).sum();
 
-        match tokio::fs::read_to_string(filename).await {
-            Ok(content) => {
-                self.contents.insert(key.clone(), content.clone()).await;
-                Ok(Some(content))
+        // 每个切分点取累计字节数首次达到目标值的位置
+        // Each cut lands where the cumulative byte count first reaches its target
+        let mut cuts = Vec::with_capacity(parts + 1);
+        cuts.push(0);
+        let mut acc = 0;
+        let mut idx = 0;
+        for i in 1..parts {
+            let target = i * total_bytes / parts;
+            while idx < lines.len() && acc < target {
+                acc += lines.get(idx).map_or(0, str::len) + 1;
+                idx += 1;
             }
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                self.invalidate(filename).await;
-                Ok(None)
-            }
-            Err(e) => Err(e),
+            cuts.push(idx);
         }
+        cuts.push(lines.len());
+
+        Ok(cuts.windows(2).map(|w| (w[0] + 1)..(w[1] + 1)).collect())
+    }
+
+    /// 强制重新加载文件并返回最新的行（对应 Python 的 `linecache.updatecache`）
+    /// Force-reload the file and return the fresh lines (Python's `linecache.updatecache`)
+    ///
+    /// - 不做变更检测，无条件重新读取；新条目直接覆盖旧条目，读者不会看到缓存缺失的间隙
+    /// - 文件不存在时会询问已注册的加载器；仍无法获取则清除该文件的条目并返回空行列表
+    ///
+    /// - Skips change detection and always re-reads; the new entry overwrites the old one, so readers never observe a gap
+    /// - Consults the registered loader when the file is missing; if that fails too, the file's entry is dropped and an empty list is returned
+    pub async fn updatecache(&self, filename: &str) -> std::io::Result<CachedLines> {
+        let _guard = self.locks.lock(filename).await;
+        let entry = self.load_file_into_cache(filename).await?;
+        Ok(entry.map_or_else(Default::default, |e| e.lines().clone()))
     }
 
-    /// 手动使指定文件的所有缓存失效
-    /// Manually invalidate all caches for a specific file
+    /// 手动使指定文件（或目录列表）的缓存条目失效；已缓存的元数据旁路文件一并失效
+    /// Manually invalidate the cached entry of a specific file (or directory listing), along with its metadata sidecar if cached
+    ///
+    /// 会等待该文件正在进行的加载完成，因此失效之后不会再被旧数据重新填充。
+    /// Waits for any in-flight load of the file, so stale data cannot repopulate it after invalidation.
     pub async fn invalidate(&self, filename: &str) {
-        let key = filename.to_string();
-        self.lines.remove(&key).await;
-        self.contents.remove(&key).await;
-        self.metadata.remove(&key).await;
+        self.invalidate_key(filename).await;
+        let sidecar = sidecar::sidecar_path(filename);
+        if self.entries.contains_key(&sidecar) {
+            self.invalidate_key(&sidecar).await;
+        }
+    }
+
+    /// 一次性使一组相关文件失效：读者要么看到整组的旧版本，要么在失效完成后重新加载，不会看到新旧混杂的组合
+    /// Invalidate a set of related files as one step: readers either see the whole old set or reload after the invalidation, never a mix of old and new
+    ///
+    /// 先按固定顺序取得所有成员的加载锁再逐个移除，期间未命中的读取会等待整组失效完成；已缓存的元数据旁路文件一并失效。
+    /// Every member's load lock is taken in a fixed order before any removal, so misses in the meantime wait for the whole group; cached metadata sidecars are dropped too.
+    pub async fn invalidate_group(&self, files: &[&str]) {
+        let sidecars: Vec<String> = files
+            .iter()
+            .map(|filename| sidecar::sidecar_path(filename))
+            .filter(|sidecar| self.entries.contains_key(sidecar))
+            .collect();
+        let keys: Vec<&str> = files.iter().copied().chain(sidecars.iter().map(String::as_str)).collect();
+        let _guards = self.locks.lock_many(&keys).await;
+        for filename in &keys {
+            self.sync_points.reach(SyncPoint::Invalidate, filename).await;
+            self.remove_entry(filename).await;
+            self.dirs.remove(*filename).await;
+        }
+        if let Some(hooks) = self.hooks.get() {
+            for filename in &keys {
+                hooks.on_invalidate(filename);
+            }
+        }
+    }
+
+    /// 使单个键失效 | Invalidate a single key
+    async fn invalidate_key(&self, filename: &str) {
+        let _guard = self.locks.lock(filename).await;
+        self.sync_points.reach(SyncPoint::Invalidate, filename).await;
+        self.remove_entry(filename).await;
+        self.dirs.remove(filename).await;
+        if let Some(hooks) = self.hooks.get() {
+            hooks.on_invalidate(filename);
+        }
     }
 
-    /// 清空全部缓存（三个缓存全部清除）
-    /// Clear all caches completely
+    /// 清空全部缓存（等待所有正在进行的加载完成后再清空）
+    /// Clear all caches completely (after every in-flight load has finished)
+    ///
+    /// 在 [`child`](Self::child) 创建的作用域子缓存上调用时，只移除该子缓存引入的键。
+    /// On a scoped child created by [`child`](Self::child), only the keys introduced by that child are removed.
     pub async fn clear(&self) {
-        self.lines.invalidate_all();
-        self.contents.invalidate_all();
-        self.metadata.invalidate_all();
+        if self.clear_scope().await {
+            return;
+        }
+        let _guards = self.locks.lock_all().await;
+        self.entries.invalidate_all();
+        self.dirs.invalidate_all();
+        self.line_counts.invalidate_all();
+        self.by_content.invalidate_all();
+        self.recent_stats.clear();
+        if let Some(hooks) = self.hooks.get() {
+            hooks.on_clear();
+        }
+    }
+
+    /// 当前缓存条目的加权内存占用（字节）；驱逐在后台维护中完成，数值可能短暂滞后
+    /// Weighted memory usage of the cached entries in bytes; eviction runs as background maintenance, so the figure may briefly lag
+    pub fn memory_usage(&self) -> u64 {
+        self.entries.weighted_size()
+    }
+
+    /// 当前缓存的全部文件名（按字典序）；遍历条目但不计为访问，不影响驱逐顺序
+    /// Every cached filename, in lexicographic order; entries are iterated without counting as accesses, leaving the eviction order alone
+    pub fn list_cached(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.entries.iter().map(|(key, _)| key.to_string()).collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    /// 同 [`list_cached`](Self::list_cached)，并附带每个条目的权重（字节），即它计入 [`memory_usage`](Self::memory_usage) 的大小
+    /// Same as [`list_cached`](Self::list_cached), with each entry's weight in bytes, i.e. what it counts towards [`memory_usage`](Self::memory_usage)
+    pub fn list_cached_weighted(&self) -> Vec<(String, u64)> {
+        let mut entries: Vec<(String, u64)> = self
+            .entries
+            .iter()
+            .map(|(key, entry)| {
+                let weight = self.weighing.entry_weight(&key, &entry);
+                (key.to_string(), weight)
+            })
+            .collect();
+        entries.sort_unstable();
+        entries
+    }
+
+    /// 条目缓存的总内存预算（字节），包括 [`set_max_capacity`](Self::set_max_capacity) 设置的运行时上限
+    /// Total memory budget of the entry cache in bytes, including a runtime limit set by [`set_max_capacity`](Self::set_max_capacity)
+    pub fn memory_budget(&self) -> u64 {
+        self.watermarks.capacity().unwrap_or(self.budget)
+    }
+
+    /// 优雅关闭：停止后台任务（如预加载），等待所有正在进行的加载完成，并送达所有待处理的移除通知（配额退还、驱逐回调）后返回
+    /// Graceful shutdown: stops background tasks (such as preloading), waits for every in-flight load and delivers every pending removal notice (quota refunds, eviction callbacks) before returning
+    ///
+    /// - 同样作用于由本缓存创建的所有命名空间
+    /// - 缓存没有待写入的快照，因此没有需要刷写的内容；返回后缓存仍可继续使用
+    ///
+    /// - Also applies to every namespace created from this cache
+    /// - The cache has no pending snapshot writes, so there is nothing to flush; the cache stays usable afterwards
+    pub async fn shutdown(&self) {
+        let mut pending = vec![self.clone()];
+        while let Some(cache) = pending.pop() {
+            pending.extend(cache.namespace_handles());
+            cache.tasks.stop().await;
+            let _guards = cache.locks.lock_all().await;
+            cache.entries.run_pending_tasks().await;
+        }
+    }
+
+    /// 文件当前版本的代号，每次重新加载（变更、失效或驱逐之后）严格递增；长时间运行的任务可据此发现中途的内容变化
+    /// Generation of the file's current version, strictly increasing with every reload (after a change, invalidation or eviction); long-running jobs can compare it to detect mid-job content changes
+    ///
+    /// 文件不存在且没有加载器可用时返回 `None`。| Returns `None` when the file is missing and no loader helps.
+    pub async fn generation(&self, filename: &str) -> std::io::Result<Option<u64>> {
+        Ok(self.fresh_entry(filename).await?.map(|entry| entry.generation()))
     }
 
     /// 兼容旧版方法名（已废弃，仅为平滑升级保留）
@@ -237,79 +967,336 @@ impl AsyncLineCache {
 
     // ====================== 内部私有方法 | Internal private methods ======================
 
-    /// 获取缓存中的行向量，若不存在则加载并缓存
-    /// Get cached lines; load and cache the file if not present
-    async fn load_or_get_lines(&self, filename: &str) -> std::io::Result<CachedLines> {
-        let key = filename.to_string();
-        if let Some(lines) = self.lines.get(&key).await {
-            return Ok(lines);
-        }
-        self.load_file_into_cache(filename).await
+    /// 以当前的预算、权重方式、回调与配额重建条目缓存（仅限构造阶段使用，已有条目会丢失）
+    /// Rebuild the entry cache with the current budget, weighing and hooks (construction time only; entries are dropped)
+    fn rebuild_entries(&mut self) {
+        self.entries = weigh::entries_cache(self.budget, &self.weighing, self.policy, &self.hooks, &self.quotas);
     }
 
-    /// 核心加载逻辑：读取文件 → 按行拆分 → 写入缓存
-    /// Core loading logic: read file → split into lines → insert into caches
-    async fn load_file_into_cache(&self, filename: &str) -> std::io::Result<CachedLines> {
-        let file = match File::open(filename).await {
-            Ok(f) => f,
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                self.invalidate(filename).await;
-                return Ok(Arc::new(vec![]));
-            }
-            Err(e) => return Err(e),
-        };
+    /// 先做变更检测，未变更且已缓存时直接返回，否则重新加载；文件不存在时返回空行
+    /// Run change detection; return the cached lines if unchanged, otherwise reload; a missing file yields no lines
+    async fn fresh_lines(&self, filename: &str) -> std::io::Result<CachedLines> {
+        let entry = self.fresh_entry(filename).await?;
+        Ok(entry.map_or_else(Default::default, |e| e.lines().clone()))
+    }
 
-        let meta = tokio::fs::metadata(filename).await?;
-        let mut reader = BufReader::new(file);
-        let mut content = String::with_capacity(meta.len() as usize + 1);
-        reader.read_to_string(&mut content).await?;
+    /// 返回文件的最新条目（必要时重新加载）；文件不存在且没有加载器可用时返回 `None`
+    /// Return the file's up-to-date entry, reloading if needed; `None` when the file is missing and no loader helps
+    async fn fresh_entry(&self, filename: &str) -> std::io::Result<Option<FileEntry>> {
+        let start = std::time::Instant::now();
+        if let Some((entry, op)) = self.checked_entry(filename).await? {
+            self.latencies.record(op, start.elapsed());
+            return Ok(Some(entry));
+        }
+        self.sync_points.reach(SyncPoint::Checked, filename).await;
+        let _guard = self.locks.lock(filename).await;
+        let entry = self.locked_fresh_entry(filename).await;
+        self.latencies.record(Op::Load, start.elapsed());
+        entry
+    }
 
-        let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    /// 在已持有加载锁的情况下返回最新条目 | Return the up-to-date entry while the load lock is held
+    async fn locked_fresh_entry(&self, filename: &str) -> std::io::Result<Option<FileEntry>> {
+        // 等锁期间其他任务可能已经完成了重新加载 | another task may have reloaded it while we waited for the lock
+        if let Some(entry) = self.cached_fresh_entry(filename).await? {
+            return Ok(Some(entry));
+        }
+        if let Some(hooks) = self.hooks.get() {
+            hooks.on_miss(filename);
+        }
+        self.sync_points.reach(SyncPoint::Load, filename).await;
+        let loaded = self.load_file_into_cache(filename).await;
+        self.quarantine.observe(filename, &loaded).await;
+        loaded
+    }
+
+    /// 已缓存且通过变更检测的条目 | The cached entry, if it passes change detection
+    async fn cached_fresh_entry(&self, filename: &str) -> std::io::Result<Option<FileEntry>> {
+        Ok(self.checked_entry(filename).await?.map(|(entry, _)| entry))
+    }
 
-        // 【关键兼容点】严格模仿 Python linecache 的行为：
-        // 如果文件以 \n 结尾且不为空，必须追加一个空行
-        // Critical compatibility point: exactly mimic Python linecache behavior:
-        // If file ends with '\n' and is not empty, append an extra empty line
-        if content.ends_with('\n') && !content.is_empty() {
-            lines.push(String::new());
+    /// 已缓存且通过变更检测的条目，以及这次检测是否 stat 了文件
+    /// The cached entry, if it passes change detection, with whether the check stat'ed the file
+    async fn checked_entry(&self, filename: &str) -> std::io::Result<Option<(FileEntry, Op)>> {
+        let Some(entry) = self.entries.get(filename).await else { return Ok(None) };
+        let Some(op) = self.check_entry(filename, &entry).await? else { return Ok(None) };
+        eviction::touch(&entry, self.clock.now());
+        if let Some(hooks) = self.hooks.get() {
+            hooks.on_hit(filename);
         }
+        Ok(Some((entry, op)))
+    }
 
-        let lines_arc = Arc::new(lines);
-        let key = filename.to_string();
+    /// 核心加载逻辑：读取文件 → 按行拆分 → 写入缓存；文件不存在时询问已注册的加载器
+    /// Core loading logic: read file → split into lines → insert into the cache; consults the registered loader when the file is missing
+    ///
+    /// - 新条目整体覆盖旧条目，因此基于旧版本的行、内容与元数据会一起被替换
+    /// - 取消安全：所有可能挂起或失败的工作（读文件、调用加载器、建索引）都在修改缓存之前完成，
+    ///   之后只剩一次原子写入；调用方的 future 在任意 `.await` 处被丢弃都不会留下不一致的状态
+    /// - 调用方必须持有该文件的加载锁
+    ///
+    /// - The new entry replaces the old one as a whole, so the lines, content and metadata of the old version go together
+    /// - Cancellation-safe: everything that can suspend or fail (reading, calling the loader, indexing) finishes before the
+    ///   cache is touched, leaving one atomic write; dropping the caller's future at any `.await` cannot leave inconsistent state
+    /// - The caller must hold the file's load lock
+    async fn load_file_into_cache(&self, filename: &str) -> std::io::Result<Option<FileEntry>> {
+        // 预留的加载额度一直持有到条目写入缓存、开始计入权重为止
+        // The reserved load room is held until the entry is inserted and starts counting towards the weight
+        let gate = self.load_gate.current();
+        let (entry, _reserved) = self.read_entry(filename, &gate).await?;
+        self.publish(filename, entry.clone()).await?;
+        Ok(entry)
+    }
 
-        self.lines.insert(key.clone(), lines_arc.clone()).await;
-        self.metadata.insert(key, (meta.modified()?, meta.len())).await;
+    /// 从磁盘（文件不存在时从加载器）构建条目但不写入缓存，同时返回为它预留的加载额度
+    /// Build the entry from disk (or a loader when the file is missing) without caching it, along with the load room reserved for it
+    async fn read_entry<'g>(
+        &self,
+        filename: &str,
+        gate: &'g gate::LoadGate,
+    ) -> std::io::Result<(Option<FileEntry>, Option<SemaphorePermit<'g>>)> {
+        let read = match self.confine(filename).await? {
+            // 只打开通过根目录检查的路径，不再按原始文件名重新解析 | only the path that passed the root check is opened, never the raw filename again
+            Some(path) => {
+                let (path, options) = (path.as_path(), self.overrides.get(filename));
+                RetryPolicy::run(self.retry.as_deref(), || async move {
+                    let read = read_file(filename, path, options, gate);
+                    match self.load_timeout {
+                        Some(limit) => Self::within_deadline(tokio::time::Instant::now() + limit, read).await,
+                        None => read.await,
+                    }
+                })
+                .await?
+            }
+            None => None,
+        };
+        Ok(match read {
+            Some((content, stamp, reserved)) => (Some(self.build_entry(filename, content, stamp)?), Some(reserved)),
+            None => match (self.loaders.get(filename), self.loaders.get_lazy(filename)) {
+                (Some(loader), _) => (self.loader_entry(filename, loader.as_ref())?, None),
+                (None, Some(lazy)) => (Some(self.build_entry(filename, lazy().await?, Stamp::Virtual)?), None),
+                (None, None) => (None, None),
+            },
+        })
+    }
 
-        Ok(lines_arc)
+    /// 通过加载器获取源码并写入缓存（标记为虚拟条目）；加载器无法提供时返回 `None` 且不改动缓存
+    /// Fetch source through a loader and cache it as a virtual entry; returns `None` without touching the cache if the loader cannot provide it
+    ///
+    /// 调用方必须持有该文件的加载锁。| The caller must hold the file's load lock.
+    async fn load_from_loader(
+        &self,
+        filename: &str,
+        loader: &dyn SourceLoader,
+    ) -> std::io::Result<Option<FileEntry>> {
+        let entry = self.loader_entry(filename, loader)?;
+        if entry.is_some() {
+            self.publish(filename, entry.clone()).await?;
+        }
+        Ok(entry)
     }
 
-    /// 检查文件是否被修改（通过 mtime + size 双重校验）
-    /// Check if file has been modified (using mtime + size dual validation)
-    async fn is_file_modified(&self, filename: &str) -> std::io::Result<bool> {
-        match tokio::fs::metadata(filename).await {
-            Ok(meta) => {
-                let mtime = meta.modified()?;
-                let size = meta.len();
+    /// 通过加载器构建虚拟条目（不写入缓存）；加载器无法提供时返回 `None`
+    /// Build a virtual entry through a loader (without caching it); `None` if the loader cannot provide it
+    fn loader_entry(&self, filename: &str, loader: &dyn SourceLoader) -> std::io::Result<Option<FileEntry>> {
+        let Some(source) = loader.get_source(filename)? else { return Ok(None); };
+        Ok(Some(self.build_entry(filename, source, Stamp::Virtual)?))
+    }
 
-                if let Some((cached_mtime, cached_size)) = self.metadata.get(filename).await {
-                    Ok(mtime != cached_mtime || size != cached_size)
-                } else {
-                    Ok(true) // 首次访问必然需要加载 | first access always needs loading
+    /// 经过加载时处理后为读入的文本建立条目 | Build the entry for text that was read, after load-time processing
+    fn build_entry(&self, filename: &str, text: String, stamp: Stamp) -> std::io::Result<FileEntry> {
+        let (text, stats) = self.transforms.apply(filename, text)?;
+        Ok(FileEntry::new(Arc::new(LineBuffer::new(text)), stamp, self.clock.now()).with_load_stats(stats))
+    }
+
+    /// 把构建完成的条目一次性写入缓存；`None` 表示文件已不存在，移除旧条目
+    /// Publish a fully built entry in one write; `None` means the file is gone and the old entry is removed
+    ///
+    /// 超过硬水位线时移除旧条目并返回错误。| Above the hard watermark, the old entry is removed and an error returned.
+    async fn publish(&self, filename: &str, entry: Option<FileEntry>) -> std::io::Result<()> {
+        match entry {
+            Some(entry) => {
+                let limit = self.overrides.get(filename).and_then(|options| options.max_bytes);
+                if let Some(bytes) = self.watermarks.oversize(&entry, limit) {
+                    // 超过单条目上限：照常返回数据，但不缓存 | above the per-entry limit: served as usual, but not cached
+                    self.remove_entry(filename).await;
+                    if let Some(hooks) = self.hooks.get() {
+                        hooks.on_oversize(filename, bytes);
+                    }
+                    return Ok(());
+                }
+                self.prebuild(filename, &entry);
+                if let Err(e) = self.admit_watermarks(filename, &entry).await {
+                    self.remove_entry(filename).await;
+                    return Err(e);
+                }
+                if !self.admit_quotas(filename, &entry).await {
+                    // 超出前缀配额：照常返回数据，但不缓存 | over its prefix quota: served as usual, but not cached
+                    self.remove_entry(filename).await;
+                    return Ok(());
+                }
+                let on_disk = matches!(entry.stamp(), Stamp::Disk { .. });
+                eviction::touch(&entry, self.clock.now());
+                let hash = content::entry_content_hash(&entry);
+                let introduced = !self.entries.contains_key(filename);
+                self.entries.insert(filename.to_string(), entry.clone()).await;
+                self.by_content.insert(hash, filename.to_string()).await;
+                // 只记录此前不在缓存中的键：重新加载父缓存已有的键不算由作用域引入
+                // Only keys absent before count: reloading a key the parent already had does not introduce it
+                if let Some(scope) = self.scope.as_ref().filter(|_| introduced) {
+                    scope.record(filename);
+                }
+                if let Some(hooks) = self.hooks.get() {
+                    hooks.on_load(filename, &entry);
+                }
+                if on_disk {
+                    self.mark_stat_checked(filename).await;
                 }
             }
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                self.invalidate(filename).await;
-                Ok(true)
+            None => {
+                self.remove_entry(filename).await;
             }
-            Err(e) => Err(e),
         }
+        Ok(())
+    }
+
+    /// 在写入之前为登记的文件构建派生数据，使其计入条目权重 | Build the derived data of registered files before the insert, so it counts in the entry's weight
+    fn prebuild(&self, filename: &str, entry: &FileEntry) {
+        if self.indexed.contains(filename) {
+            index::prebuild(entry);
+        }
+        if self.folded.contains(filename) {
+            fold::prebuild(entry);
+        }
+    }
+
+    /// 已缓存的条目缺少登记的派生数据时，把补建了派生数据的副本重新写入，使权重随之更新；派生数据不会在写入之后补建到原条目上
+    /// When the cached entry lacks derived data it is registered for, write a copy with that data built, so the weight follows; derived data is never added to an entry after its insert
+    pub(crate) async fn rebuild_derived(&self, filename: &str) -> std::io::Result<()> {
+        let _guard = self.locks.lock(filename).await;
+        let Some(entry) = self.entries.get(filename).await else { return Ok(()) };
+        let derived = entry.derived();
+        let missing = (self.indexed.contains(filename) && derived.index.get().is_none())
+            || (self.folded.contains(filename) && derived.folded.get().is_none());
+        if !missing {
+            return Ok(());
+        }
+        self.publish(filename, Some(entry.rederived())).await
+    }
+
+    /// 检查缓存条目是否仍然有效（通过 mtime + size 双重校验）；有效时返回是否为此 stat 了文件，过期时返回 `None`
+    /// Check whether the cached entry is still valid (mtime + size dual validation), returning whether the file was stat'ed for it, or `None` if stale
+    ///
+    /// 只在已有条目时才 stat 路径：首次访问直接走加载路径，由打开后的句柄 fstat 一次完成。
+    /// The path is only stat'ed when an entry exists: a first access goes straight to loading, where one fstat on the opened handle suffices.
+    async fn check_entry(&self, filename: &str, entry: &FileEntry) -> std::io::Result<Option<Op>> {
+        let options = self.overrides.get(filename);
+        if options.and_then(|options| options.ttl).is_some_and(|ttl| entry.age(self.clock.now()) >= ttl) {
+            return Ok(None);
+        }
+        match entry.stamp() {
+            // 虚拟条目没有对应文件，无需 stat | virtual entries have no backing file, skip the stat
+            Stamp::Virtual => Ok(Some(Op::Hit)),
+            Stamp::Disk { mtime, size } => {
+                // 关闭变更检测时直接返回，命中路径上只剩一次条目查找 | with change detection off, a hit costs only the entry lookup
+                let revalidate = options.and_then(|options| options.revalidate).unwrap_or_else(|| self.recent_stats.revalidates());
+                if !revalidate || self.recent_stats.within_ttl(filename).await {
+                    return Ok(Some(Op::Hit));
+                }
+                match RetryPolicy::run(self.retry.as_deref(), || tokio::fs::metadata(self.resolve(filename))).await {
+                    Ok(meta) => {
+                        let fresh = meta.modified()? == mtime && meta.len() == size;
+                        if fresh {
+                            self.mark_stat_checked(filename).await;
+                        }
+                        Ok(fresh.then_some(Op::Revalidation))
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                    Err(e) => Err(e),
+                }
+            }
+        }
+    }
+
+    /// 移除文件的条目并返回它（调用方必须持有该文件的加载锁）
+    /// Remove the file's entry and return it (the caller must hold the file's load lock)
+    async fn remove_entry(&self, filename: &str) -> Option<FileEntry> {
+        let removed = self.entries.remove(filename).await;
+        self.recent_stats.remove(filename).await;
+        removed
+    }
+
+    /// 记录文件刚刚通过 stat 校验（仅在启用微缓存时生效）
+    /// Record that the file just passed a stat check (only when micro-caching is enabled)
+    async fn mark_stat_checked(&self, filename: &str) {
+        self.recent_stats.mark(filename).await;
     }
 }
 
 /// 为方便使用提供 Default 实现
 /// Provide Default implementation for convenience
+#[cfg(feature = "sysinfo")]
 impl Default for AsyncLineCache {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+/// 打开并读取整个文件，文件不存在时返回 `None`
+/// Open and read the whole file, returning `None` if it does not exist
+///
+/// 对打开后的句柄只做一次 fstat，其结果同时用于预估缓冲区容量、向 `gate` 预留加载额度和生成变更检测戳；
+/// 启用 `coding-cookie` 特性时按 `options` 中的编码或源文件的编码声明解码。
+/// A single fstat on the opened handle feeds buffer sizing, the load reservation on `gate` and the change-detection stamp;
+/// with the `coding-cookie` feature, files are decoded by the encoding in `options` or, for source files, their coding cookie.
+#[cfg(feature = "async")]
+#[allow(clippy::cast_possible_truncation)]
+async fn read_file<'g>(
+    filename: &str,
+    path: &std::path::Path,
+    options: Option<FileOptions>,
+    gate: &'g gate::LoadGate,
+) -> std::io::Result<Option<(String, Stamp, SemaphorePermit<'g>)>> {
+    let file = match File::open(path).await {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let meta = file.metadata().await?;
+    let stamp = Stamp::Disk { mtime: meta.modified()?, size: meta.len() };
+    let reserved = gate.reserve(meta.len()).await?;
+
+    let mut reader = BufReader::new(file);
+    #[cfg(not(feature = "coding-cookie"))]
+    let content = {
+        let mut content = String::with_capacity(meta.len() as usize + 1);
+        reader.read_to_string(&mut content).await?;
+        let _ = (filename, options);
+        content
+    };
+    #[cfg(feature = "coding-cookie")]
+    let content = {
+        let mut bytes = Vec::with_capacity(meta.len() as usize + 1);
+        reader.read_to_end(&mut bytes).await?;
+        encoding::decode_source(filename, bytes, options.and_then(|options| options.encoding))?
+    };
+
+    Ok(Some((content, stamp, reserved)))
+}
+
+/// 轮询一次 `fut`，已就绪时返回结果，否则放弃它 | Poll `fut` once, returning its output if ready and dropping it otherwise
+#[cfg(feature = "async")]
+fn poll_now<F: std::future::Future>(fut: F) -> Option<F::Output> {
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    match std::pin::pin!(fut).poll(&mut cx) {
+        std::task::Poll::Ready(output) => Some(output),
+        std::task::Poll::Pending => None,
+    }
+}
+
+/// 随机选择一个行下标；空文件返回 `None`
+/// Pick a random line index; `None` for an empty file
+#[cfg(feature = "async")]
+fn random_index(lines: &LineBuffer) -> Option<usize> {
+    (!lines.is_empty()).then(|| rand::thread_rng().gen_range(0..lines.len()))
+}
@@ -0,0 +1,133 @@
+//! 源码加载器：文件在磁盘上不存在时的后备来源（对应 Python 的 `module_globals["__loader__"]`）
+//! Source loaders: fallback source when a file is missing on disk (the analog of Python's `module_globals["__loader__"]`)
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+/// 为不在文件系统上的"文件"（如 zipimport、冻结模块、内存生成代码）提供源码
+/// Provides source for "files" that do not live on the filesystem (zipimport, frozen modules, generated code)
+///
+/// 对应 Python 中 `__loader__.get_source(name)`：返回 `Ok(None)` 表示该加载器无法提供此文件。
+/// Mirrors Python's `__loader__.get_source(name)`: returning `Ok(None)` means the loader cannot provide this file.
+pub trait SourceLoader: Send + Sync {
+    /// 获取 `filename` 的完整源码
+    /// Get the full source of `filename`
+    fn get_source(&self, filename: &str) -> std::io::Result<Option<String>>;
+}
+
+/// 任意 `Fn(&str) -> io::Result<Option<String>>` 闭包都可直接作为加载器使用
+/// Any `Fn(&str) -> io::Result<Option<String>>` closure can be used as a loader directly
+impl<F> SourceLoader for F
+where
+    F: Fn(&str) -> std::io::Result<Option<String>> + Send + Sync,
+{
+    fn get_source(&self, filename: &str) -> std::io::Result<Option<String>> {
+        self(filename)
+    }
+}
+
+/// 异步产生源码的惰性加载器（见 `register_lazy`）| A lazy loader producing source asynchronously (see `register_lazy`)
+pub(crate) type LazyLoader = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = std::io::Result<String>> + Send>> + Send + Sync>;
+
+/// 按前缀设置的加载器（前缀，加载器）| Loaders set by prefix (prefix, loader)
+type PrefixLoaders = Vec<(String, Arc<dyn SourceLoader>)>;
+
+/// 按文件名注册的加载器表，所有克隆出的缓存实例共享同一张表
+/// Per-filename loader registry, shared by every clone of the cache
+#[derive(Clone, Default)]
+pub(crate) struct LoaderRegistry {
+    by_name: Arc<RwLock<HashMap<String, Arc<dyn SourceLoader>>>>,
+    by_prefix: Arc<RwLock<PrefixLoaders>>,
+    lazy: Arc<RwLock<HashMap<String, LazyLoader>>>,
+}
+
+impl LoaderRegistry {
+    pub(crate) fn register(&self, filename: &str, loader: Arc<dyn SourceLoader>) {
+        self.by_name
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .insert(filename.to_string(), loader);
+    }
+
+    pub(crate) fn register_lazy(&self, filename: &str, loader: LazyLoader) {
+        self.lazy
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .insert(filename.to_string(), loader);
+    }
+
+    /// 移除该文件名的同步与惰性加载器 | Remove both the sync and the lazy loader of the filename
+    pub(crate) fn unregister(&self, filename: &str) -> bool {
+        let sync = self
+            .by_name
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .remove(filename)
+            .is_some();
+        let lazy = self
+            .lazy
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .remove(filename)
+            .is_some();
+        sync || lazy
+    }
+
+    pub(crate) fn set_prefix(&self, prefix: &str, loader: Arc<dyn SourceLoader>) {
+        let mut prefixes = self.by_prefix.write().unwrap_or_else(std::sync::PoisonError::into_inner);
+        prefixes.retain(|(existing, _)| existing != prefix);
+        prefixes.push((prefix.to_string(), loader));
+    }
+
+    pub(crate) fn unset_prefix(&self, prefix: &str) -> bool {
+        let mut prefixes = self.by_prefix.write().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let before = prefixes.len();
+        prefixes.retain(|(existing, _)| existing != prefix);
+        prefixes.len() != before
+    }
+
+    /// 按文件名注册的加载器优先，其次是匹配的最长前缀 | The loader registered for the filename first, then the longest matching prefix
+    pub(crate) fn get(&self, filename: &str) -> Option<Arc<dyn SourceLoader>> {
+        let exact = self
+            .by_name
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(filename)
+            .cloned();
+        exact.or_else(|| {
+            let prefixes = self.by_prefix.read().unwrap_or_else(std::sync::PoisonError::into_inner);
+            prefixes
+                .iter()
+                .filter(|(prefix, _)| filename.starts_with(prefix.as_str()))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map(|(_, loader)| Arc::clone(loader))
+        })
+    }
+
+    pub(crate) fn get_lazy(&self, filename: &str) -> Option<LazyLoader> {
+        self.lazy
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(filename)
+            .cloned()
+    }
+}
+
+impl fmt::Debug for LoaderRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let len = self
+            .by_name
+            .read()
+            .map_or(0, |map| map.len());
+        let prefixes = self.by_prefix.read().map_or(0, |prefixes| prefixes.len());
+        let lazy = self.lazy.read().map_or(0, |map| map.len());
+        f.debug_struct("LoaderRegistry")
+            .field("registered", &len)
+            .field("prefixes", &prefixes)
+            .field("lazy", &lazy)
+            .finish()
+    }
+}
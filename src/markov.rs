@@ -0,0 +1,107 @@
+//! `generate` 特性：基于缓存文件的词级马尔可夫链文本生成
+//! `generate` feature: word-level Markov-chain text generation over cached files
+
+use crate::AsyncLineCache;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// 词级马尔可夫链模型：由前 `order` 个词预测下一个词
+/// Word-level Markov-chain model: the previous `order` words predict the next one
+///
+/// - 词按空白切分，链只在同一行内延续，行尾视为链的终点
+/// - 每个词只保存一次，状态与后继都以词编号存储
+///
+/// - Words are split on whitespace and chains only continue within a line; a line end terminates the chain
+/// - Every word is stored once, and states and successors are kept as word ids
+#[derive(Debug, Clone, Default)]
+pub struct MarkovModel {
+    order: usize,
+    words: Vec<String>,
+    /// 每行开头的 `order` 个词，作为生成的起点 | The first `order` words of each line, used as starting states
+    starts: Vec<Vec<u32>>,
+    /// 状态 → 后继词（按出现次数重复，均匀抽取即按频率加权）
+    /// State → successor words (repeated per occurrence, so uniform picks are frequency-weighted)
+    transitions: HashMap<Vec<u32>, Vec<u32>>,
+}
+
+impl MarkovModel {
+    /// 从若干行文本构建 `order` 阶模型（`order` 为 0 时按 1 处理）
+    /// Build an order-`order` model from lines of text (an `order` of 0 is treated as 1)
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn from_lines<'a>(lines: impl IntoIterator<Item = &'a str>, order: usize) -> Self {
+        let order = order.max(1);
+        let mut model = Self { order, ..Self::default() };
+        let mut ids: HashMap<&'a str, u32> = HashMap::new();
+
+        for line in lines {
+            let tokens: Vec<u32> = line
+                .split_whitespace()
+                .map(|word| {
+                    *ids.entry(word).or_insert_with(|| {
+                        model.words.push(word.to_string());
+                        (model.words.len() - 1) as u32
+                    })
+                })
+                .collect();
+            if tokens.len() < order {
+                continue;
+            }
+            model.starts.push(tokens[..order].to_vec());
+            for window in tokens.windows(order + 1) {
+                model
+                    .transitions
+                    .entry(window[..order].to_vec())
+                    .or_default()
+                    .push(window[order]);
+            }
+        }
+        model
+    }
+
+    /// 模型的阶数 | Order of the model
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// 模型是否为空（没有任何一行达到 `order` 个词）
+    /// Whether the model is empty (no line had at least `order` words)
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// 生成最多 `len` 个词、以空格连接的文本；链在行尾中断时从新的随机起点继续
+    /// Generate text of up to `len` words joined by spaces; when a chain hits a line end it restarts from a new random start
+    ///
+    /// 空模型返回空字符串。| An empty model returns an empty string.
+    pub fn generate<R: Rng + ?Sized>(&self, len: usize, rng: &mut R) -> String {
+        let mut output: Vec<u32> = Vec::with_capacity(len);
+        let mut state: Vec<u32> = Vec::new();
+
+        while output.len() < len {
+            let next = self.transitions.get(&state).and_then(|next| next.choose(rng));
+            if let Some(&word) = next {
+                output.push(word);
+                state.remove(0);
+                state.push(word);
+            } else {
+                let Some(start) = self.starts.choose(rng) else { break };
+                let take = start.len().min(len - output.len());
+                output.extend_from_slice(&start[..take]);
+                state.clone_from(start);
+            }
+        }
+
+        let words: Vec<&str> = output.iter().map(|&id| self.words[id as usize].as_str()).collect();
+        words.join(" ")
+    }
+}
+
+impl AsyncLineCache {
+    /// 基于文件的缓存行构建 `order` 阶词级马尔可夫链模型（文件为空或不存在时得到空模型）
+    /// Build an order-`order` word-level Markov model from the file's cached lines (an empty or missing file yields an empty model)
+    pub async fn build_markov(&self, filename: &str, order: usize) -> std::io::Result<MarkovModel> {
+        let lines = self.fresh_lines(filename).await?;
+        Ok(MarkovModel::from_lines(lines.iter(), order))
+    }
+}
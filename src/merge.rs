@@ -0,0 +1,80 @@
+//! 合并视图：把多个缓存文件呈现为一个首尾相接、行号连续的虚拟文件
+//! Merged view: presents several cached files as one virtual file, concatenated with continuous line numbering
+
+use crate::{AsyncLineCache, CachedLines};
+use rand::Rng;
+
+/// 多个文件按顺序拼接而成的虚拟文件
+/// A virtual file made of several files concatenated in order
+///
+/// - 视图不复制任何数据：每次访问都经由缓存取得成员文件的最新内容，成员在磁盘上变更后行号随之更新
+/// - 每个成员贡献其全部内容行；成员末尾换行符产生的兼容空行不计入视图
+///
+/// - The view copies no data: every access goes through the cache for each member's current content, so line numbers follow members changing on disk
+/// - Every member contributes all of its content lines; the compatibility empty line after a member's final newline is not part of the view
+#[derive(Debug, Clone)]
+pub struct MergedView {
+    cache: AsyncLineCache,
+    members: Vec<String>,
+}
+
+impl MergedView {
+    /// 成员文件（按拼接顺序）| Member files, in concatenation order
+    pub fn members(&self) -> &[String] {
+        &self.members
+    }
+
+    /// 视图当前的总行数 | Current total number of lines in the view
+    pub async fn line_count(&self) -> std::io::Result<usize> {
+        let mut total = 0;
+        for member in &self.members {
+            total += self.cache.fresh_lines(member).await?.content_len();
+        }
+        Ok(total)
+    }
+
+    /// 获取视图的第 `lineno` 行（从 1 开始，跨成员连续编号）；超出范围时返回 `None`
+    /// Get line `lineno` of the view (1-based, numbered continuously across members); `None` when out of range
+    pub async fn get_line(&self, lineno: usize) -> std::io::Result<Option<String>> {
+        let Some(mut idx) = lineno.checked_sub(1) else { return Ok(None) };
+        for member in &self.members {
+            let lines = self.cache.fresh_lines(member).await?;
+            let len = lines.content_len();
+            if idx < len {
+                return Ok(lines.get(idx).map(String::from));
+            }
+            idx -= len;
+        }
+        Ok(None)
+    }
+
+    /// 在视图的所有行中均匀随机返回一行；视图为空时返回 `None`
+    /// Return a line drawn uniformly from every line of the view; `None` when the view is empty
+    pub async fn random_line(&self) -> std::io::Result<Option<String>> {
+        let mut members: Vec<CachedLines> = Vec::with_capacity(self.members.len());
+        for member in &self.members {
+            members.push(self.cache.fresh_lines(member).await?);
+        }
+        let total: usize = members.iter().map(|lines| lines.content_len()).sum();
+        if total == 0 {
+            return Ok(None);
+        }
+        let mut idx = rand::thread_rng().gen_range(0..total);
+        for lines in &members {
+            let len = lines.content_len();
+            if idx < len {
+                return Ok(lines.get(idx).map(String::from));
+            }
+            idx -= len;
+        }
+        Ok(None)
+    }
+}
+
+impl AsyncLineCache {
+    /// 把 `files` 按顺序拼接为一个合并视图，见 [`MergedView`]
+    /// Concatenate `files` in order into a merged view, see [`MergedView`]
+    pub fn merged_view(&self, files: &[&str]) -> MergedView {
+        MergedView { cache: self.clone(), members: files.iter().map(ToString::to_string).collect() }
+    }
+}
@@ -0,0 +1,130 @@
+//! 命名空间（多租户）子缓存：每个命名空间拥有独立的键空间与内存上限
+//! Namespaced (multi-tenant) sub-caches: every namespace has its own key space and memory cap
+
+use crate::AsyncLineCache;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// 已创建的命名空间表，由所有克隆出的缓存实例共享
+/// Table of created namespaces, shared by every clone of the cache
+#[derive(Clone, Default)]
+pub(crate) struct Namespaces {
+    by_name: Arc<RwLock<HashMap<String, AsyncLineCache>>>,
+}
+
+impl fmt::Debug for Namespaces {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.by_name.read().map_or(0, |map| map.len());
+        f.debug_struct("Namespaces").field("count", &names).finish()
+    }
+}
+
+impl AsyncLineCache {
+    /// 获取（首次调用时创建）名为 `name` 的命名空间句柄，内存上限与本缓存相同
+    /// Get (creating on first use) the handle of namespace `name`, capped at this cache's budget
+    ///
+    /// 见 [`namespace_with_budget`](Self::namespace_with_budget)。
+    /// See [`namespace_with_budget`](Self::namespace_with_budget).
+    #[must_use]
+    pub fn namespace(&self, name: &str) -> AsyncLineCache {
+        self.namespace_with_budget(name, self.budget)
+    }
+
+    /// 获取（首次调用时创建）名为 `name`、内存上限为 `budget` 字节的命名空间句柄
+    /// Get (creating on first use) the handle of namespace `name`, capped at `budget` bytes
+    ///
+    /// - 每个命名空间有独立的条目缓存：键互不可见，一个租户的大文件只会驱逐它自己的数据
+    /// - 加载器、回调、权重方式、沙箱根目录、stat 微缓存 TTL、变更检测开关与加载内存闸门沿用本缓存的设置；前缀配额、水位线与单条目上限不继承
+    /// - `budget` 只在创建时生效；之后再次获取同名命名空间返回已有句柄
+    ///
+    /// - Every namespace has its own entry cache: keys are invisible to each other, and one tenant's giant files can only evict its own data
+    /// - Loaders, hooks, weighing, the sandbox root, the stat micro-cache TTL, the change-detection switch and the load memory gate are inherited from this cache; prefix quotas, watermarks and the per-entry limit are not
+    /// - `budget` only applies on creation; fetching the same namespace later returns the existing handle
+    #[must_use]
+    pub fn namespace_with_budget(&self, name: &str, budget: u64) -> AsyncLineCache {
+        if let Some(existing) = self.namespaces.by_name.read().unwrap_or_else(PoisonError::into_inner).get(name) {
+            return existing.clone();
+        }
+        let mut map = self.namespaces.by_name.write().unwrap_or_else(PoisonError::into_inner);
+        map.entry(name.to_string()).or_insert_with(|| self.derive(budget)).clone()
+    }
+
+    /// 已创建的命名空间及其当前内存占用（字节）
+    /// Created namespaces with their current memory usage in bytes
+    pub fn namespace_usage(&self) -> Vec<(String, u64)> {
+        let map = self.namespaces.by_name.read().unwrap_or_else(PoisonError::into_inner);
+        let mut usage: Vec<_> = map.iter().map(|(name, ns)| (name.clone(), ns.memory_usage())).collect();
+        usage.sort_unstable();
+        usage
+    }
+
+    /// 按给定的流量权重选中一个命名空间，再从它已缓存的文件中按行数均匀抽取一行，返回（命名空间名，行）
+    /// Pick a namespace by the given traffic weights, then draw a line uniformly across its cached files by line count, returning (namespace name, line)
+    ///
+    /// - 只考虑已创建且缓存着非空文件的命名空间，权重在它们之间归一化；未创建的名称被忽略，不会因此创建命名空间
+    /// - 负数、NaN 或无穷大的权重按 0 处理；没有可抽取的命名空间时返回 `None`
+    /// - 选中的文件在返回前照常做变更检测
+    ///
+    /// - Only namespaces that exist and cache non-empty files count, and the weights are normalized among them; unknown names are ignored and never create a namespace
+    /// - Negative, NaN or infinite weights count as 0; returns `None` when no namespace can be drawn from
+    /// - The picked file goes through change detection as usual before a line is returned
+    pub async fn random_line_weighted_namespaces(&self, weights: &[(&str, f64)]) -> std::io::Result<Option<(String, String)>> {
+        let candidates: Vec<_> = {
+            let map = self.namespaces.by_name.read().unwrap_or_else(PoisonError::into_inner);
+            weights
+                .iter()
+                .filter_map(|&(name, weight)| {
+                    let weight = if weight.is_finite() { weight.max(0.0) } else { 0.0 };
+                    let ns = map.get(name)?;
+                    let files: Vec<_> = ns
+                        .entries
+                        .iter()
+                        .map(|(key, entry)| (key.to_string(), entry.lines().len()))
+                        .filter(|(_, lines)| *lines > 0)
+                        .collect();
+                    (weight > 0.0 && !files.is_empty()).then(|| (name, ns.clone(), files, weight))
+                })
+                .collect()
+        };
+        let picked = {
+            let mut rng = rand::thread_rng();
+            candidates.choose_weighted(&mut rng, |candidate| candidate.3).ok().and_then(|(name, ns, files, _)| {
+                let (filename, _) = files.choose_weighted(&mut rng, |(_, lines)| *lines).ok()?;
+                Some((*name, ns, filename))
+            })
+        };
+        let Some((name, ns, filename)) = picked else { return Ok(None) };
+        Ok(ns.random_line(filename).await?.map(|line| (name.to_string(), line)))
+    }
+
+    /// 已创建的命名空间句柄 | Handles of the created namespaces
+    pub(crate) fn namespace_handles(&self) -> Vec<AsyncLineCache> {
+        self.namespaces.by_name.read().unwrap_or_else(PoisonError::into_inner).values().cloned().collect()
+    }
+
+    /// 以本缓存的设置创建一个空的、预算为 `budget` 的独立缓存
+    /// Create an empty, independent cache with this cache's settings and a budget of `budget`
+    fn derive(&self, budget: u64) -> AsyncLineCache {
+        let mut child = self.clone();
+        child.budget = budget;
+        child.recent_stats = Arc::new(crate::RecentStats::new(self.recent_stats.ttl(), self.recent_stats.capacity(), self.clock.clone()));
+        child.recent_stats.set_revalidate(self.recent_stats.revalidates());
+        child.locks = Arc::default();
+        child.namespaces = Namespaces::default();
+        child.quotas = crate::Quotas::default();
+        child.scope = None;
+        child.watermarks = Arc::default();
+        child.dirs = crate::dir::listings_cache(self.dirs.policy().max_capacity());
+        child.line_counts = crate::dir_sample::line_counts_cache(self.line_counts.policy().max_capacity());
+        child.indexed = crate::FileSet::default();
+        child.folded = crate::FileSet::default();
+        child.served = crate::Served::default();
+        child.quarantine = crate::Quarantine::default();
+        child.by_content = crate::content::by_content_cache(self.by_content.policy().max_capacity());
+        child.latencies = Arc::default();
+        child.rebuild_entries();
+        child
+    }
+}
@@ -0,0 +1,52 @@
+//! 词级 n-gram 统计，按条目记忆
+//! Word n-gram counting, memoized per entry
+
+use crate::{AsyncLineCache, LineBuffer};
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError};
+
+/// n-gram 计数表：键为以单个空格连接的 `n` 个词，值为出现次数
+/// N-gram counts: keys are `n` words joined by a single space, values are occurrence counts
+pub type NgramCounts = HashMap<String, usize>;
+
+/// 统计所有行中的词级 n-gram（按空白切词，n-gram 不跨行）
+/// Count the word n-grams of every line (split on whitespace; n-grams never span lines)
+fn count_ngrams(lines: &LineBuffer, n: usize) -> NgramCounts {
+    let mut counts = NgramCounts::new();
+    if n == 0 {
+        return counts;
+    }
+    let mut words = Vec::new();
+    for line in lines.iter() {
+        words.clear();
+        words.extend(line.split_whitespace());
+        for gram in words.windows(n) {
+            *counts.entry(gram.join(" ")).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+impl AsyncLineCache {
+    /// 统计文件中的词级 `n`-gram 及其出现次数，结果按缓存条目记忆
+    /// Count the word `n`-grams of the file with their occurrences; the result is memoized on the cache entry
+    ///
+    /// - 同一版本的文件对同一个 `n` 只切词一次；文件变更或条目被驱逐后重新计算
+    /// - `n` 为 0、文件为空或不存在时返回空表
+    ///
+    /// - A given version of the file is tokenized once per `n`; counts are recomputed after a change or eviction
+    /// - Returns an empty table when `n` is 0 or the file is empty or missing
+    pub async fn ngrams(&self, filename: &str, n: usize) -> std::io::Result<Arc<NgramCounts>> {
+        let Some(entry) = self.fresh_entry(filename).await? else {
+            return Ok(Arc::default());
+        };
+        let memo = &entry.derived().ngrams;
+        if let Some(counts) = memo.lock().unwrap_or_else(PoisonError::into_inner).get(&n) {
+            return Ok(Arc::clone(counts));
+        }
+        // 计算期间不持锁 | counted without holding the lock
+        let counts = Arc::new(count_ngrams(entry.lines(), n));
+        let mut memo = memo.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(Arc::clone(memo.entry(n).or_insert(counts)))
+    }
+}
@@ -0,0 +1,123 @@
+//! 按路径或通配模式覆盖全局设置：同一个缓存里的小配置文件与大词典可以各用各的策略
+//! Per-path or per-pattern overrides of the global settings, so tiny config files and huge dictionaries in one cache can follow different policies
+
+use crate::discover::glob_match;
+use crate::AsyncLineCache;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+
+/// 一组针对特定文件的设置；未设置的项沿用全局设置
+/// A set of settings for specific files; settings left unset follow the global ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileOptions {
+    pub(crate) revalidate: Option<bool>,
+    pub(crate) max_bytes: Option<u64>,
+    pub(crate) ttl: Option<Duration>,
+    #[cfg(feature = "coding-cookie")]
+    pub(crate) encoding: Option<crate::encoding::Forced>,
+}
+
+impl FileOptions {
+    /// 创建空设置（全部沿用全局设置）| Create empty options (everything follows the global settings)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 是否对这些文件做变更检测，覆盖 [`AsyncLineCache::with_revalidation`]
+    /// Whether these files are checked for changes, overriding [`AsyncLineCache::with_revalidation`]
+    #[must_use]
+    pub fn revalidate(mut self, enabled: bool) -> Self {
+        self.revalidate = Some(enabled);
+        self
+    }
+
+    /// 单个条目的内容上限（字节），覆盖 [`AsyncLineCache::with_max_entry_bytes`]
+    /// Content limit of a single entry in bytes, overriding [`AsyncLineCache::with_max_entry_bytes`]
+    #[must_use]
+    pub fn max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// 条目加载 `ttl` 之后视为过期，下次访问时重新加载，即使文件没有变化
+    /// Entries count as stale `ttl` after loading and are reloaded on the next access, even if the file is unchanged
+    #[must_use]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// 按编码标签（如 `"gbk"`、`"shift_jis"`，按 WHATWG 规则识别）解码这些文件，优先于编码声明与 UTF-8 默认规则；需要 `coding-cookie` 特性
+    /// Decode these files with the encoding `label` (such as `"gbk"` or `"shift_jis"`, recognized by WHATWG rules), ahead of coding cookies and the UTF-8 default; requires the `coding-cookie` feature
+    ///
+    /// 内容开头的 BOM 仍优先于该编码；无法识别的标签使这些文件的加载返回 [`std::io::ErrorKind::InvalidData`] 错误，与无法识别的编码声明一致。
+    /// A BOM at the start of the content still takes precedence; an unrecognized label makes loading these files fail with [`std::io::ErrorKind::InvalidData`], like an unrecognized coding cookie.
+    #[cfg(feature = "coding-cookie")]
+    #[must_use]
+    pub fn encoding(mut self, label: &str) -> Self {
+        self.encoding = Some(crate::encoding::Forced::from_label(label));
+        self
+    }
+}
+
+/// 一条覆盖规则；是否含通配符在设置时判断一次，精确路径在命中路径上只做一次字符串比较
+/// One override rule; whether it has wildcards is decided once when it is set, so an exact path costs a single string comparison on the hit path
+#[derive(Debug)]
+struct Rule {
+    pattern: String,
+    glob: bool,
+    options: FileOptions,
+}
+
+impl Rule {
+    fn matches(&self, filename: &str) -> bool {
+        if self.glob {
+            glob_match(&self.pattern, filename)
+        } else {
+            self.pattern == filename
+        }
+    }
+}
+
+/// 已设置的覆盖规则，由所有克隆出的缓存实例及其命名空间共享
+/// The override rules set so far, shared by every clone of the cache and its namespaces
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FileOverrides {
+    rules: Arc<RwLock<Vec<Rule>>>,
+}
+
+impl FileOverrides {
+    /// 对 `filename` 生效的设置：最后设置的匹配规则 | Options in effect for `filename`: the last matching rule set
+    pub(crate) fn get(&self, filename: &str) -> Option<FileOptions> {
+        let rules = self.rules.read().unwrap_or_else(PoisonError::into_inner);
+        rules.iter().rev().find(|rule| rule.matches(filename)).map(|rule| rule.options)
+    }
+}
+
+impl AsyncLineCache {
+    /// 为路径或通配模式 `pattern` 设置覆盖全局设置的选项，替换同一模式之前的设置
+    /// Set options overriding the global settings for the path or glob `pattern`, replacing earlier options for the same pattern
+    ///
+    /// - 模式匹配完整路径：`*` 匹配任意个字符（包括 `/`），`?` 匹配一个字符；不含通配符的模式就是精确路径
+    /// - 多条规则都匹配时，最后设置的一条整体生效；命名空间共享这些规则
+    /// - 新设置在文件下次被访问时生效，不会使已缓存的条目失效
+    ///
+    /// - Patterns match the full path: `*` matches any run of characters (including `/`) and `?` exactly one; a pattern without wildcards is an exact path
+    /// - When several rules match, the last one set applies as a whole; namespaces share the rules
+    /// - New options take effect on the file's next access, without invalidating cached entries
+    pub fn set_file_options(&self, pattern: &str, options: FileOptions) {
+        let mut rules = self.overrides.rules.write().unwrap_or_else(PoisonError::into_inner);
+        rules.retain(|rule| rule.pattern != pattern);
+        let glob = pattern.contains(['*', '?']);
+        rules.push(Rule { pattern: pattern.to_string(), glob, options });
+    }
+
+    /// 移除 `pattern` 的覆盖设置，返回之前是否设置过
+    /// Remove the overrides of `pattern`, returning whether any were set
+    pub fn clear_file_options(&self, pattern: &str) -> bool {
+        let mut rules = self.overrides.rules.write().unwrap_or_else(PoisonError::into_inner);
+        let before = rules.len();
+        rules.retain(|rule| rule.pattern != pattern);
+        rules.len() != before
+    }
+}
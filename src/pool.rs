@@ -0,0 +1,74 @@
+//! 加权多文件语料池：按权重在多个缓存文件之间随机抽取行
+//! Weighted multi-file corpus pool: sample random lines across several cached files by weight
+
+use crate::AsyncLineCache;
+use rand::seq::SliceRandom;
+
+/// 建立在 [`AsyncLineCache`] 之上的加权语料池
+/// Weighted corpus pool built on top of [`AsyncLineCache`]
+///
+/// 每次抽样先按权重选中一个成员文件，再从该文件中随机取一行；成员文件在磁盘上变更时由缓存透明地重新加载。
+/// Each sample first picks a member file by weight, then a random line from it; members that change on disk are
+/// reloaded transparently by the cache.
+#[derive(Debug, Clone)]
+pub struct CorpusPool {
+    cache: AsyncLineCache,
+    members: Vec<(String, f64)>,
+}
+
+impl CorpusPool {
+    /// 基于给定缓存创建一个空的语料池（缓存可与其他调用方共享）
+    /// Create an empty pool on top of the given cache (which may be shared with other callers)
+    pub fn new(cache: AsyncLineCache) -> Self {
+        Self { cache, members: Vec::new() }
+    }
+
+    /// 加入一个成员文件及其权重；文件已存在时更新权重
+    /// Add a member file with its weight; updates the weight if the file is already a member
+    ///
+    /// 负数、NaN 或无穷大的权重按 0 处理，该文件不会被抽中。
+    /// Negative, NaN or infinite weights count as 0, so the file is never picked.
+    pub fn add(&mut self, filename: &str, weight: f64) -> &mut Self {
+        let weight = if weight.is_finite() { weight.max(0.0) } else { 0.0 };
+        match self.members.iter_mut().find(|(name, _)| name == filename) {
+            Some(member) => member.1 = weight,
+            None => self.members.push((filename.to_string(), weight)),
+        }
+        self
+    }
+
+    /// 移除一个成员文件，返回它之前是否在池中
+    /// Remove a member file, returning whether it was in the pool
+    pub fn remove(&mut self, filename: &str) -> bool {
+        let before = self.members.len();
+        self.members.retain(|(name, _)| name != filename);
+        self.members.len() != before
+    }
+
+    /// 成员文件数 | Number of member files
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// 是否没有任何成员文件 | Whether the pool has no member files
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// 按权重选中一个成员文件，并从中随机返回一行
+    /// Pick a member file by weight and return a random line from it
+    ///
+    /// 池为空、所有权重为 0，或选中的文件为空 / 不存在时返回 `None`。
+    /// Returns `None` when the pool is empty, every weight is 0, or the picked file is empty / missing.
+    pub async fn random_line(&self) -> std::io::Result<Option<String>> {
+        let picked = self
+            .members
+            .choose_weighted(&mut rand::thread_rng(), |(_, weight)| *weight)
+            .ok()
+            .map(|(name, _)| name.clone());
+        match picked {
+            Some(filename) => self.cache.random_line(&filename).await,
+            None => Ok(None),
+        }
+    }
+}
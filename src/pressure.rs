@@ -0,0 +1,55 @@
+//! 内存压力监视：主机可用内存低于阈值时在后台临时缩小缓存，压力解除后恢复
+//! Memory-pressure watching: a background task temporarily shrinks the cache while the host's available memory is below a threshold, and restores it afterwards
+
+use crate::AsyncLineCache;
+use std::time::Duration;
+use sysinfo::System;
+
+impl AsyncLineCache {
+    /// 启动后台任务，每隔 `interval` 采样一次可用内存；低于 `min_available` 字节时把容量上限降到当前预算的 `shrink_to` 倍（`0.0..=1.0`），回到阈值以上后恢复
+    /// Spawn a background task sampling available memory every `interval`; below `min_available` bytes it lowers the capacity limit to `shrink_to` (`0.0..=1.0`) times the current budget, restoring it once memory is back above the threshold
+    ///
+    /// - 缩小立即按最久未访问优先驱逐，每次压力期间只缩小一次；压力上限与 [`set_max_capacity`](Self::set_max_capacity) 分开保存，压力期间调用方设置的上限在压力解除后生效
+    /// - 可用内存取主机可用内存与所在 cgroup 剩余额度（Linux 上的 `memory.max` / `memory.limit_in_bytes` 减去当前用量）中较小者，因此在容器内按容器的限制判断
+    /// - 任务在 [`shutdown`](Self::shutdown) 时停止；需要 `sysinfo` 特性
+    ///
+    /// - Shrinking evicts least recently accessed entries at once, once per pressure episode; the pressure limit is kept apart from [`set_max_capacity`](Self::set_max_capacity), so a limit the caller sets during pressure applies once it ends
+    /// - Available memory is the smaller of the host's and what is left of the cgroup's limit (`memory.max` / `memory.limit_in_bytes` minus current usage, on Linux), so inside a container the container's limit decides
+    /// - The task stops on [`shutdown`](Self::shutdown); requires the `sysinfo` feature
+    ///
+    /// # Panics
+    ///
+    /// 不在 tokio 运行时中调用时 panic。| Panics when called outside a tokio runtime.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    pub fn watch_memory_pressure(&self, min_available: u64, interval: Duration, shrink_to: f64) {
+        let cache = self.clone();
+        let shrink_to = shrink_to.clamp(0.0, 1.0);
+        let interval = interval.max(Duration::from_millis(1));
+        self.tasks.spawn(async move {
+            let mut system = System::new();
+            let mut ticker = tokio::time::interval(interval);
+            let mut pressured = false;
+            loop {
+                ticker.tick().await;
+                let now = available_memory(&mut system) < min_available;
+                if now == pressured {
+                    continue;
+                }
+                pressured = now;
+                let limit = pressured.then(|| (cache.memory_budget() as f64 * shrink_to) as u64);
+                cache.watermarks.set_pressure(limit);
+                cache.shrink_to_capacity().await;
+            }
+        });
+    }
+}
+
+/// 主机可用内存；在 Linux 的 cgroup 中不超过 cgroup 的剩余额度
+/// The host's available memory, bounded by what is left of the cgroup's limit inside a Linux cgroup
+fn available_memory(system: &mut System) -> u64 {
+    system.refresh_memory();
+    let host = system.available_memory();
+    #[cfg(target_os = "linux")]
+    let host = crate::cgroup::memory_available().map_or(host, |left| host.min(left));
+    host
+}
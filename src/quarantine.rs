@@ -0,0 +1,98 @@
+//! 隔离区：反复加载失败的文件被隔离，目录级抽样与预加载不再重试它们
+//! Quarantine: files that keep failing to load are set aside, so directory-level sampling and preloading stop retrying them
+
+use crate::AsyncLineCache;
+use moka::future::Cache;
+use std::collections::BTreeSet;
+use std::io::ErrorKind;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+/// 连续失败多少次后隔离 | Consecutive failures before a file is quarantined
+const FAILURES_BEFORE_QUARANTINE: u32 = 3;
+
+/// 最多为多少个文件保留失败计数，超出时淘汰最久未失败的计数
+/// Most files a failure count is kept for; beyond it the counts failed least recently are dropped
+const MAX_COUNTED_FILES: u64 = 10_000;
+
+/// 失败计数在这段时间内没有新的失败时过期 | A failure count expires after this long without a new failure
+const FAILURE_TTL: Duration = Duration::from_hours(1);
+
+/// 连续失败计数与已隔离的文件，由所有克隆出的缓存实例共享
+/// Consecutive failure counts and quarantined files, shared by every clone of the cache
+///
+/// 计数有容量上限与过期时间，不会因为大量互不相同的失败文件名而无限增长。
+/// The counts are capped in number and expire, so a flood of distinct failing filenames cannot grow them without bound.
+#[derive(Debug, Clone)]
+pub(crate) struct Quarantine {
+    failures: Cache<String, u32>,
+    files: Arc<Mutex<BTreeSet<String>>>,
+}
+
+impl Default for Quarantine {
+    fn default() -> Self {
+        Self {
+            failures: Cache::builder().max_capacity(MAX_COUNTED_FILES).time_to_idle(FAILURE_TTL).build(),
+            files: Arc::default(),
+        }
+    }
+}
+
+impl Quarantine {
+    fn files(&self) -> std::sync::MutexGuard<'_, BTreeSet<String>> {
+        self.files.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 记录一次加载的结果：内容本身无效（编码错误、超长行等无效数据，或超过水位线）的失败累计计数，
+    /// 成功（包括文件不存在）清除计数并解除隔离；其他错误（根目录之外的路径、权限、超时等）不计数
+    /// Record the outcome of a load: failures caused by the content itself (invalid data such as bad encoding or
+    /// overlong lines, or a watermark rejection) add up, while a success (including a missing file) clears the count
+    /// and releases the file; other errors (paths outside the root, permissions, timeouts and so on) are not counted
+    pub(crate) async fn observe<T>(&self, filename: &str, result: &std::io::Result<T>) {
+        match result {
+            Ok(_) => {
+                self.failures.invalidate(filename).await;
+                self.files().remove(filename);
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::InvalidData | ErrorKind::OutOfMemory) => {
+                let failures = self
+                    .failures
+                    .entry(filename.to_string())
+                    .and_upsert_with(|count| async move { count.map_or(1, |count| count.into_value().saturating_add(1)) })
+                    .await
+                    .into_value();
+                if failures >= FAILURES_BEFORE_QUARANTINE {
+                    self.files().insert(filename.to_string());
+                }
+            }
+            Err(_) => {}
+        }
+    }
+
+    pub(crate) fn contains(&self, filename: &str) -> bool {
+        self.files().contains(filename)
+    }
+}
+
+impl AsyncLineCache {
+    /// 已隔离的文件（按路径排序）：因内容无效连续加载失败 3 次（编码错误、超长行、超过水位线等）后进入隔离区
+    /// Quarantined files, sorted by path: a file is quarantined after its content fails to load 3 times in a row (bad encoding, overlong lines, watermark rejections and so on)
+    ///
+    /// - [`random_line_in_dir`](Self::random_line_in_dir) 与 [`discover`](Self::discover) 的预加载跳过已隔离的文件
+    /// - 直接访问仍会尝试加载；某次加载成功时文件自动解除隔离
+    /// - 根目录之外的路径、权限不足、超时等与内容无关的错误不计入失败次数
+    ///
+    /// - [`random_line_in_dir`](Self::random_line_in_dir) and the preloading of [`discover`](Self::discover) skip quarantined files
+    /// - Direct access still attempts the load, and a file is released automatically once a load succeeds
+    /// - Errors unrelated to the content, such as paths outside the root, missing permissions or timeouts, do not count as failures
+    pub fn quarantined(&self) -> Vec<String> {
+        self.quarantine.files().iter().cloned().collect()
+    }
+
+    /// 手动解除隔离并清除失败计数，返回文件之前是否处于隔离中
+    /// Release a file from quarantine by hand and clear its failure count, returning whether it was quarantined
+    pub async fn release_quarantine(&self, filename: &str) -> bool {
+        self.quarantine.failures.invalidate(filename).await;
+        self.quarantine.files().remove(filename)
+    }
+}
@@ -0,0 +1,172 @@
+//! 按键前缀（目录）的字节配额：在写入时强制执行，独立于全局内存预算
+//! Byte quotas per key prefix (directory): enforced at insert time, independently of the global memory budget
+
+use crate::{AsyncLineCache, FileEntry};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// 一条前缀配额及其当前用量
+/// One prefix quota with its current usage
+#[derive(Debug)]
+struct Rule {
+    prefix: String,
+    limit: u64,
+    used: AtomicU64,
+}
+
+/// 配额表，由条目缓存的移除通知与写入路径共享
+/// Quota table, shared by the entry cache's removal notifications and the insert path
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Quotas {
+    rules: Arc<RwLock<Vec<Arc<Rule>>>>,
+}
+
+impl Quotas {
+    /// 覆盖 `filename` 的所有配额 | Every quota covering `filename`
+    fn matching(&self, filename: &str) -> Vec<Arc<Rule>> {
+        self.rules
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|rule| filename.starts_with(&rule.prefix))
+            .cloned()
+            .collect()
+    }
+
+    /// 条目已移除（任何原因）：退还其用量 | An entry was removed (for any reason): refund its usage
+    pub(crate) fn refund(&self, filename: &str, size: u64) {
+        for rule in self.matching(filename) {
+            rule.refund(size);
+        }
+    }
+}
+
+impl Rule {
+    /// 原子地为 `size` 字节预留用量，`replaced` 是写入时将被退还的旧版本权重；超出上限时不预留并返回 `false`
+    /// Atomically reserve `size` bytes, `replaced` being the weight of the old version refunded on insert; over the limit nothing is reserved and `false` is returned
+    fn reserve(&self, size: u64, replaced: u64) -> bool {
+        self.used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                (used.saturating_sub(replaced).saturating_add(size) <= self.limit).then_some(used + size)
+            })
+            .is_ok()
+    }
+
+    /// 退还 `size` 字节 | Refund `size` bytes
+    fn refund(&self, size: u64) {
+        // 配额在条目写入之后才设置时，用量可能从未计入 | usage may never have been charged if the quota was set after the insert
+        let _ = self.used.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| Some(used.saturating_sub(size)));
+    }
+}
+
+impl AsyncLineCache {
+    /// 为即将写入的 `entry` 在覆盖它的每条配额中预留用量，按最久未访问优先驱逐同一前缀下的其他条目腾出空间
+    /// Reserve usage for `entry` about to be inserted in every quota covering it, evicting other entries under the same prefix, least recently accessed first, to make room
+    ///
+    /// - 预留是原子的，并发加载不会一起越过配额；写入后条目的用量即为这次预留，移除时照常退还
+    /// - 单个条目就超出某条配额，或腾不出空间时返回 `false` 并退还已做的预留，该条目不应写入缓存
+    /// - 调用方必须持有该文件的加载锁
+    ///
+    /// - Reservations are atomic, so concurrent loads cannot overrun a quota together; once inserted, the entry's usage is this reservation, refunded on removal as usual
+    /// - Returns `false`, refunding the reservations made so far, when the entry alone exceeds a quota or no room can be made; the entry must not be cached
+    /// - The caller must hold the file's load lock
+    pub(crate) async fn admit_quotas(&self, filename: &str, entry: &FileEntry) -> bool {
+        let rules = self.quotas.matching(filename);
+        if rules.is_empty() {
+            return true;
+        }
+        let size = self.weighing.entry_weight(filename, entry);
+        // 被替换的旧版本会在写入时退还用量；先乐观地按没有旧版本预留，失败时再做不计为访问的遍历
+        // The replaced old version is refunded on insert; optimistically reserve as if there were none, walking the entries (which does not count as an access) only on failure
+        let mut victims: Option<Vec<(String, FileEntry)>> = None;
+        let mut replaced = 0;
+        let mut reserved: Vec<&Arc<Rule>> = Vec::with_capacity(rules.len());
+        for rule in &rules {
+            let mut flushed = false;
+            let admitted = loop {
+                if size > rule.limit {
+                    break false;
+                }
+                if rule.reserve(size, replaced) {
+                    break true;
+                }
+                let Some(candidates) = victims.as_mut() else {
+                    let mut all = self.entries_by_recency();
+                    if let Some(pos) = all.iter().position(|(key, _)| key == filename) {
+                        replaced = self.weighing.entry_weight(filename, &all.remove(pos).1);
+                    }
+                    victims = Some(all);
+                    continue;
+                };
+                match candidates.iter().position(|(key, _)| key.starts_with(&rule.prefix)) {
+                    // 移除通知立即退还用量 | the removal notice refunds the usage at once
+                    Some(pos) => {
+                        let (key, _) = candidates.remove(pos);
+                        self.evict_for(filename, &key).await;
+                    }
+                    // 批量失效的移除通知在后台维护中送达，先让用量追上再判断
+                    // Removal notices of bulk invalidation arrive during maintenance; let usage catch up before deciding
+                    None if !flushed => {
+                        self.entries.run_pending_tasks().await;
+                        flushed = true;
+                    }
+                    None => break false,
+                }
+            };
+            if !admitted {
+                for rule in reserved {
+                    rule.refund(size);
+                }
+                return false;
+            }
+            reserved.push(rule);
+        }
+        true
+    }
+
+    /// 为以 `prefix` 开头的所有文件设置总字节配额（如 `/data/logs/`），可在运行时随时调用
+    /// Set a total byte quota for every file whose path starts with `prefix` (e.g. `/data/logs/`); may be called at any time
+    ///
+    /// - 写入新条目会先驱逐同一前缀下的其他条目以满足配额；单个文件就超出配额时照常返回数据，但不写入缓存
+    /// - 末尾的 `*` 会被忽略，`/data/logs/*` 与 `/data/logs/` 等价；再次设置同一前缀会替换旧配额
+    /// - 配额独立于全局内存预算：条目仍会因全局预算不足而被驱逐
+    ///
+    /// - Inserting a new entry first evicts other entries under the same prefix to stay within the quota; a file that alone exceeds it is still returned, just not cached
+    /// - A trailing `*` is ignored, so `/data/logs/*` equals `/data/logs/`; setting the same prefix again replaces its quota
+    /// - Quotas are independent of the global budget: entries can still be evicted when the global budget runs out
+    pub fn set_quota(&self, prefix: &str, bytes: u64) {
+        let prefix = prefix.trim_end_matches('*').to_string();
+        let used = self
+            .entries
+            .iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .map(|(key, entry)| self.weighing.entry_weight(&key, &entry))
+            .sum();
+        let rule = Arc::new(Rule { prefix, limit: bytes, used: AtomicU64::new(used) });
+        let mut rules = self.quotas.rules.write().unwrap_or_else(PoisonError::into_inner);
+        rules.retain(|existing| existing.prefix != rule.prefix);
+        rules.push(rule);
+    }
+
+    /// 删除某个前缀的配额，返回之前是否设置过
+    /// Remove the quota of a prefix, returning whether one was set
+    pub fn remove_quota(&self, prefix: &str) -> bool {
+        let prefix = prefix.trim_end_matches('*');
+        let mut rules = self.quotas.rules.write().unwrap_or_else(PoisonError::into_inner);
+        let before = rules.len();
+        rules.retain(|rule| rule.prefix != prefix);
+        rules.len() != before
+    }
+
+    /// 所有配额的 `(前缀, 已用字节, 上限字节)`
+    /// `(prefix, used bytes, limit bytes)` of every quota
+    pub fn quota_usage(&self) -> Vec<(String, u64, u64)> {
+        self.quotas
+            .rules
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|rule| (rule.prefix.clone(), rule.used.load(Ordering::Relaxed), rule.limit))
+            .collect()
+    }
+}
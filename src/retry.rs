@@ -0,0 +1,92 @@
+//! 瞬时 I/O 错误的重试策略：网络文件系统偶尔返回的 `EIO` / `ESTALE` 等错误在缓存内部重试，调用方无需自行包装
+//! Retry policy for transient I/O errors: errors such as the `EIO` / `ESTALE` network filesystems return now and then are retried inside the cache, so callers need not wrap every call
+
+use std::future::Future;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+/// `EIO` 在所有 Unix 平台上的编号 | The number of `EIO` on every Unix platform
+#[cfg(unix)]
+const EIO: i32 = 5;
+
+/// 重试策略：总尝试次数、首次退避时长（之后每次翻倍）与可重试的错误
+/// A retry policy: total attempts, the first backoff (doubling after each retry) and which errors are retryable
+///
+/// 默认尝试 3 次、首次退避 10ms，重试 [`ErrorKind::Interrupted`]、[`ErrorKind::StaleNetworkFileHandle`]（`ESTALE`）与 Unix 上的 `EIO`。
+/// By default 3 attempts with a 10ms first backoff, retrying [`ErrorKind::Interrupted`], [`ErrorKind::StaleNetworkFileHandle`] (`ESTALE`) and `EIO` on Unix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    attempts: u32,
+    backoff: Duration,
+    kinds: Vec<ErrorKind>,
+    os_errors: Vec<i32>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            backoff: Duration::from_millis(10),
+            kinds: vec![ErrorKind::Interrupted, ErrorKind::StaleNetworkFileHandle],
+            #[cfg(unix)]
+            os_errors: vec![EIO],
+            #[cfg(not(unix))]
+            os_errors: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 默认策略 | The default policy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 总尝试次数（包括第一次，至少为 1）| Total attempts, the first included (at least 1)
+    #[must_use]
+    pub fn attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// 第一次重试前的等待时长，之后每次翻倍 | Wait before the first retry, doubling for each one after
+    #[must_use]
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// 只重试给定种类与操作系统错误码的错误，替换默认列表
+    /// Retry only errors of the given kinds and OS error codes, replacing the default lists
+    #[must_use]
+    pub fn retry_on(mut self, kinds: &[ErrorKind], os_errors: &[i32]) -> Self {
+        self.kinds = kinds.to_vec();
+        self.os_errors = os_errors.to_vec();
+        self
+    }
+
+    fn is_retryable(&self, error: &std::io::Error) -> bool {
+        self.kinds.contains(&error.kind()) || error.raw_os_error().is_some_and(|code| self.os_errors.contains(&code))
+    }
+
+    /// 按策略执行 `op`：可重试的错误在退避后重试，直到成功、遇到其他错误或用完尝试次数；没有策略时只执行一次
+    /// Run `op` under the policy: retryable errors are retried after a backoff until success, another error or the last attempt; without a policy it runs once
+    pub(crate) async fn run<T, F, Fut>(policy: Option<&Self>, mut op: F) -> std::io::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = std::io::Result<T>>,
+    {
+        let Some(policy) = policy else { return op().await };
+        let mut delay = policy.backoff;
+        for _ in 1..policy.attempts {
+            match op().await {
+                Err(e) if policy.is_retryable(&e) => {
+                    tokio::time::sleep(delay).await;
+                    delay = delay.saturating_mul(2);
+                }
+                result => return result,
+            }
+        }
+        op().await
+    }
+}
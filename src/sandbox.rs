@@ -0,0 +1,36 @@
+//! 根目录沙箱：只允许读取位于指定根目录之内的文件与目录，防止用户提供的路径穿越到根目录之外
+//! Root-directory sandbox: only files and directories inside the given root may be read, so user-supplied paths cannot traverse out of it
+
+use crate::AsyncLineCache;
+use std::borrow::Cow;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+impl AsyncLineCache {
+    /// 解析 `path`（展开 `..` 与符号链接）并确认它位于根目录之内，返回之后应当打开的路径；未设置根目录时原样返回
+    /// Resolve `path` (`..` and symlinks expanded) and check that it lies inside the root, returning the path to open from then on; without a root it is returned as is
+    ///
+    /// - 相对路径按根目录解析，而不是当前工作目录；调用方只应打开返回的已检查路径，而不是原始字符串
+    /// - 不存在的路径不会从磁盘读到任何内容，返回 `None`，交给之后的加载器处理
+    ///
+    /// - Relative paths resolve against the root, not the working directory; callers must open only the checked path returned, never the original string
+    /// - A missing path reads nothing from disk and yields `None`, leaving it to any registered loader
+    pub(crate) async fn confine(&self, path: &str) -> std::io::Result<Option<PathBuf>> {
+        let Some(root) = &self.root else { return Ok(Some(PathBuf::from(path))) };
+        match tokio::fs::canonicalize(root.join(path)).await {
+            Ok(resolved) if resolved.starts_with(root) => Ok(Some(resolved)),
+            Ok(_) => Err(Error::new(ErrorKind::PermissionDenied, format!("{path} is outside the root directory"))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 变更检测 stat 的路径：设置了根目录时相对路径按根目录解析，与 [`confine`](Self::confine) 一致
+    /// The path change detection stats: with a root, relative paths resolve against it, matching [`confine`](Self::confine)
+    pub(crate) fn resolve<'p>(&self, path: &'p str) -> Cow<'p, Path> {
+        match &self.root {
+            Some(root) => Cow::Owned(root.join(path)),
+            None => Cow::Borrowed(Path::new(path)),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! 作用域子缓存：与父缓存共享已加载的数据，但可以只清除自己引入的键
+//! Scoped child caches: share loaded data with the parent, but can clear just the keys they introduced
+
+use crate::AsyncLineCache;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// 作用域引入的键（写入前不在缓存中），由该作用域的所有克隆共享
+/// Keys introduced by a scope (absent from the cache before its insert), shared by every clone of that scope
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Scope {
+    introduced: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Scope {
+    /// 记录作用域内首次加载进缓存的键 | Record a key first brought into the cache within the scope
+    pub(crate) fn record(&self, filename: &str) {
+        let mut keys = self.introduced.lock().unwrap_or_else(PoisonError::into_inner);
+        if !keys.contains(filename) {
+            keys.insert(filename.to_string());
+        }
+    }
+
+    /// 取出并清空已记录的键 | Take and reset the recorded keys
+    fn take(&self) -> HashSet<String> {
+        std::mem::take(&mut *self.introduced.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl AsyncLineCache {
+    /// 创建一个轻量的作用域子缓存，适合按请求或按任务使用的临时场景
+    /// Create a cheap scoped child cache, for per-request or per-job scratch usage
+    ///
+    /// - 子缓存与父缓存共享同一份条目与全部配置：父缓存已加载的文件直接命中，子缓存加载的文件父缓存也能看到
+    /// - 子缓存的 [`clear`](Self::clear) 只移除由它自己加载进来的键，不影响父缓存原有的数据
+    ///
+    /// - The child shares entries and every setting with its parent: files the parent loaded are hits, and files the child loads are visible to the parent
+    /// - [`clear`](Self::clear) on the child only drops keys the child itself loaded, leaving the parent's data alone
+    #[must_use]
+    pub fn child(&self) -> AsyncLineCache {
+        let mut child = self.clone();
+        child.scope = Some(Scope::default());
+        child
+    }
+
+    /// 清除作用域内引入的键；返回 `false` 表示这不是作用域子缓存
+    /// Clear the keys introduced within the scope; `false` means this is not a scoped child
+    pub(crate) async fn clear_scope(&self) -> bool {
+        let Some(scope) = &self.scope else { return false };
+        for filename in scope.take() {
+            self.invalidate(&filename).await;
+        }
+        true
+    }
+}
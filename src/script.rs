@@ -0,0 +1,104 @@
+//! 文字系统检测：按 Unicode 区块统计抽样行中的字符，判断文件的主要文字（进而粗略区分语言）
+//! Script detection: counts the characters of sampled lines by Unicode block to find a file's dominant script (and so, roughly, its language)
+
+use crate::{AsyncLineCache, LineBuffer};
+
+/// 参与统计的最多行数，在整个文件中均匀抽取 | Maximum number of lines counted, sampled evenly across the file
+const SAMPLE_LINES: usize = 256;
+
+/// 文件的主要文字系统
+/// The dominant writing system of a file
+///
+/// 日文与中文都使用汉字：出现假名即判为 [`Japanese`](Script::Japanese)，只有汉字时判为 [`Han`](Script::Han)。
+/// Japanese and Chinese both use Han characters: any kana makes it [`Japanese`](Script::Japanese), Han alone is [`Han`](Script::Han).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Script {
+    /// 拉丁字母（英语、法语、德语、越南语等）| Latin (English, French, German, Vietnamese, ...)
+    Latin,
+    /// 西里尔字母（俄语、乌克兰语等）| Cyrillic (Russian, Ukrainian, ...)
+    Cyrillic,
+    /// 希腊字母 | Greek
+    Greek,
+    /// 阿拉伯字母（阿拉伯语、波斯语、乌尔都语等）| Arabic (Arabic, Persian, Urdu, ...)
+    Arabic,
+    /// 希伯来字母 | Hebrew
+    Hebrew,
+    /// 天城文（印地语、马拉地语等）| Devanagari (Hindi, Marathi, ...)
+    Devanagari,
+    /// 泰文 | Thai
+    Thai,
+    /// 韩文 | Hangul (Korean)
+    Hangul,
+    /// 假名与汉字（日语）| Kana with Han (Japanese)
+    Japanese,
+    /// 只有汉字（中文）| Han only (Chinese)
+    Han,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Self> {
+        Some(match c {
+            'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}' => Self::Latin,
+            '\u{0400}'..='\u{052F}' => Self::Cyrillic,
+            '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => Self::Greek,
+            '\u{0600}'..='\u{06FF}' | '\u{0750}'..='\u{077F}' => Self::Arabic,
+            '\u{0590}'..='\u{05FF}' => Self::Hebrew,
+            '\u{0900}'..='\u{097F}' => Self::Devanagari,
+            '\u{0E00}'..='\u{0E7F}' => Self::Thai,
+            '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}' => Self::Hangul,
+            '\u{3040}'..='\u{30FF}' => Self::Japanese,
+            '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' => Self::Han,
+            _ => return None,
+        })
+    }
+}
+
+/// 统计抽样行中各文字的字符数，返回最多的一种；没有可识别的字符时返回 `None`
+/// Count the characters of each script over the sampled lines and return the most frequent; `None` when nothing is recognized
+fn detect(lines: &LineBuffer) -> Option<Script> {
+    // 与声明顺序一致，按判别值下标 | in declaration order, indexed by discriminant
+    const SCRIPTS: [Script; 10] = [
+        Script::Latin,
+        Script::Cyrillic,
+        Script::Greek,
+        Script::Arabic,
+        Script::Hebrew,
+        Script::Devanagari,
+        Script::Thai,
+        Script::Hangul,
+        Script::Japanese,
+        Script::Han,
+    ];
+    let mut counts = [0usize; SCRIPTS.len()];
+    let step = lines.len().div_ceil(SAMPLE_LINES).max(1);
+    for line in lines.iter().step_by(step) {
+        for script in line.chars().filter_map(Script::of) {
+            counts[script as usize] += 1;
+        }
+    }
+    let kana = counts[Script::Japanese as usize];
+    let (best, &count) = counts.iter().enumerate().max_by_key(|&(_, count)| count)?;
+    match SCRIPTS[best] {
+        _ if count == 0 => None,
+        // 汉字在日文中往往多于假名 | Han characters often outnumber kana in Japanese text
+        Script::Han if kana > 0 => Some(Script::Japanese),
+        script => Some(script),
+    }
+}
+
+impl AsyncLineCache {
+    /// 检测文件的主要文字系统，结果按缓存条目记忆，用于把多语种语料分流给不同的生成器
+    /// Detect the file's dominant writing system, memoized on the cache entry, for routing multilingual corpora to the right generators
+    ///
+    /// - 只统计在整个文件中均匀抽取的最多 256 行，大文件也能很快完成
+    /// - 按文字系统而非具体语言判断：同为拉丁字母的英语与法语无法区分
+    /// - 文件为空、不存在或没有可识别的字母时返回 `None`
+    ///
+    /// - Only up to 256 lines sampled evenly across the file are counted, so large files finish quickly
+    /// - Detection is by script rather than language: English and French, both Latin, cannot be told apart
+    /// - Returns `None` when the file is empty, missing, or has no recognizable letters
+    pub async fn detect_language(&self, filename: &str) -> std::io::Result<Option<Script>> {
+        let Some(entry) = self.fresh_entry(filename).await? else { return Ok(None) };
+        Ok(*entry.derived().script.get_or_init(|| detect(entry.lines())))
+    }
+}
@@ -0,0 +1,83 @@
+//! 启动自检：用临时文件走一遍加载、重新校验与失效，尽早暴露文件系统或配置问题
+//! Startup self-test: runs a temp file through load, revalidation and invalidation to surface filesystem or configuration problems early
+
+use crate::AsyncLineCache;
+use std::io::{Error, ErrorKind};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const FIRST: &str = "linecache self-test";
+const SECOND: &str = "linecache self-test, rewritten";
+
+/// 自检各步骤的耗时 | Time taken by each self-test step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    load: Duration,
+    revalidate: Duration,
+    invalidate: Duration,
+}
+
+impl SelfTestReport {
+    /// 首次从磁盘加载的耗时 | Time of the first load from disk
+    pub fn load(&self) -> Duration {
+        self.load
+    }
+
+    /// 命中缓存并校验文件未变的耗时 | Time of a cache hit that checks the file is unchanged
+    pub fn revalidate(&self) -> Duration {
+        self.revalidate
+    }
+
+    /// 改写文件、使其失效并重新加载的耗时 | Time to rewrite the file, invalidate it and load it again
+    pub fn invalidate(&self) -> Duration {
+        self.invalidate
+    }
+}
+
+impl AsyncLineCache {
+    /// 在系统临时目录中创建一个文件，用当前配置完整走一遍加载、重新校验与失效，检查每一步读回的内容并计时
+    /// Create a file in the system temp directory and run it through load, revalidation and invalidation under the
+    /// current settings, checking what each step reads back and timing it
+    ///
+    /// - 读回的内容不符（例如行长度上限过小）时返回 [`ErrorKind::InvalidData`] 错误，文件系统错误原样返回
+    /// - 结束时临时文件被删除、条目被失效；注册的回调会看到这些事件
+    ///
+    /// - Content that does not read back as written (for example under a too-small line length limit) is an
+    ///   [`ErrorKind::InvalidData`] error; filesystem errors are returned as they are
+    /// - The temp file is deleted and its entry invalidated at the end; registered hooks see these events
+    pub async fn self_test(&self) -> std::io::Result<SelfTestReport> {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos());
+        let path = std::env::temp_dir().join(format!("linecache-self-test-{}-{nanos}.txt", std::process::id()));
+        let filename = path.to_string_lossy().into_owned();
+        let report = self.run_self_test(&path, &filename).await;
+        self.invalidate(&filename).await;
+        let _ = tokio::fs::remove_file(&path).await;
+        report
+    }
+
+    async fn run_self_test(&self, path: &std::path::Path, filename: &str) -> std::io::Result<SelfTestReport> {
+        tokio::fs::write(path, FIRST).await?;
+        let start = Instant::now();
+        expect(self.get_line(filename, 1).await?.as_deref(), FIRST)?;
+        let load = start.elapsed();
+
+        let start = Instant::now();
+        expect(self.get_line(filename, 1).await?.as_deref(), FIRST)?;
+        let revalidate = start.elapsed();
+
+        tokio::fs::write(path, SECOND).await?;
+        let start = Instant::now();
+        self.invalidate(filename).await;
+        expect(self.get_line(filename, 1).await?.as_deref(), SECOND)?;
+        let invalidate = start.elapsed();
+
+        Ok(SelfTestReport { load, revalidate, invalidate })
+    }
+}
+
+fn expect(line: Option<&str>, written: &str) -> std::io::Result<()> {
+    if line == Some(written) {
+        Ok(())
+    } else {
+        Err(Error::new(ErrorKind::InvalidData, format!("self-test wrote {written:?} but read back {line:?}")))
+    }
+}
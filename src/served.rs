@@ -0,0 +1,107 @@
+//! 按行统计随机接口的出现次数，用于需要均匀用到每一行的公平抽样
+//! Per-line counts of how often the random APIs served each line, for fairness-aware sampling that must use every line about equally
+
+use crate::{AsyncLineCache, FileEntry};
+use rand::Rng;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// 一个文件版本的逐行计数（与行下标一一对应）| Per-line counters of one version of a file, indexed like the lines
+pub(crate) type ServedCounts = Arc<[AtomicU32]>;
+
+/// 登记为需要统计的文件，由所有克隆出的缓存实例共享
+/// Files registered for serve counting, shared by every clone of the cache
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Served {
+    files: Arc<RwLock<HashSet<String>>>,
+}
+
+impl Served {
+    fn contains(&self, filename: &str) -> bool {
+        self.files.read().unwrap_or_else(PoisonError::into_inner).contains(filename)
+    }
+
+    /// 若文件已登记，为条目中被返回的各行计数 | If the file is registered, count each served line of the entry
+    pub(crate) fn record(&self, filename: &str, entry: &FileEntry, indices: impl IntoIterator<Item = usize>) {
+        if !self.contains(filename) {
+            return;
+        }
+        let counts = entry_counts(entry);
+        for idx in indices {
+            if let Some(count) = counts.get(idx) {
+                bump(count);
+            }
+        }
+    }
+}
+
+/// 条目的计数器（首次访问时分配，全部为 0）| The entry's counters, allocated zeroed on first access
+fn entry_counts(entry: &FileEntry) -> &ServedCounts {
+    entry.derived().served.get_or_init(|| (0..entry.lines().len()).map(|_| AtomicU32::new(0)).collect())
+}
+
+/// 计数加一，到达上限后保持不变 | Add one, saturating at the maximum
+fn bump(count: &AtomicU32) {
+    let _ = count.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_add(1));
+}
+
+impl AsyncLineCache {
+    /// 开启或关闭文件的出现次数统计：开启后随机接口每返回一行就为该行计数
+    /// Turn serve counting on or off for the file: once on, every line returned by the random APIs is counted
+    ///
+    /// - 统计 [`random_line`](Self::random_line)、[`random_line_excluding`](Self::random_line_excluding)、[`random_lines`](Self::random_lines) 与 [`least_served_line`](Self::least_served_line) 返回的行
+    /// - 每行一个 `u32` 计数器，随条目保存；文件重新加载或条目被驱逐时从 0 重新开始
+    ///
+    /// - Lines returned by [`random_line`](Self::random_line), [`random_line_excluding`](Self::random_line_excluding), [`random_lines`](Self::random_lines) and [`least_served_line`](Self::least_served_line) are counted
+    /// - One `u32` counter per line, kept with the entry; counting starts over from 0 when the file is reloaded or its entry evicted
+    pub fn track_served(&self, filename: &str, enabled: bool) {
+        let mut files = self.served.files.write().unwrap_or_else(PoisonError::into_inner);
+        if enabled {
+            files.insert(filename.to_string());
+        } else {
+            files.remove(filename);
+        }
+    }
+
+    /// 返回出现次数最少的一行及其行号（从 1 开始），并为它计数；多行并列时随机选择其一
+    /// Return the least served line with its 1-based number, counting it as served; ties are broken at random
+    ///
+    /// - 反复调用会轮流用到每一行；并发调用可能偶尔选中同一行
+    /// - 文件未开启统计时等同于随机抽取；文件为空或不存在时返回 `None`
+    ///
+    /// - Repeated calls cycle through every line; concurrent calls may occasionally pick the same one
+    /// - Without serve counting this is a plain random pick; returns `None` for an empty or missing file
+    pub async fn least_served_line(&self, filename: &str) -> std::io::Result<Option<(usize, String)>> {
+        let Some(entry) = self.fresh_entry(filename).await? else { return Ok(None) };
+        let lines = entry.lines();
+        let mut rng = rand::thread_rng();
+        if !self.served.contains(filename) {
+            let len = lines.content_len();
+            return Ok((len > 0).then(|| rng.gen_range(0..len)).and_then(|idx| Some((idx + 1, lines.get(idx)?.to_string()))));
+        }
+        let counts = entry_counts(&entry);
+        let mut best: Option<(u32, usize)> = None;
+        let mut ties = 0u32;
+        for (idx, count) in counts.iter().enumerate().take(lines.content_len()) {
+            let count = count.load(Ordering::Relaxed);
+            match best {
+                Some((least, _)) if count > least => {}
+                Some((least, _)) if count == least => {
+                    // 蓄水池抽样，在并列的行中均匀选择 | reservoir sampling, uniform among the tied lines
+                    ties += 1;
+                    if rng.gen_range(0..ties) == 0 {
+                        best = Some((count, idx));
+                    }
+                }
+                _ => {
+                    best = Some((count, idx));
+                    ties = 1;
+                }
+            }
+        }
+        let Some((_, idx)) = best else { return Ok(None) };
+        self.served.record(filename, &entry, [idx]);
+        Ok(lines.get(idx).map(|line| (idx + 1, line.to_string())))
+    }
+}
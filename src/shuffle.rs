@@ -0,0 +1,124 @@
+//! 把缓存文件的行随机打乱后写入新文件
+//! Write a randomly permuted copy of a cached file's lines
+
+use crate::{AsyncLineCache, LineBuffer};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+
+/// 默认情况下在内存中一次打乱的最大行数（只打乱行下标，不复制行文本）
+/// Default maximum number of lines permuted in memory at once (only line indices are permuted; the text is never copied)
+const IN_MEMORY_LINES: usize = 1 << 22;
+
+impl AsyncLineCache {
+    /// 将文件的行随机打乱后流式写入 `dest`，返回写入的行数
+    /// Stream a randomly permuted copy of the file's lines to `dest`, returning the number of lines written
+    ///
+    /// - 每行之后写一个 `\n`；文件末尾换行符带来的兼容空行不参与打乱
+    /// - 行数较少时直接在缓存的行索引上打乱下标；超过约 400 万行时改用分块外部打乱，见 [`write_shuffled_in_chunks`](Self::write_shuffled_in_chunks)
+    ///
+    /// - Every line is followed by `\n`; the compatibility empty line produced by a final newline is not shuffled
+    /// - Small files are permuted by index over the cached line index; beyond about 4M lines a chunked external shuffle is used, see [`write_shuffled_in_chunks`](Self::write_shuffled_in_chunks)
+    pub async fn write_shuffled(&self, filename: &str, dest: impl AsRef<Path>) -> std::io::Result<usize> {
+        self.write_shuffled_in_chunks(filename, dest, IN_MEMORY_LINES).await
+    }
+
+    /// 同 [`write_shuffled`](Self::write_shuffled)，但由调用方指定内存中一次最多打乱多少行
+    /// Same as [`write_shuffled`](Self::write_shuffled), but the caller chooses how many lines are permuted in memory at once
+    ///
+    /// 行数超过 `chunk_lines` 时做两遍外部打乱：先把每行随机分配到 `dest` 旁边的若干临时分块文件，
+    /// 再逐个读回分块、在内存中打乱后追加到 `dest`，结果仍是均匀随机排列；临时文件在结束时删除。
+    /// Above `chunk_lines` lines a two-pass external shuffle runs: every line is first dealt to a random temporary
+    /// chunk file next to `dest`, then each chunk is read back, permuted in memory and appended to `dest`,
+    /// which still yields a uniformly random permutation; the temporary files are removed afterwards.
+    pub async fn write_shuffled_in_chunks(
+        &self,
+        filename: &str,
+        dest: impl AsRef<Path>,
+        chunk_lines: usize,
+    ) -> std::io::Result<usize> {
+        let lines = self.fresh_lines(filename).await?;
+        let count = lines.content_len();
+        let dest = dest.as_ref();
+        let chunk_lines = chunk_lines.max(1);
+        let mut rng = StdRng::from_entropy();
+        let mut out = BufWriter::new(File::create(dest).await?);
+
+        if count <= chunk_lines {
+            let mut order: Vec<usize> = (0..count).collect();
+            order.shuffle(&mut rng);
+            write_lines(&mut out, order.iter().filter_map(|&idx| lines.get(idx))).await?;
+        } else {
+            external_shuffle(&lines, count, dest, chunk_lines, &mut rng, &mut out).await?;
+        }
+        out.flush().await?;
+        Ok(count)
+    }
+}
+
+/// 逐行写出，每行之后加 `\n` | Write lines one by one, each followed by `\n`
+async fn write_lines<'a, W: AsyncWrite + Unpin>(
+    out: &mut W,
+    lines: impl Iterator<Item = &'a str>,
+) -> std::io::Result<()> {
+    for line in lines {
+        out.write_all(line.as_bytes()).await?;
+        out.write_all(b"\n").await?;
+    }
+    Ok(())
+}
+
+/// 两遍外部打乱：随机分发到分块文件，再逐块打乱写出
+/// Two-pass external shuffle: deal lines to random chunk files, then permute and write out each chunk
+async fn external_shuffle<W: AsyncWrite + Unpin>(
+    lines: &LineBuffer,
+    count: usize,
+    dest: &Path,
+    chunk_lines: usize,
+    rng: &mut StdRng,
+    out: &mut W,
+) -> std::io::Result<()> {
+    let chunks = count.div_ceil(chunk_lines);
+    let paths: Vec<PathBuf> = (0..chunks)
+        .map(|i| {
+            let mut name = dest.as_os_str().to_os_string();
+            name.push(format!(".shuffle-{i}.tmp"));
+            PathBuf::from(name)
+        })
+        .collect();
+
+    let result: std::io::Result<()> = async {
+        let mut writers = Vec::with_capacity(chunks);
+        for path in &paths {
+            writers.push(BufWriter::new(File::create(path).await?));
+        }
+        for line in lines.iter_range(0, count) {
+            let writer = &mut writers[rng.gen_range(0..chunks)];
+            writer.write_all(line.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+        }
+        for writer in &mut writers {
+            writer.flush().await?;
+        }
+        drop(writers);
+
+        for path in &paths {
+            let chunk = tokio::fs::read_to_string(path).await?;
+            // 每行都以 '\n' 结尾，最后一段为空 | every line ends with '\n', so the last piece is empty
+            let mut chunk_lines: Vec<&str> = chunk.split('\n').collect();
+            chunk_lines.pop();
+            chunk_lines.shuffle(rng);
+            write_lines(out, chunk_lines.into_iter()).await?;
+        }
+        Ok(())
+    }
+    .await;
+
+    for path in &paths {
+        let _ = tokio::fs::remove_file(path).await; // 尽力清理 | best-effort cleanup
+    }
+    result
+}
@@ -0,0 +1,34 @@
+//! 逐行元数据旁路文件：`<file>.meta.jsonl` 的第 n 条记录注解主文件的第 n 行
+//! Per-line metadata sidecars: record n of `<file>.meta.jsonl` annotates line n of the main file
+
+use crate::AsyncLineCache;
+
+/// 主文件对应的旁路文件路径 | Sidecar path of a main file
+pub(crate) fn sidecar_path(filename: &str) -> String {
+    format!("{filename}.meta.jsonl")
+}
+
+impl AsyncLineCache {
+    /// 获取第 `lineno` 行（从 1 开始）及旁路文件 `<file>.meta.jsonl` 中对应的元数据记录
+    /// Get line `lineno` (1-based) with its metadata record from the sidecar `<file>.meta.jsonl`
+    ///
+    /// - 元数据以原始 JSON 文本返回（如 `{"tags":["greeting"],"weight":2}`），由调用方按自己的结构解析
+    /// - 旁路文件不存在、记录缺失或为空行时元数据为 `None`；行本身超出范围时返回 `Ok(None)`
+    /// - 旁路文件与主文件一样缓存并各自检测变更；[`invalidate`](Self::invalidate) 主文件时一并失效
+    ///
+    /// - Metadata is returned as raw JSON text (e.g. `{"tags":["greeting"],"weight":2}`) for the caller to parse into its own types
+    /// - The metadata is `None` when the sidecar is missing or its record is absent or blank; `Ok(None)` means the line itself is out of range
+    /// - The sidecar is cached like the main file, each checked for changes on its own; [`invalidate`](Self::invalidate) on the main file drops it too
+    pub async fn get_line_with_meta(
+        &self,
+        filename: &str,
+        lineno: usize,
+    ) -> std::io::Result<Option<(String, Option<String>)>> {
+        let Some(line) = self.get_line(filename, lineno).await? else { return Ok(None) };
+        let meta = self
+            .get_line(&sidecar_path(filename), lineno)
+            .await?
+            .filter(|record| !record.trim().is_empty());
+        Ok(Some((line, meta)))
+    }
+}
@@ -0,0 +1,144 @@
+//! 基于共享行缓存的零拷贝视图与迭代器
+//! Zero-copy views and iterators over the shared cached lines
+
+use crate::CachedLines;
+
+/// 缓存文件中一段连续行的零拷贝视图（内部只持有 `Arc` 与区间）
+/// Zero-copy view of a contiguous run of lines in a cached file (holds only an `Arc` and a range)
+///
+/// 即使缓存随后重新加载了该文件，视图仍然指向创建时的版本。
+/// The view keeps pointing at the version it was created from, even if the cache reloads the file later.
+#[derive(Debug, Clone)]
+pub struct LineSlice {
+    lines: CachedLines,
+    start: usize,
+    end: usize,
+}
+
+impl LineSlice {
+    /// 由行向量与从 0 开始的下标区间构造视图（调用方保证区间合法）
+    /// Build a view from the lines and a 0-based index range (caller guarantees the range is valid)
+    pub(crate) fn new(lines: CachedLines, start: usize, end: usize) -> Self {
+        debug_assert!(start <= end && end <= lines.len());
+        Self { lines, start, end }
+    }
+
+    /// 视图中的行数
+    /// Number of lines in the view
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// 视图是否为空
+    /// Whether the view is empty
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// 视图第一行在文件中的行号（从 1 开始）
+    /// Line number (1-based) of the first line of the view within the file
+    pub fn first_lineno(&self) -> usize {
+        self.start + 1
+    }
+
+    /// 获取视图内第 `idx` 行（从 0 开始）
+    /// Get the `idx`-th line within the view (0-based)
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        if idx < self.len() {
+            self.lines.get(self.start + idx)
+        } else {
+            None
+        }
+    }
+
+    /// 按顺序遍历视图中的行
+    /// Iterate over the lines of the view in order
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &str> + '_ {
+        self.lines.iter_range(self.start, self.end)
+    }
+
+    /// 复制为独立的 `Vec<String>`
+    /// Copy into an owned `Vec<String>`
+    pub fn to_vec(&self) -> Vec<String> {
+        self.iter().map(String::from).collect()
+    }
+}
+
+/// `AsyncLineCache::windows` 返回的滑动窗口迭代器，每个窗口都是零拷贝的 [`LineSlice`]
+/// Sliding-window iterator returned by `AsyncLineCache::windows`; every window is a zero-copy [`LineSlice`]
+#[derive(Debug, Clone)]
+pub struct LineWindows {
+    lines: CachedLines,
+    size: usize,
+    pos: usize,
+}
+
+impl LineWindows {
+    pub(crate) fn new(lines: CachedLines, size: usize) -> Self {
+        Self { lines, size, pos: 0 }
+    }
+
+    /// 尚未产出的窗口数量
+    /// Number of windows not yet yielded
+    fn remaining(&self) -> usize {
+        if self.size == 0 {
+            0
+        } else {
+            (self.lines.len() + 1).saturating_sub(self.pos + self.size)
+        }
+    }
+}
+
+impl Iterator for LineWindows {
+    type Item = LineSlice;
+
+    fn next(&mut self) -> Option<LineSlice> {
+        if self.remaining() == 0 {
+            return None;
+        }
+        let window = LineSlice::new(self.lines.clone(), self.pos, self.pos + self.size);
+        self.pos += 1;
+        Some(window)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.remaining();
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for LineWindows {}
+
+/// `AsyncLineCache::numbered_lines` 返回的迭代器，按顺序产出 `(行号, 行内容)`，行号从 1 开始
+/// Iterator returned by `AsyncLineCache::numbered_lines`, yielding `(lineno, line)` in order with 1-based numbers
+///
+/// 迭代器持有创建时那一版行数据的 `Arc`，遍历过程中文件被重新加载也不会错位。
+/// The iterator holds an `Arc` to the lines it was created from, so numbering stays consistent even if the file is reloaded mid-iteration.
+#[derive(Debug, Clone)]
+pub struct NumberedLines {
+    lines: CachedLines,
+    pos: usize,
+}
+
+impl NumberedLines {
+    pub(crate) fn new(lines: CachedLines) -> Self {
+        Self { lines, pos: 0 }
+    }
+}
+
+impl Iterator for NumberedLines {
+    type Item = (usize, String);
+
+    fn next(&mut self) -> Option<(usize, String)> {
+        let line = self.lines.get(self.pos)?.to_string();
+        self.pos += 1;
+        Some((self.pos, line))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.lines.len() - self.pos;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for NumberedLines {}
@@ -0,0 +1,70 @@
+//! 快照：固定一组文件的当前版本，批处理任务在缓存并发重新加载时仍然读到一致的冻结视图
+//! Snapshots: pin the current versions of a set of files, so a batch job reads one consistent frozen view while the cache reloads concurrently
+
+use crate::{AsyncLineCache, CachedLines, FileEntry};
+use rand::Rng;
+use std::collections::HashMap;
+
+/// 一组文件在同一时刻的版本，读取完全在内存中进行，不再访问缓存或磁盘
+/// The versions of a set of files at one point in time; reads are purely in memory, never touching the cache or the disk again
+///
+/// 快照持有各版本的共享引用：缓存替换或驱逐条目后旧版本仍然可读，快照被丢弃时才释放。
+/// The snapshot holds shared references to each version: old versions stay readable after the cache replaces or evicts them, and are released when the snapshot is dropped.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    entries: HashMap<String, FileEntry>,
+}
+
+impl Snapshot {
+    /// 快照中是否包含该文件（文件不存在时不包含）| Whether the snapshot holds the file (missing files are left out)
+    pub fn contains(&self, filename: &str) -> bool {
+        self.entries.contains_key(filename)
+    }
+
+    /// 快照中文件的条目 | The file's entry in the snapshot
+    pub fn entry(&self, filename: &str) -> Option<&FileEntry> {
+        self.entries.get(filename)
+    }
+
+    /// 快照中文件的所有行 | All lines of the file in the snapshot
+    pub fn lines(&self, filename: &str) -> Option<&CachedLines> {
+        self.entries.get(filename).map(FileEntry::lines)
+    }
+
+    /// 快照中文件的第 `lineno` 行（从 1 开始）| Line `lineno` (1-based) of the file in the snapshot
+    pub fn get_line(&self, filename: &str, lineno: usize) -> Option<&str> {
+        self.lines(filename)?.get(lineno.checked_sub(1)?)
+    }
+
+    /// 快照中文件的随机一行 | A random line of the file in the snapshot
+    pub fn random_line(&self, filename: &str) -> Option<&str> {
+        let lines = self.lines(filename)?;
+        if lines.is_empty() {
+            return None;
+        }
+        lines.get(rand::thread_rng().gen_range(0..lines.len()))
+    }
+
+    /// 快照中文件的代号，见 [`AsyncLineCache::generation`] | The file's generation in the snapshot, see [`AsyncLineCache::generation`]
+    pub fn generation(&self, filename: &str) -> Option<u64> {
+        self.entries.get(filename).map(FileEntry::generation)
+    }
+}
+
+impl AsyncLineCache {
+    /// 为一组文件拍摄快照，固定它们当前的版本（必要时先加载）
+    /// Take a snapshot of a set of files, pinning their current versions (loading them first if needed)
+    ///
+    /// 拍摄期间持有所有成员的加载锁，与 [`invalidate_group`](Self::invalidate_group) 互斥，因此不会拍到新旧混杂的组合。
+    /// All members' load locks are held while it is taken, excluding [`invalidate_group`](Self::invalidate_group), so it never captures a mix of old and new versions.
+    pub async fn snapshot(&self, files: &[&str]) -> std::io::Result<Snapshot> {
+        let _guards = self.locks.lock_many(files).await;
+        let mut entries = HashMap::with_capacity(files.len());
+        for &filename in files {
+            if let Some(entry) = self.locked_fresh_entry(filename).await? {
+                entries.insert(filename.to_string(), entry);
+            }
+        }
+        Ok(Snapshot { entries })
+    }
+}
@@ -0,0 +1,94 @@
+//! 排序视图：按需构建并随条目缓存的行排序置换（只保存下标），支持二分查找与前缀查询
+//! Sorted views: a sorted permutation of a file's lines (indices only), built on demand and cached with the entry, for binary search and prefix queries
+
+use crate::{AsyncLineCache, FileEntry};
+use std::sync::Arc;
+
+/// 文件各行按字节序排列后的虚拟文件，第 1 行是最小的行
+/// A virtual file of the file's lines in byte order, line 1 being the smallest
+///
+/// - 排序置换按源文件的缓存条目记忆：同一版本的源文件只排序一次，源文件变更或条目被驱逐后重新排序
+/// - 只保存下标，不复制行文本；源文件末尾换行符产生的兼容空行不参与排序
+///
+/// - The permutation is memoized on the source's cache entry: a given version is sorted once, and again after a change or eviction
+/// - Only indices are kept, never copies of the line text; the compatibility empty line after the source's final newline is not sorted
+#[derive(Debug, Clone)]
+pub struct SortedView {
+    cache: AsyncLineCache,
+    source: String,
+}
+
+impl SortedView {
+    /// 源文件名 | Source filename
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 行数 | Number of lines
+    pub async fn line_count(&self) -> std::io::Result<usize> {
+        Ok(self.sorted().await?.map_or(0, |(_, order)| order.len()))
+    }
+
+    /// 排序后的第 `rank` 行（从 1 开始）；超出范围时返回 `None`
+    /// Line `rank` in sorted order (1-based); `None` when out of range
+    pub async fn get_line(&self, rank: usize) -> std::io::Result<Option<String>> {
+        let Some((entry, order)) = self.sorted().await? else { return Ok(None) };
+        Ok(rank
+            .checked_sub(1)
+            .and_then(|idx| order.get(idx))
+            .and_then(|&idx| entry.lines().get(idx))
+            .map(String::from))
+    }
+
+    /// 二分查找完全等于 `line` 的行，返回其在源文件中的行号（从 1 开始；有重复时返回其中一个）
+    /// Binary-search for a line equal to `line`, returning its 1-based line number in the source (any one of them when duplicated)
+    pub async fn find(&self, line: &str) -> std::io::Result<Option<usize>> {
+        let Some((entry, order)) = self.sorted().await? else { return Ok(None) };
+        let lines = entry.lines();
+        Ok(order
+            .binary_search_by(|&idx| lines.get(idx).unwrap_or_default().cmp(line))
+            .ok()
+            .map(|pos| order[pos] + 1))
+    }
+
+    /// 以 `prefix` 开头的行，按排序顺序最多返回 `limit` 行
+    /// Lines starting with `prefix`, at most `limit` of them, in sorted order
+    pub async fn with_prefix(&self, prefix: &str, limit: usize) -> std::io::Result<Vec<String>> {
+        let Some((entry, order)) = self.sorted().await? else { return Ok(Vec::new()) };
+        let lines = entry.lines();
+        let line = |idx: usize| lines.get(idx).unwrap_or_default();
+        let start = order.partition_point(|&idx| line(idx) < prefix);
+        Ok(order[start..]
+            .iter()
+            .map(|&idx| line(idx))
+            .take_while(|line| line.starts_with(prefix))
+            .take(limit)
+            .map(String::from)
+            .collect())
+    }
+
+    /// 源文件的最新条目 | The source's current entry
+    pub(crate) async fn entry(&self) -> std::io::Result<Option<FileEntry>> {
+        self.cache.fresh_entry(&self.source).await
+    }
+
+    /// 源文件的最新条目及其排序置换 | The source's current entry with its sorted permutation
+    pub(crate) async fn sorted(&self) -> std::io::Result<Option<(FileEntry, Arc<[usize]>)>> {
+        let Some(entry) = self.entry().await? else { return Ok(None) };
+        let order = Arc::clone(entry.derived().sorted.get_or_init(|| {
+            let lines = entry.lines();
+            let mut order: Vec<usize> = (0..lines.content_len()).collect();
+            order.sort_by(|&a, &b| lines.get(a).cmp(&lines.get(b)));
+            order.into()
+        }));
+        Ok(Some((entry, order)))
+    }
+}
+
+impl AsyncLineCache {
+    /// 创建 `filename` 的排序视图，见 [`SortedView`]；排序在首次访问时才进行
+    /// Create a sorted view of `filename`, see [`SortedView`]; sorting happens on first access
+    pub fn sorted_view(&self, filename: &str) -> SortedView {
+        SortedView { cache: self.clone(), source: filename.to_string() }
+    }
+}
@@ -0,0 +1,84 @@
+//! stat 结果微缓存：记录每个文件最近一次通过 stat 校验的时刻，TTL 可在运行时调整
+//! Stat-result micro-cache: remembers when each file last passed its stat check, with a TTL adjustable at runtime
+
+use crate::clock::{nanos, SharedClock};
+use moka::future::Cache;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// 最多记录的文件数 | Maximum number of files remembered
+const CAPACITY: u64 = 8192;
+
+/// 最近通过 stat 校验的文件；TTL 为 0 时关闭，既不记录也不命中；关闭变更检测时一律视为刚校验过
+/// Files that recently passed their stat check; a TTL of 0 disables it, so nothing is recorded or hit; with change detection off, every file counts as just checked
+#[derive(Debug)]
+pub(crate) struct RecentStats {
+    checked: Cache<String, Duration>,
+    ttl_nanos: AtomicU64,
+    revalidate: AtomicBool,
+    clock: SharedClock,
+}
+
+impl RecentStats {
+    pub(crate) fn new(ttl: Duration, capacity: Option<u64>, clock: SharedClock) -> Self {
+        Self {
+            checked: Cache::new(capacity.unwrap_or(CAPACITY)),
+            ttl_nanos: AtomicU64::new(nanos(ttl)),
+            revalidate: AtomicBool::new(true),
+            clock,
+        }
+    }
+
+    /// 是否对已缓存的文件做变更检测 | Whether cached files are checked for changes
+    pub(crate) fn revalidates(&self) -> bool {
+        self.revalidate.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_revalidate(&self, enabled: bool) {
+        self.revalidate.store(enabled, Ordering::Relaxed);
+    }
+
+    pub(crate) fn capacity(&self) -> Option<u64> {
+        self.checked.policy().max_capacity()
+    }
+
+    pub(crate) fn ttl(&self) -> Duration {
+        Duration::from_nanos(self.ttl_nanos.load(Ordering::Relaxed))
+    }
+
+    /// 调整 TTL，立即对所有已记录的文件生效 | Change the TTL, applying at once to every recorded file
+    pub(crate) fn set_ttl(&self, ttl: Duration) {
+        self.ttl_nanos.store(nanos(ttl), Ordering::Relaxed);
+        if ttl.is_zero() {
+            self.checked.invalidate_all();
+        }
+    }
+
+    /// 文件是否在 TTL 内通过过 stat 校验（关闭变更检测时总是成立）
+    /// Whether the file passed a stat check within the TTL (always, with change detection off)
+    pub(crate) async fn is_recent(&self, filename: &str) -> bool {
+        !self.revalidates() || self.within_ttl(filename).await
+    }
+
+    /// 文件是否在 TTL 内通过过 stat 校验，不考虑变更检测开关
+    /// Whether the file passed a stat check within the TTL, regardless of the change-detection switch
+    pub(crate) async fn within_ttl(&self, filename: &str) -> bool {
+        let ttl = self.ttl();
+        !ttl.is_zero() && self.checked.get(filename).await.is_some_and(|at| self.clock.now().saturating_sub(at) < ttl)
+    }
+
+    /// 记录文件刚刚通过 stat 校验 | Record that the file just passed a stat check
+    pub(crate) async fn mark(&self, filename: &str) {
+        if !self.ttl().is_zero() {
+            self.checked.insert(filename.to_string(), self.clock.now()).await;
+        }
+    }
+
+    pub(crate) async fn remove(&self, filename: &str) {
+        self.checked.remove(filename).await;
+    }
+
+    pub(crate) fn clear(&self) {
+        self.checked.invalidate_all();
+    }
+}
@@ -0,0 +1,97 @@
+//! 阻塞版行缓存：基于 `std::fs` 与 moka 同步缓存，无需 tokio 运行时，适合普通的命令行工具
+//! Blocking line cache built on `std::fs` and moka's sync cache, needing no tokio runtime, for plain command-line tools
+
+use crate::{CachedLines, LineBuffer};
+use moka::sync::Cache;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// 变更检测用的文件指纹：mtime + 大小 | File fingerprint for change detection: mtime + size
+type Stamp = (SystemTime, u64);
+
+/// 同步行缓存，提供与 [`AsyncLineCache`](crate::AsyncLineCache) 相同的 `get_line` / `get_lines` / `invalidate` 接口
+/// Synchronous line cache offering the same `get_line` / `get_lines` / `invalidate` surface as [`AsyncLineCache`](crate::AsyncLineCache)
+///
+/// - 每次访问都以 mtime + 大小检测文件变更，行切分规则（包括末尾换行符之后的兼容空行）与异步版相同
+/// - 同一文件的并发未命中可能各自读取一次文件；克隆出的实例共享同一份缓存
+/// - 不支持加载器、加载时处理、水位线等异步版的扩展功能
+///
+/// - Every access checks the file for changes by mtime + size, and lines are split exactly as in the async cache (the compatibility empty line after a final newline included)
+/// - Concurrent misses of one file may each read it; clones share one cache
+/// - Loaders, load-time processing, watermarks and the other extensions of the async cache are not supported
+#[derive(Debug, Clone)]
+pub struct LineCache {
+    entries: Cache<String, (CachedLines, Stamp)>,
+}
+
+impl LineCache {
+    /// 以内存预算（字节）创建实例 | Create an instance with a memory budget in bytes
+    pub fn new_with_capacity(bytes: u64) -> Self {
+        let entries = Cache::builder()
+            .max_capacity(bytes)
+            .weigher(|_: &String, (lines, _): &(CachedLines, Stamp)| {
+                u32::try_from(lines.entry_size()).unwrap_or(u32::MAX)
+            })
+            .build();
+        Self { entries }
+    }
+
+    /// 获取第 `lineno` 行（从 1 开始）；行号超出范围或文件不存在时返回 `None`
+    /// Get the `lineno`-th line (1-based); `None` when out of range or when the file does not exist
+    pub fn get_line(&self, filename: &str, lineno: usize) -> std::io::Result<Option<String>> {
+        let lines = self.fresh_lines(filename)?;
+        Ok(lines.and_then(|lines| lines.get(lineno.wrapping_sub(1)).map(String::from)))
+    }
+
+    /// 获取文件全部行；文件不存在或为空时返回 `None`
+    /// Get all lines of the file; `None` when the file does not exist or is empty
+    pub fn get_lines(&self, filename: &str) -> std::io::Result<Option<Vec<String>>> {
+        let lines = self.fresh_lines(filename)?;
+        Ok(lines.filter(|lines| !lines.is_empty()).map(|lines| lines.to_vec()))
+    }
+
+    /// 使指定文件的条目失效 | Invalidate the entry of a file
+    pub fn invalidate(&self, filename: &str) {
+        self.entries.invalidate(filename);
+    }
+
+    /// 清空全部缓存 | Clear the whole cache
+    pub fn clear(&self) {
+        self.entries.invalidate_all();
+    }
+
+    /// 当前的加权内存占用（字节），可能短暂滞后 | Current weighted memory usage in bytes, which may briefly lag
+    pub fn memory_usage(&self) -> u64 {
+        self.entries.weighted_size()
+    }
+
+    /// 通过变更检测的已缓存行，否则重新读取；文件不存在时返回 `None`
+    /// The cached lines if they pass change detection, otherwise a fresh read; `None` when the file does not exist
+    fn fresh_lines(&self, filename: &str) -> std::io::Result<Option<CachedLines>> {
+        let meta = match std::fs::metadata(filename) {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.entries.invalidate(filename);
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        let stamp = (meta.modified()?, meta.len());
+        if let Some((lines, cached)) = self.entries.get(filename) {
+            if cached == stamp {
+                return Ok(Some(lines));
+            }
+        }
+        let text = match std::fs::read_to_string(filename) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.entries.invalidate(filename);
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        let lines = Arc::new(LineBuffer::new(text));
+        self.entries.insert(filename.to_string(), (Arc::clone(&lines), stamp));
+        Ok(Some(lines))
+    }
+}
@@ -0,0 +1,86 @@
+//! 测试用同步点：在变更检测、失效与加载之间注入等待，用于编写确定性的并发竞争测试
+//! Test sync points: inject waits between change detection, invalidation and loading, for deterministic race tests
+//!
+//! 注册接口只在启用 `test-hooks` 特性时存在；未启用时同步点是空操作，不占用任何字段开销。
+//! The registration API exists only with the `test-hooks` feature; without it the sync points are no-ops with no field overhead.
+
+#[cfg(feature = "test-hooks")]
+use crate::AsyncLineCache;
+use std::fmt;
+#[cfg(feature = "test-hooks")]
+use std::future::Future;
+#[cfg(feature = "test-hooks")]
+use std::pin::Pin;
+#[cfg(feature = "test-hooks")]
+use std::sync::Arc;
+
+/// 缓存内部可以注入等待的位置 | A place inside the cache where a wait can be injected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncPoint {
+    /// 变更检测判定条目缺失或过期之后、等待加载锁之前
+    /// After change detection found the entry missing or stale, before waiting for the load lock
+    Checked,
+    /// 已持有加载锁并再次确认需要加载之后、读取文件之前
+    /// With the load lock held and the reload confirmed, before the file is read
+    Load,
+    /// 已持有加载锁、移除条目之前（`invalidate` 与 `invalidate_group` 的每个键）
+    /// With the load lock held, before the entry is removed (each key of `invalidate` and `invalidate_group`)
+    Invalidate,
+}
+
+/// 同步点回调：收到位置与文件名，返回的 future 完成后缓存才继续执行
+/// A sync-point callback: given the point and the filename, the cache only proceeds once the returned future completes
+#[cfg(feature = "test-hooks")]
+pub type SyncHook = Arc<dyn Fn(SyncPoint, String) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// 已注册的同步点回调（可能没有），由所有克隆出的缓存实例及其命名空间共享
+/// The registered sync-point callback, if any, shared by every clone of the cache and its namespaces
+#[derive(Clone, Default)]
+pub(crate) struct SyncPoints {
+    #[cfg(feature = "test-hooks")]
+    hook: Option<SyncHook>,
+}
+
+impl SyncPoints {
+    /// 到达同步点：等待回调返回的 future 完成 | Reach a sync point: wait for the callback's future to complete
+    #[cfg_attr(not(feature = "test-hooks"), allow(clippy::unused_async))]
+    pub(crate) async fn reach(&self, point: SyncPoint, filename: &str) {
+        #[cfg(feature = "test-hooks")]
+        if let Some(hook) = &self.hook {
+            hook(point, filename.to_string()).await;
+        }
+        #[cfg(not(feature = "test-hooks"))]
+        let _ = (point, filename);
+    }
+}
+
+impl fmt::Debug for SyncPoints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "test-hooks")]
+        let registered = self.hook.is_some();
+        #[cfg(not(feature = "test-hooks"))]
+        let registered = false;
+        f.debug_struct("SyncPoints").field("registered", &registered).finish()
+    }
+}
+
+#[cfg(feature = "test-hooks")]
+impl AsyncLineCache {
+    /// 注册同步点回调，替换之前注册的回调；仅用于测试，不要在生产环境启用 `test-hooks` 特性
+    /// Register a sync-point callback, replacing any registered before; for tests only, never enable the `test-hooks` feature in production
+    ///
+    /// - 缓存在每个 [`SyncPoint`] 处调用 `hook` 并等待返回的 future，测试可借此暂停一个任务、放行另一个任务，复现特定的交错顺序
+    /// - 回调在持有加载锁时也会被等待（[`SyncPoint::Load`] 与 [`SyncPoint::Invalidate`]），此时同一文件的其他加载与失效都会排队
+    ///
+    /// - The cache calls `hook` at every [`SyncPoint`] and awaits the returned future, so a test can park one task and release another to reproduce an exact interleaving
+    /// - Some points are awaited with the load lock held ([`SyncPoint::Load`] and [`SyncPoint::Invalidate`]), queueing every other load and invalidation of that file meanwhile
+    #[must_use]
+    pub fn with_sync_hook<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(SyncPoint, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.sync_points.hook = Some(Arc::new(move |point, filename| Box::pin(hook(point, filename))));
+        self
+    }
+}
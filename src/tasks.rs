@@ -0,0 +1,30 @@
+//! 缓存派生的后台任务（如预加载队列），由 `shutdown` 统一停止
+//! Background tasks spawned by the cache (such as preload queues), stopped together by `shutdown`
+
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::task::JoinSet;
+
+/// 正在运行的后台任务，由所有克隆出的缓存实例共享
+/// Running background tasks, shared by every clone of the cache
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tasks {
+    set: Arc<Mutex<JoinSet<()>>>,
+}
+
+impl Tasks {
+    /// 在当前运行时上启动后台任务，顺带回收已完成的任务
+    /// Spawn a background task on the current runtime, reaping finished ones along the way
+    pub(crate) fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        let mut set = self.set.lock().unwrap_or_else(PoisonError::into_inner);
+        while set.try_join_next().is_some() {}
+        set.spawn(task);
+    }
+
+    /// 中止所有后台任务并等待它们退出 | Abort every background task and wait for them to exit
+    pub(crate) async fn stop(&self) {
+        let mut set = std::mem::take(&mut *self.set.lock().unwrap_or_else(PoisonError::into_inner));
+        set.abort_all();
+        while set.join_next().await.is_some() {}
+    }
+}
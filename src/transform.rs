@@ -0,0 +1,228 @@
+//! 加载时的内容处理：在建立行索引之前按配置改写读入的文本
+//! Load-time content processing: rewrites the text that was read, as configured, before the line index is built
+
+use crate::AsyncLineCache;
+use std::collections::HashSet;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// 超长行的处理方式 | How over-long lines are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineLengthPolicy {
+    /// 拒绝加载整个文件，返回 `InvalidData` 错误 | Refuse to load the whole file with an `InvalidData` error
+    Error,
+    /// 截断到上限，丢弃其余部分 | Cut the line at the limit, dropping the rest
+    Truncate,
+    /// 切分为多行，每行不超过上限（之后的行号随之后移）| Split it into several lines within the limit (shifting later line numbers)
+    SplitIntoChunks,
+}
+
+/// 加载时处理的统计，随条目保存 | Statistics of load-time processing, kept with the entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadStats {
+    sanitized_chars: usize,
+    dropped_duplicates: usize,
+}
+
+impl LoadStats {
+    /// 被清理掉的控制字符与零宽字符数 | Number of control and zero-width characters removed
+    pub fn sanitized_chars(&self) -> usize {
+        self.sanitized_chars
+    }
+
+    /// 加载时去掉的重复行数 | Number of duplicate lines dropped at load
+    pub fn dropped_duplicates(&self) -> usize {
+        self.dropped_duplicates
+    }
+}
+
+/// 加载处理配置 | Load-processing settings
+#[derive(Debug, Clone, Copy, Default)]
+struct Settings {
+    max_line: Option<(usize, LineLengthPolicy)>,
+    sanitize: bool,
+}
+
+/// 当前的加载处理配置，由所有克隆出的缓存实例共享
+/// Current load-processing settings, shared by every clone of the cache
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Transforms {
+    settings: Arc<RwLock<Settings>>,
+    dedupe: Arc<RwLock<HashSet<String>>>,
+}
+
+impl Transforms {
+    pub(crate) fn set_max_line(&self, max: usize, policy: LineLengthPolicy) {
+        self.settings.write().unwrap_or_else(PoisonError::into_inner).max_line = Some((max.max(1), policy));
+    }
+
+    pub(crate) fn set_sanitize(&self, enabled: bool) {
+        self.settings.write().unwrap_or_else(PoisonError::into_inner).sanitize = enabled;
+    }
+
+    pub(crate) fn set_dedupe(&self, filename: &str, enabled: bool) {
+        let mut dedupe = self.dedupe.write().unwrap_or_else(PoisonError::into_inner);
+        if enabled {
+            dedupe.insert(filename.to_string());
+        } else {
+            dedupe.remove(filename);
+        }
+    }
+
+    /// 按配置处理 `filename` 读入的文本 | Process the text read for `filename` as configured
+    pub(crate) fn apply(&self, filename: &str, mut text: String) -> std::io::Result<(String, LoadStats)> {
+        let settings = *self.settings.read().unwrap_or_else(PoisonError::into_inner);
+        let mut stats = LoadStats::default();
+        if settings.sanitize {
+            stats.sanitized_chars = sanitize(&mut text);
+        }
+        if let Some((max, policy)) = settings.max_line {
+            text = limit_line_length(filename, text, max, policy)?;
+        }
+        if self.dedupe.read().unwrap_or_else(PoisonError::into_inner).contains(filename) {
+            (text, stats.dropped_duplicates) = dedupe(text);
+        }
+        Ok((text, stats))
+    }
+}
+
+/// 是否为需要清理的字符：除换行与制表符以外的控制字符，以及零宽字符
+/// Whether a character is to be removed: control characters other than line breaks and tabs, plus zero-width code points
+fn is_unwanted(c: char) -> bool {
+    (c.is_control() && !matches!(c, '\n' | '\r' | '\t')) || matches!(c, '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}')
+}
+
+/// 原地删除需要清理的字符，返回删除的字符数；没有时不改动文本
+/// Remove unwanted characters in place, returning how many were removed; the text is untouched when there are none
+fn sanitize(text: &mut String) -> usize {
+    let removed = text.chars().filter(|&c| is_unwanted(c)).count();
+    if removed > 0 {
+        text.retain(|c| !is_unwanted(c));
+    }
+    removed
+}
+
+/// 去掉重复的行（保留首次出现的位置与原来的分隔符），返回处理后的文本与去掉的行数
+/// Drop duplicate lines (keeping first occurrences and their original separators), returning the text and the number dropped
+fn dedupe(text: String) -> (String, usize) {
+    let mut seen = HashSet::new();
+    let mut out = String::with_capacity(text.len());
+    let mut dropped = 0;
+    let mut last_dropped = false;
+    for segment in text.split_inclusive('\n') {
+        last_dropped = !seen.insert(segment.trim_end_matches(['\n', '\r']));
+        if last_dropped {
+            dropped += 1;
+        } else {
+            out.push_str(segment);
+        }
+    }
+    if dropped == 0 {
+        return (text, 0);
+    }
+    // 去掉的是没有换行符的末行时，去掉保留部分的末尾换行，免得多出一个兼容空行
+    // When the dropped line was a final one without a newline, drop the kept text's final newline so no compatibility empty line appears
+    if last_dropped && !text.ends_with('\n') {
+        let separator = if out.ends_with("\r\n") { 2 } else { usize::from(out.ends_with('\n')) };
+        out.truncate(out.len() - separator);
+    }
+    (out, dropped)
+}
+
+/// 把超过 `max` 字节的行按 `policy` 处理；没有超长行时原样返回，不复制文本
+/// Apply `policy` to lines longer than `max` bytes; text without such lines is returned as is, without copying
+fn limit_line_length(filename: &str, text: String, max: usize, policy: LineLengthPolicy) -> std::io::Result<String> {
+    if text.split('\n').all(|line| line.trim_end_matches('\r').len() <= max) {
+        return Ok(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    for (idx, segment) in text.split_inclusive('\n').enumerate() {
+        let body = segment.trim_end_matches(['\n', '\r']);
+        let separator = &segment[body.len()..];
+        if body.len() <= max {
+            out.push_str(segment);
+            continue;
+        }
+        match policy {
+            LineLengthPolicy::Error => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("line {} of {filename} is {} bytes, above the limit of {max}", idx + 1, body.len()),
+                ));
+            }
+            LineLengthPolicy::Truncate => {
+                out.push_str(&body[..char_floor(body, max)]);
+                out.push_str(separator);
+            }
+            LineLengthPolicy::SplitIntoChunks => {
+                let mut rest = body;
+                while !rest.is_empty() {
+                    // 上限小于单个字符时也至少前进一个字符 | always advance by at least one character, even below one character's width
+                    let cut = match char_floor(rest, max) {
+                        0 => rest.chars().next().map_or(rest.len(), char::len_utf8),
+                        cut => cut,
+                    };
+                    out.push_str(&rest[..cut]);
+                    rest = &rest[cut..];
+                    out.push_str(if rest.is_empty() { separator } else { "\n" });
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// 不超过 `max` 的最大字符边界 | The largest char boundary not above `max`
+fn char_floor(text: &str, max: usize) -> usize {
+    (0..=max.min(text.len())).rev().find(|&idx| text.is_char_boundary(idx)).unwrap_or(0)
+}
+
+impl AsyncLineCache {
+    /// 启用加载时清理：删除控制字符（保留换行与制表符）与零宽字符（U+200B~U+200D、U+2060、U+FEFF）
+    /// Enable load-time sanitizing: removes control characters (keeping line breaks and tabs) and zero-width code points (U+200B-U+200D, U+2060, U+FEFF)
+    ///
+    /// - 抓取来的词表中这些字符会污染下游生成的文本；删除的个数见 [`load_stats`](Self::load_stats)
+    /// - 零宽连接符（U+200D）同样被删除，组合表情会拆成各自的字符
+    /// - 只作用于之后开始的加载
+    ///
+    /// - Such characters in scraped wordlists corrupt downstream generated text; the number removed is reported by [`load_stats`](Self::load_stats)
+    /// - The zero-width joiner (U+200D) is removed too, so joined emoji fall apart into their parts
+    /// - Only applies to loads starting afterwards
+    #[must_use]
+    pub fn with_sanitize(self, enabled: bool) -> Self {
+        self.transforms.set_sanitize(enabled);
+        self
+    }
+
+    /// 为单个文件开启或关闭加载时去重：按行切分时去掉重复的行，只保留首次出现的一行
+    /// Turn load-time deduplication on or off for one file: duplicate lines are dropped while splitting, keeping the first occurrence
+    ///
+    /// - 去掉的行数见 [`load_stats`](Self::load_stats)；之后的行号随之前移
+    /// - 会使该文件已缓存的条目失效，下次访问时按新设置重新加载
+    ///
+    /// - The number dropped is reported by [`load_stats`](Self::load_stats); later line numbers shift up accordingly
+    /// - Invalidates the file's cached entry, so the next access reloads it under the new setting
+    pub async fn set_dedupe_on_load(&self, filename: &str, enabled: bool) {
+        self.transforms.set_dedupe(filename, enabled);
+        self.invalidate(filename).await;
+    }
+
+    /// 文件当前版本在加载时处理的统计；文件不存在且没有加载器可用时返回 `None`
+    /// Load-time processing statistics of the file's current version; `None` when the file is missing and no loader helps
+    pub async fn load_stats(&self, filename: &str) -> std::io::Result<Option<LoadStats>> {
+        Ok(self.fresh_entry(filename).await?.map(|entry| entry.load_stats()))
+    }
+
+    /// 限制单行的最大字节数，防止单行的超大文件（如压缩过的 JSON）变成一个无法细粒度驱逐的巨型条目
+    /// Cap the length of a single line in bytes, so a huge single-line file (such as minified JSON) cannot become one giant entry that defeats fine-grained eviction
+    ///
+    /// - 超长行按 `policy` 报错、截断或切分为多行；长度不含行尾的 `\n` / `\r\n`
+    /// - 只作用于之后开始的加载，已缓存的条目保持不变；同样作用于加载器提供的源码
+    ///
+    /// - Over-long lines fail, are truncated or split into several lines according to `policy`; the length excludes the trailing `\n` / `\r\n`
+    /// - Only applies to loads starting afterwards, leaving cached entries alone; source from loaders is processed too
+    #[must_use]
+    pub fn with_max_line_length(self, max: usize, policy: LineLengthPolicy) -> Self {
+        self.transforms.set_max_line(max, policy);
+        self
+    }
+}
@@ -0,0 +1,32 @@
+//! 旁路读取：沿用缓存的行切分与加载时处理，但从不写入缓存，适合一次性的批处理任务
+//! Bypass reads: the cache's line splitting and load-time processing without ever inserting entries, for one-shot batch jobs
+
+use crate::{AsyncLineCache, CachedLines};
+
+impl AsyncLineCache {
+    /// 读取并解析文件的全部行，但不写入缓存；已缓存且未变更的条目直接复用
+    /// Read and parse all the file's lines without caching them; a cached, unchanged entry is reused as is
+    ///
+    /// - 加载时处理（解码、清理、行长度限制）、沙箱、加载超时、重试与加载内存上限照常生效，已注册的加载器照常作为后备来源
+    /// - 不触发加载回调、不计入配额与水位线，也不会驱逐任何已缓存的条目
+    /// - 文件不存在且没有加载器可用时返回 `None`
+    ///
+    /// - Load-time processing (decoding, sanitizing, line-length limits), the sandbox, the load timeout, retries and the load memory cap apply as usual, and registered loaders still serve as fallback
+    /// - No load hooks fire, nothing counts against quotas or watermarks, and no cached entry is ever evicted
+    /// - Returns `None` when the file is missing and no loader helps
+    pub async fn lines_uncached(&self, filename: &str) -> std::io::Result<Option<CachedLines>> {
+        if let Some(entry) = self.cached_fresh_entry(filename).await? {
+            return Ok(Some(entry.lines().clone()));
+        }
+        let gate = self.load_gate.current();
+        let (entry, _reserved) = self.read_entry(filename, &gate).await?;
+        Ok(entry.map(|entry| entry.lines().clone()))
+    }
+
+    /// 同 `get_line`（从 1 开始计数），但不写入缓存，见 [`lines_uncached`](Self::lines_uncached)
+    /// Same as `get_line` (1-based), but without caching, see [`lines_uncached`](Self::lines_uncached)
+    pub async fn get_line_uncached(&self, filename: &str, lineno: usize) -> std::io::Result<Option<String>> {
+        let lines = self.lines_uncached(filename).await?;
+        Ok(lines.and_then(|lines| lines.get(lineno.wrapping_sub(1)).map(String::from)))
+    }
+}
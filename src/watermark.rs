@@ -0,0 +1,213 @@
+//! 软/硬内存水位线：在写入时同步执行，先于后台驱逐生效
+//! Soft/hard memory watermarks: enforced synchronously at insert time, ahead of background eviction
+
+use crate::{AsyncLineCache, FileEntry};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 未设置的水位线 | An unset watermark
+const OFF: u64 = u64::MAX;
+
+/// 写入会使缓存占用超过硬水位线，条目被拒绝
+/// An insert would take the cache above its hard watermark, so the entry was rejected
+///
+/// 以 [`std::io::ErrorKind::OutOfMemory`] 类型的 `io::Error` 返回，可通过 `get_ref()` 向下转型取得。
+/// Returned as an `io::Error` of kind [`std::io::ErrorKind::OutOfMemory`]; downcast it via `get_ref()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatermarkExceeded {
+    filename: String,
+    size: u64,
+    hard: u64,
+}
+
+impl WatermarkExceeded {
+    /// 被拒绝的文件名 | The rejected filename
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// 被拒绝条目的加权大小（字节）| Weighted size of the rejected entry in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// 当时生效的硬水位线（字节）| The hard watermark in effect, in bytes
+    pub fn hard(&self) -> u64 {
+        self.hard
+    }
+}
+
+impl fmt::Display for WatermarkExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "caching {} ({} bytes) would exceed the hard watermark of {} bytes", self.filename, self.size, self.hard)
+    }
+}
+
+impl std::error::Error for WatermarkExceeded {}
+
+/// 当前的水位线、运行时容量上限与单条目上限，由所有克隆出的缓存实例共享
+/// The current watermarks, runtime capacity limit and per-entry limit, shared by every clone of the cache
+#[derive(Debug)]
+pub(crate) struct Watermarks {
+    soft: AtomicU64,
+    hard: AtomicU64,
+    /// 调用方设置的容量上限 | The capacity limit set by the caller
+    capacity: AtomicU64,
+    /// 内存压力期间临时施加的上限，与调用方的上限分开保存，压力解除后调用方的上限照旧生效
+    /// The limit imposed temporarily under memory pressure, kept apart from the caller's so the caller's applies again afterwards
+    pressure: AtomicU64,
+    max_entry: AtomicU64,
+}
+
+impl Default for Watermarks {
+    fn default() -> Self {
+        Self {
+            soft: AtomicU64::new(OFF),
+            hard: AtomicU64::new(OFF),
+            capacity: AtomicU64::new(OFF),
+            pressure: AtomicU64::new(OFF),
+            max_entry: AtomicU64::new(OFF),
+        }
+    }
+}
+
+impl Watermarks {
+    pub(crate) fn set(&self, soft: u64, hard: u64) {
+        self.soft.store(soft.min(hard), Ordering::Relaxed);
+        self.hard.store(hard, Ordering::Relaxed);
+    }
+
+    /// 生效的运行时容量上限（调用方的上限与压力上限中较小者）；都未设置时为 `None`
+    /// The runtime capacity limit in effect (the smaller of the caller's and the pressure limit); `None` when neither is set
+    pub(crate) fn capacity(&self) -> Option<u64> {
+        let capacity = self.capacity.load(Ordering::Relaxed).min(self.pressure.load(Ordering::Relaxed));
+        (capacity != OFF).then_some(capacity)
+    }
+
+    /// 设置或解除内存压力上限 | Set or lift the memory-pressure limit
+    #[cfg(feature = "sysinfo")]
+    pub(crate) fn set_pressure(&self, limit: Option<u64>) {
+        self.pressure.store(limit.unwrap_or(OFF), Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_max_entry(&self, bytes: u64) {
+        self.max_entry.store(bytes, Ordering::Relaxed);
+    }
+
+    /// 内容超过单条目上限（`limit` 覆盖全局上限）时返回其字节数
+    /// The content's size in bytes, if it exceeds the per-entry limit (`limit` overrides the global one)
+    pub(crate) fn oversize(&self, entry: &FileEntry, limit: Option<u64>) -> Option<u64> {
+        let bytes = entry.raw().len() as u64;
+        (bytes > limit.unwrap_or_else(|| self.max_entry.load(Ordering::Relaxed))).then_some(bytes)
+    }
+}
+
+impl AsyncLineCache {
+    /// 为即将写入的 `entry` 检查水位线：超过软水位线（或更低的运行时容量上限）时按最久未访问优先同步驱逐其他条目，仍超过硬水位线时拒绝写入
+    /// Check the watermarks for `entry` about to be inserted: above soft (or a lower runtime capacity limit), other entries are evicted synchronously, least recently accessed first; still above hard, the insert is rejected
+    ///
+    /// 调用方必须持有该文件的加载锁。| The caller must hold the file's load lock.
+    pub(crate) async fn admit_watermarks(&self, filename: &str, entry: &FileEntry) -> std::io::Result<()> {
+        let marks = &self.watermarks;
+        let soft = marks.soft.load(Ordering::Relaxed).min(marks.capacity().unwrap_or(OFF));
+        let hard = marks.hard.load(Ordering::Relaxed);
+        if soft == OFF {
+            return Ok(());
+        }
+        let size = self.weighing.entry_weight(filename, entry);
+        // 加权占用在后台维护中更新，先让它追上再逐个扣除被驱逐的条目；写入只发生在加载之后，这次维护的开销相对很小
+        // The weighted size is updated during maintenance, so let it catch up, then deduct each evicted entry ourselves;
+        // inserts only follow a load, so this maintenance pass is cheap in comparison
+        self.entries.run_pending_tasks().await;
+        let mut usage = self.entries.weighted_size().saturating_add(size);
+        if usage <= soft {
+            return Ok(());
+        }
+        // 被替换的旧版本从同一次遍历中取得，不做计为访问的查找 | the replaced version comes from the same pass, without a lookup that counts as an access
+        let mut victims = self.entries_by_recency();
+        if let Some(pos) = victims.iter().position(|(key, _)| key == filename) {
+            let (_, old) = victims.remove(pos);
+            usage = usage.saturating_sub(self.weighing.entry_weight(filename, &old));
+        }
+        for (key, _) in victims {
+            if usage <= soft {
+                break;
+            }
+            if let Some(old) = self.evict_for(filename, &key).await {
+                usage = usage.saturating_sub(self.weighing.entry_weight(&key, &old));
+            }
+        }
+        if usage > hard {
+            let exceeded = WatermarkExceeded { filename: filename.to_string(), size, hard };
+            return Err(std::io::Error::new(std::io::ErrorKind::OutOfMemory, exceeded));
+        }
+        Ok(())
+    }
+
+    /// 设置软/硬内存水位线（字节），比总预算更早、更确定地约束内存
+    /// Set soft and hard memory watermarks in bytes, bounding memory earlier and more predictably than the total budget
+    ///
+    /// - 写入会使占用超过 `soft` 时，先同步驱逐其他条目，直到回到 `soft` 以下
+    /// - 驱逐后仍会超过 `hard`（通常是单个大文件）时，拒绝写入并返回 [`WatermarkExceeded`] 错误，旧条目同时移除
+    /// - `soft` 大于 `hard` 时按 `hard` 处理；命名空间不继承水位线
+    ///
+    /// - An insert that would take usage above `soft` first evicts other entries synchronously until usage is back under `soft`
+    /// - If it would still exceed `hard` afterwards (typically one giant file), the insert is rejected with a [`WatermarkExceeded`] error and the old entry is removed
+    /// - A `soft` above `hard` is treated as `hard`; namespaces do not inherit watermarks
+    #[must_use]
+    pub fn with_watermarks(self, soft: u64, hard: u64) -> Self {
+        self.watermarks.set(soft, hard);
+        self
+    }
+
+    /// 在运行时调整条目缓存的容量上限（字节），并立即按最久未访问优先驱逐条目，直到占用回到新上限以下
+    /// Change the entry cache's capacity limit in bytes at runtime, evicting least recently accessed entries at once until usage is back under it
+    ///
+    /// - 底层缓存的预算在构造时固定，上限可以在它之下任意调低、调高；超过构造时的预算时返回 [`std::io::ErrorKind::InvalidInput`] 错误且不做任何改变。需要更大的调整空间时，以可能用到的最大预算构造缓存，启动后再调低
+    /// - 设置为构造时的预算即取消限制；之后的写入像软水位线一样先同步驱逐其他条目；[`memory_budget`](Self::memory_budget) 返回生效的上限；命名空间不继承该上限
+    ///
+    /// - The underlying cache's budget is fixed at construction; below it the limit can be lowered and raised freely, while a limit above it fails with [`std::io::ErrorKind::InvalidInput`] and changes nothing. For more headroom, build with the largest budget you may need and lower it at startup
+    /// - Setting the construction budget lifts the limit; later inserts evict other entries synchronously, like a soft watermark; [`memory_budget`](Self::memory_budget) returns the limit in effect; namespaces do not inherit it
+    pub async fn set_max_capacity(&self, bytes: u64) -> std::io::Result<()> {
+        if bytes > self.budget {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("capacity {bytes} exceeds the construction-time budget of {} bytes", self.budget),
+            ));
+        }
+        self.watermarks.capacity.store(if bytes == self.budget { OFF } else { bytes }, Ordering::Relaxed);
+        self.shrink_to_capacity().await;
+        Ok(())
+    }
+
+    /// 按最久未访问优先驱逐条目，直到占用回到生效的容量上限以下
+    /// Evict least recently accessed entries until usage is back under the capacity limit in effect
+    pub(crate) async fn shrink_to_capacity(&self) {
+        let limit = self.memory_budget();
+        self.entries.run_pending_tasks().await;
+        let mut usage = self.entries.weighted_size();
+        for filename in self.eviction_candidates(usize::MAX).await {
+            if usage <= limit {
+                break;
+            }
+            let _guard = self.locks.lock(&filename).await;
+            if let Some(old) = self.remove_entry(&filename).await {
+                usage = usage.saturating_sub(self.weighing.entry_weight(&filename, &old));
+            }
+        }
+    }
+
+    /// 单个条目的内容上限（字节）：更大的文件照常读取并返回数据，但从不写入缓存
+    /// Content limit of a single entry in bytes: larger files are read and served as usual, but never cached
+    ///
+    /// - 跳过写入时触发 [`CacheHooks::on_oversize`](crate::CacheHooks::on_oversize) 回调，可用于告警；之前缓存的旧版本同时移除
+    /// - 未缓存的大文件每次访问都会重新读取；命名空间不继承该上限
+    ///
+    /// - Skipped inserts fire the [`CacheHooks::on_oversize`](crate::CacheHooks::on_oversize) callback, for warnings; an older cached version is removed at the same time
+    /// - An uncached large file is read again on every access; namespaces do not inherit the limit
+    #[must_use]
+    pub fn with_max_entry_bytes(self, bytes: u64) -> Self {
+        self.watermarks.set_max_entry(bytes);
+        self
+    }
+}
@@ -0,0 +1,95 @@
+//! 条目权重计算与条目缓存的构建
+//! Entry weighing and construction of the entry cache
+//!
+//! 每个条目只有一份文本（原始内容即行缓冲区的文本），因此整个缓存共用一个内存预算，无需再在多个缓存之间划分。
+//! Every entry holds its text once (the raw content is the line buffer's text), so the whole cache shares one
+//! memory budget and nothing has to be split between several caches.
+
+use crate::hooks::Hooks;
+use crate::quota::Quotas;
+use crate::builder::EntryPolicy;
+use crate::{CachedLines, FileEntry};
+use moka::future::{Cache, CacheBuilder};
+use moka::notification::RemovalCause;
+use std::fmt;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// 自定义权重函数：根据路径与行数据给出条目的权重（字节）
+/// A custom weigher: gives an entry's weight in bytes from its path and lines
+pub(crate) type Weigher = Arc<dyn Fn(&str, &CachedLines) -> u32 + Send + Sync>;
+
+/// 权重计算方式
+/// Weighing strategy
+#[derive(Clone, Default)]
+pub(crate) enum Weighing {
+    /// 基于容量的估算（默认）| Capacity-based estimate (default)
+    #[default]
+    Capacity,
+    /// 分配器报告的真实分配大小 | Allocator-reported usable size
+    #[cfg(all(feature = "alloc-weigher", target_os = "linux"))]
+    Allocator,
+    /// 调用方提供的权重函数 | A caller-supplied weigher
+    Custom(Weigher),
+}
+
+impl Weighing {
+    /// 条目的权重（字节），也是它计入前缀配额与水位线的大小
+    /// Weight of an entry in bytes, which is also what it counts against prefix quotas and watermarks
+    ///
+    /// 写入之前为登记文件构建的派生数据（如倒排索引）按估计大小计入，与权重方式无关。
+    /// Derived data built before the insert for registered files (such as the inverted index) is added at its estimated size, whatever the strategy.
+    pub(crate) fn entry_weight(&self, filename: &str, entry: &FileEntry) -> u64 {
+        let lines = match self {
+            Weighing::Capacity => entry.lines().entry_size() as u64,
+            #[cfg(all(feature = "alloc-weigher", target_os = "linux"))]
+            Weighing::Allocator => crate::alloc_weigher::lines_size(entry.lines()) as u64,
+            Weighing::Custom(weigher) => u64::from(weigher(filename, entry.lines())),
+        };
+        lines.saturating_add(entry.derived().prebuilt_bytes.load(Ordering::Relaxed))
+    }
+}
+
+impl fmt::Debug for Weighing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Weighing::Capacity => f.write_str("Capacity"),
+            #[cfg(all(feature = "alloc-weigher", target_os = "linux"))]
+            Weighing::Allocator => f.write_str("Allocator"),
+            Weighing::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+/// 按给定预算、权重方式、驱逐策略与过期时间构建条目缓存；条目移除时退还配额用量，容量驱逐会通知 `on_evict` 回调
+/// Build the entry cache for the given budget, weighing strategy, eviction policy and expiration; removals refund quota usage, and capacity evictions notify the `on_evict` hook
+pub(crate) fn entries_cache(
+    budget: u64,
+    weighing: &Weighing,
+    policy: EntryPolicy,
+    hooks: &Hooks,
+    quotas: &Quotas,
+) -> Cache<String, FileEntry> {
+    let hooks = hooks.clone();
+    let quotas = quotas.clone();
+    let weigher = weighing.clone();
+    let weighing = weighing.clone();
+    let mut builder = CacheBuilder::new(budget).eviction_policy(policy.eviction.policy());
+    if let Some(ttl) = policy.time_to_live {
+        builder = builder.time_to_live(ttl);
+    }
+    if let Some(tti) = policy.time_to_idle {
+        builder = builder.time_to_idle(tti);
+    }
+    builder
+        .weigher(move |k: &String, v: &FileEntry| u32::try_from(weigher.entry_weight(k, v)).unwrap_or(u32::MAX))
+        .eviction_listener(move |key, entry, cause| {
+            quotas.refund(&key, weighing.entry_weight(&key, &entry));
+            if cause == RemovalCause::Size {
+                if let Some(hooks) = hooks.get() {
+                    hooks.on_evict(&key);
+                }
+            }
+        })
+        .build()
+}
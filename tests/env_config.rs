@@ -0,0 +1,26 @@
+#![cfg(feature = "sysinfo")]
+
+//! 环境变量只在首次构造缓存时读取一次，因此放在独立的测试进程中
+//! Environment variables are read once, when the first cache is built, so this runs in its own test process
+
+use linecache::AsyncLineCache;
+
+#[tokio::test]
+async fn test_environment_overrides_defaults() -> Result<(), Box<dyn std::error::Error>> {
+    std::env::set_var("LINECACHE_MAX_BYTES", "1048576");
+    std::env::set_var("LINECACHE_STAT_TTL_MS", "not-a-number");
+
+    assert_eq!(AsyncLineCache::new().memory_budget(), 1 << 20);
+    // 明确设置的值优先于环境变量 | explicit settings win over the environment
+    assert_eq!(AsyncLineCache::new_with_capacity(4096).memory_budget(), 4096);
+
+    // 无法解析的 TTL 被忽略，每次访问都会发现变更 | the unparsable TTL is ignored, so every access sees changes
+    let cache = AsyncLineCache::new();
+    let file = tempfile::NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "old")?;
+    assert_eq!(cache.get_line(path, 1).await?.as_deref(), Some("old"));
+    std::fs::write(path, "newer")?;
+    assert_eq!(cache.get_line(path, 1).await?.as_deref(), Some("newer"));
+    Ok(())
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "async")]
+
 use linecache::AsyncLineCache;
 use std::{collections::HashSet, time::Duration};
 use tempfile::NamedTempFile;
@@ -5,7 +7,7 @@ use tokio::time::sleep;
 
 #[tokio::test]
 async fn test_basic_line_retrieval_and_boundaries() -> Result<(), Box<dyn std::error::Error>> {
-    let cache = AsyncLineCache::new();
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
     let content = "Line 1\nLine 2\nLine 3\nLast Line\n";
     let file = NamedTempFile::new()?;
     let path = file.path().to_str().unwrap().to_string();
@@ -22,7 +24,7 @@ async fn test_basic_line_retrieval_and_boundaries() -> Result<(), Box<dyn std::e
 
 #[tokio::test]
 async fn test_empty_and_not_found_files() -> Result<(), Box<dyn std::error::Error>> {
-    let cache = AsyncLineCache::new();
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
 
     // 1. 文件不存在
     assert_eq!(
@@ -57,7 +59,7 @@ async fn test_empty_and_not_found_files() -> Result<(), Box<dyn std::error::Erro
 
 #[tokio::test]
 async fn test_file_modification_detection() -> Result<(), Box<dyn std::error::Error>> {
-    let cache = AsyncLineCache::new();
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
     let file = NamedTempFile::new()?;
     let path = file.path().to_str().unwrap().to_string();
 
@@ -78,7 +80,7 @@ async fn test_file_modification_detection() -> Result<(), Box<dyn std::error::Er
 
 #[tokio::test]
 async fn test_get_lines_and_content() -> Result<(), Box<dyn std::error::Error>> {
-    let cache = AsyncLineCache::new();
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
     let content = "Hello\nWorld\nRust\n"; // 以 \n 结尾
     let file = NamedTempFile::new()?;
     let path = file.path().to_str().unwrap().to_string();
@@ -100,7 +102,7 @@ async fn test_get_lines_and_content() -> Result<(), Box<dyn std::error::Error>>
 
 #[tokio::test]
 async fn test_random_getters() -> Result<(), Box<dyn std::error::Error>> {
-    let cache = AsyncLineCache::new();
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
     let content = "A\nB\nC\nD\n中文\n🚀\n";
     let file = NamedTempFile::new()?;
     let path = file.path().to_str().unwrap().to_string();
@@ -126,7 +128,7 @@ async fn test_random_getters() -> Result<(), Box<dyn std::error::Error>> {
 
 #[tokio::test]
 async fn test_invalidation_and_clear() -> Result<(), Box<dyn std::error::Error>> {
-    let cache = AsyncLineCache::new();
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
 
     let f1 = NamedTempFile::new()?;
     let p1 = f1.path().to_str().unwrap().to_string();
@@ -140,23 +142,22 @@ async fn test_invalidation_and_clear() -> Result<(), Box<dyn std::error::Error>>
     cache.get_content(&p1).await?;
     cache.get_line(&p2, 1).await?;
 
-    assert!(cache.lines.get(&p1).await.is_some());
-    assert!(cache.contents.get(&p1).await.is_some());
+    assert!(cache.contains(&p1));
 
     cache.invalidate(&p1).await;
-    assert!(cache.lines.get(&p1).await.is_none());
+    assert!(!cache.contains(&p1));
 
-    assert!(cache.lines.get(&p2).await.is_some());
+    assert!(cache.contains(&p2));
 
     cache.clear().await;
-    assert!(cache.lines.get(&p2).await.is_none());
+    assert!(!cache.contains(&p2));
 
     Ok(())
 }
 
 #[tokio::test]
 async fn test_weigher_sanity() -> Result<(), Box<dyn std::error::Error>> {
-    let cache = AsyncLineCache::new();
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
     let big = "X".repeat(10 * 1024 * 1024);
     let content = format!("{big}\nLine2\n");
 
@@ -167,9 +168,2246 @@ async fn test_weigher_sanity() -> Result<(), Box<dyn std::error::Error>> {
     cache.get_lines(&path).await?;
     cache.get_content(&path).await?;
 
-    assert!(cache.lines.get(&path).await.is_some());
-    assert!(cache.contents.get(&path).await.is_some());
+    let entry = cache.peek_entry(&path).unwrap();
+    assert_eq!(entry.raw().len(), big.len() + "\nLine2\n".len());
+
+    cache.clear().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_span_text_keeps_separators() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "a\r\nb\nc\n")?;
+
+    assert_eq!(cache.get_span_text(&path, 1, 2).await?, Some("a\r\nb".to_string()));
+    assert_eq!(cache.get_span_text(&path, 2, 3).await?, Some("b\nc".to_string()));
+    // 包含尾随空行时保留其前面的换行符 | including the trailing empty line keeps the separator before it
+    assert_eq!(cache.get_span_text(&path, 3, 99).await?, Some("c\n".to_string()));
+    assert_eq!(cache.get_span_text(&path, 0, 1).await?, Some("a".to_string()));
+    assert_eq!(cache.get_span_text(&path, 3, 2).await?, None);
+    assert_eq!(cache.get_span_text(&path, 5, 9).await?, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sliding_windows() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "a\nb\nc\nd")?;
+
+    let windows: Vec<Vec<String>> = cache.windows(&path, 2).await?.map(|w| w.to_vec()).collect();
+    assert_eq!(windows, vec![vec!["a", "b"], vec!["b", "c"], vec!["c", "d"]]);
+
+    let mut iter = cache.windows(&path, 3).await?;
+    assert_eq!(iter.len(), 2);
+    let second = iter.nth(1).unwrap();
+    assert_eq!(second.first_lineno(), 2);
+    assert_eq!(second.get(2), Some("d"));
+    assert_eq!(second.get(3), None);
+
+    assert_eq!(cache.windows(&path, 0).await?.count(), 0);
+    assert_eq!(cache.windows(&path, 5).await?.count(), 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_partition_ranges() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "1\n2\n3\n4\n5\n6\n7")?;
+
+    assert_eq!(cache.partition(&path, 3).await?, vec![1..3, 3..5, 5..8]);
+    assert_eq!(cache.partition(&path, 0).await?, vec![]);
+    let many = cache.partition(&path, 10).await?;
+    assert_eq!(many.len(), 10);
+    assert_eq!(many.iter().map(ExactSizeIterator::len).sum::<usize>(), 7);
+
+    // 第一行很长，按字节切分时应独占第一个区间
+    // The first line is long, so byte-balanced splitting gives it a range of its own
+    std::fs::write(&path, format!("{}\na\nb\nc", "x".repeat(100)))?;
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(cache.partition_by_bytes(&path, 2).await?, vec![1..2, 2..5]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_numbered_lines() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "x\ny\n")?;
+
+    let numbered: Vec<(usize, String)> = cache.numbered_lines(&path).await?.collect();
+    assert_eq!(
+        numbered,
+        vec![(1, "x".to_string()), (2, "y".to_string()), (3, String::new())]
+    );
+    for (lineno, line) in cache.numbered_lines(&path).await? {
+        assert_eq!(cache.get_line(&path, lineno).await?, Some(line));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_getlines_with_loader() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let loader = |name: &str| -> std::io::Result<Option<String>> {
+        Ok((name == "<zip>/mod.py").then(|| "import os\nprint(os)".to_string()))
+    };
+
+    // 没有加载器时与 get_lines 一致 | without a loader it behaves like get_lines
+    assert_eq!(cache.get_lines_with_loader("<zip>/mod.py", None).await?, None);
+
+    let lines = cache.get_lines_with_loader("<zip>/mod.py", Some(&loader)).await?;
+    assert_eq!(lines, Some(vec!["import os".to_string(), "print(os)".to_string()]));
+    // 之后无需加载器即可命中缓存 | afterwards the entry is served from cache without a loader
+    assert_eq!(cache.get_line("<zip>/mod.py", 2).await?.as_deref(), Some("print(os)"));
+    assert_eq!(cache.get_lines_with_loader("<zip>/other.py", Some(&loader)).await?, None);
+
+    // 注册的加载器在普通读取路径上同样生效 | registered loaders also serve the plain read path
+    cache.register_loader("<frozen>", std::sync::Arc::new(|_: &str| Ok(Some("frozen\n".to_string()))));
+    assert_eq!(cache.get_line("<frozen>", 1).await?.as_deref(), Some("frozen"));
+    assert!(cache.unregister_loader("<frozen>"));
+    cache.invalidate("<frozen>").await;
+    assert_eq!(cache.get_line("<frozen>", 1).await?, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_updatecache_forces_reload() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "old")?;
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("old"));
+
+    // 同样大小的写入可能不被 mtime+size 检测到，updatecache 无条件重读
+    // A same-size rewrite may slip past mtime+size detection; updatecache re-reads unconditionally
+    std::fs::write(&path, "new")?;
+    assert_eq!(cache.updatecache(&path).await?.to_vec(), vec!["new".to_string()]);
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("new"));
+
+    cache.register_loader("<gone>", std::sync::Arc::new(|_: &str| Ok(Some("from loader".to_string()))));
+    assert_eq!(cache.updatecache("<gone>").await?.len(), 1);
+    assert!(cache.updatecache("no-such-file.txt").await?.is_empty());
+
+    Ok(())
+}
+
+#[cfg(feature = "coding-cookie")]
+#[tokio::test]
+async fn test_coding_cookie_latin1_source() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("legacy.py");
+    let path = path.to_str().unwrap();
+
+    // 0xE9 在 latin-1 中是 'é'，但不是合法的 UTF-8
+    // 0xE9 is 'é' in latin-1 but invalid UTF-8
+    std::fs::write(path, b"#!/usr/bin/env python\n# -*- coding: latin-1 -*-\ns = 'caf\xe9'\n")?;
+    assert_eq!(cache.get_line(path, 3).await?.as_deref(), Some("s = 'café'"));
+    assert!(cache.get_content(path).await?.unwrap().contains("café"));
+
+    // 非源文件仍按 UTF-8 严格解码 | non-source files are still strict UTF-8
+    let txt = dir.path().join("legacy.txt");
+    std::fs::write(&txt, b"# coding: latin-1\ncaf\xe9\n")?;
+    assert!(cache.get_line(txt.to_str().unwrap(), 2).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stat_ttl_micro_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20).with_stat_ttl(Duration::from_millis(300));
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "v1\n")?;
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("v1"));
+
+    // 窗口内不再 stat，因此还看不到修改 | within the window no stat happens, so the change is not seen yet
+    std::fs::write(&path, "v2 changed\n")?;
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("v1"));
+
+    sleep(Duration::from_millis(400)).await;
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("v2 changed"));
+
+    // 手动失效同样清除 stat 微缓存 | manual invalidation also clears the stat micro-cache
+    std::fs::write(&path, "v3\n")?;
+    cache.invalidate(&path).await;
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("v3"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_offset_index_matches_str_lines() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+
+    for content in ["a\r\nb\rc\n\r\n", "\r\nx", "no newline", "\n\n", "tail\r"] {
+        std::fs::write(&path, content)?;
+        cache.invalidate(&path).await;
+
+        let mut expected: Vec<String> = content.lines().map(String::from).collect();
+        if content.ends_with('\n') {
+            expected.push(String::new());
+        }
+        assert_eq!(cache.get_lines(&path).await?, Some(expected), "content: {content:?}");
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "bytes")]
+#[tokio::test]
+async fn test_bytes_views_share_buffer() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "alpha\r\nbeta\n")?;
+
+    let line = cache.get_line_bytes(&path, 2).await?.unwrap();
+    assert_eq!(&line[..], b"beta");
+    let content = cache.get_content_bytes(&path).await?.unwrap();
+    assert_eq!(&content[..], b"alpha\r\nbeta\n");
+
+    // 视图与缓存共享同一块内存 | the views share memory with the cached buffer
+    let cached = cache.peek_entry(&path).unwrap();
+    assert_eq!(content.as_ptr(), cached.raw().as_ptr());
+    assert_eq!(cache.get_line_bytes(&path, 9).await?, None);
+    assert_eq!(cache.get_content_bytes("missing.txt").await?, None);
+
+    Ok(())
+}
+
+#[cfg(all(feature = "alloc-weigher", target_os = "linux"))]
+#[tokio::test]
+async fn test_allocator_weigher_charges_usable_size() -> Result<(), Box<dyn std::error::Error>> {
+    // SAFETY: 测试进程使用默认的系统分配器 | the test binary uses the default system allocator
+    let cache = unsafe { AsyncLineCache::new_with_capacity(64 << 20).with_allocator_weigher() };
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "x".repeat(4000))?;
+
+    cache.get_line(&path, 1).await?;
+    cache.run_pending_tasks().await;
+    // 至少包含文本本身 | at least covers the text itself
+    assert!(cache.memory_usage() >= 4000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unified_entry_stays_in_sync() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "old\n")?;
+
+    // 行、内容与元数据属于同一个条目 | lines, content and metadata belong to one entry
+    cache.get_line(&path, 1).await?;
+    let entry = cache.peek_entry(&path).unwrap();
+    assert_eq!(entry.raw(), "old\n");
+    assert_eq!(entry.lines().get(0), Some("old"));
+    assert!(entry.mtime().is_some());
+
+    // 文件变更后，内容与行一起刷新 | after a change, content and lines refresh together
+    std::fs::write(&path, "new content\n")?;
+    assert_eq!(cache.get_content(&path).await?.as_deref(), Some("new content\n"));
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("new content"));
+
+    // 被移除的条目不会留下孤立的元数据 | a removed entry leaves no orphaned metadata behind
+    cache.invalidate(&path).await;
+    assert_eq!(cache.get_content(&path).await?.as_deref(), Some("new content\n"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_invalidate_never_repopulated_with_stale_data() -> Result<(), Box<dyn std::error::Error>> {
+    // 长 TTL 让变更检测无法兜底，只能依靠失效与加载的顺序保证
+    // A long TTL means change detection cannot paper over races; only invalidate/load ordering can
+    let cache = AsyncLineCache::new_with_capacity(64 << 20).with_stat_ttl(Duration::from_secs(60));
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "v0\n")?;
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let cache = cache.clone();
+            let path = path.clone();
+            tokio::spawn(async move {
+                for _ in 0..200 {
+                    cache.get_content(&path).await.unwrap();
+                }
+            })
+        })
+        .collect();
+
+    for i in 1..50 {
+        let version = format!("v{i}\n");
+        std::fs::write(&path, &version)?;
+        cache.invalidate(&path).await;
+        // 失效之后只可能看到新版本（或尚未重新加载）| after invalidation only the new version (or nothing) may be cached
+        if let Some(entry) = cache.peek_entry(&path) {
+            assert_eq!(entry.raw(), version);
+        }
+    }
+    for reader in readers {
+        reader.await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancelled_load_leaves_consistent_state() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    let content = "line\n".repeat(1 << 20);
+    std::fs::write(&path, &content)?;
+
+    // 在加载的不同阶段丢弃调用方的 future | drop the caller's future at various points of the load
+    for micros in [0, 10, 100, 1000] {
+        cache.clear().await;
+        let _ = tokio::time::timeout(Duration::from_micros(micros), cache.get_content(&path)).await;
+        // 要么什么都没缓存，要么是完整的条目 | either nothing is cached or the whole entry is
+        if let Some(entry) = cache.peek_entry(&path) {
+            assert_eq!(entry.raw().len(), content.len());
+            assert_eq!(entry.lines().len(), (1 << 20) + 1);
+        }
+    }
+    assert_eq!(cache.get_content(&path).await?.map(|c| c.len()), Some(content.len()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_load_memory_limit_queues_large_loads() -> Result<(), Box<dyn std::error::Error>> {
+    // 上限远小于单个文件：加载只能逐个进行，但每个文件都必须成功
+    // The limit is far below a single file: loads run one at a time, yet every file must load
+    let cache = AsyncLineCache::new_with_capacity(64 << 20).with_load_memory_limit(4096);
+    let files: Vec<_> = (0..4).map(|_| NamedTempFile::new()).collect::<Result<_, _>>()?;
+    for (i, f) in files.iter().enumerate() {
+        std::fs::write(f.path(), format!("{i}\n").repeat(64 * 1024))?;
+    }
+
+    let loads = files.iter().map(|f| {
+        let cache = cache.clone();
+        let path = f.path().to_str().unwrap().to_string();
+        tokio::spawn(async move { cache.get_line(&path, 1).await })
+    });
+    for (i, load) in loads.collect::<Vec<_>>().into_iter().enumerate() {
+        assert_eq!(load.await??, Some(i.to_string()));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_random_lines_batch() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "a\nb\nc")?;
+
+    let batch = cache.random_lines(&path, 1000).await?;
+    assert_eq!(batch.len(), 1000);
+    let seen: HashSet<_> = batch.iter().map(String::as_str).collect();
+    // 有放回抽样：三行都应出现 | sampling with replacement: all three lines show up
+    assert_eq!(seen, HashSet::from(["a", "b", "c"]));
+
+    assert!(cache.random_lines(&path, 0).await?.is_empty());
+    assert!(cache.random_lines("missing.txt", 5).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_weighted_corpus_pool() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::CorpusPool;
+
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let heavy = NamedTempFile::new()?;
+    let light = NamedTempFile::new()?;
+    std::fs::write(heavy.path(), "heavy\n")?;
+    std::fs::write(light.path(), "light")?;
+
+    let mut pool = CorpusPool::new(cache);
+    assert_eq!(pool.random_line().await?, None);
+    pool.add(heavy.path().to_str().unwrap(), 3.0)
+        .add(light.path().to_str().unwrap(), 1.0);
+    assert_eq!(pool.len(), 2);
+
+    let mut heavy_hits = 0;
+    for _ in 0..2000 {
+        if pool.random_line().await?.as_deref() == Some("heavy") {
+            heavy_hits += 1;
+        }
+    }
+    // 期望约 3/4 的样本来自权重为 3 的文件（尾随空行也属于它）
+    // Roughly 3/4 of the samples should come from the weight-3 file (its trailing empty line included)
+    assert!((500..1100).contains(&heavy_hits), "heavy lines: {heavy_hits}");
+
+    // 成员文件变更后透明重新加载 | members are reloaded transparently after changing
+    assert!(pool.remove(heavy.path().to_str().unwrap()));
+    std::fs::write(light.path(), "changed")?;
+    assert_eq!(pool.random_line().await?.as_deref(), Some("changed"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_random_line_excluding() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::ExclusionSet;
+
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "a\nb\nc\nb")?;
+
+    // 按行号与按内容排除后只剩第 1 行 | excluding by number and by content leaves only line 1
+    let mut excluded = ExclusionSet::new();
+    excluded.exclude_lineno(3).exclude_line("b");
+    for _ in 0..50 {
+        assert_eq!(cache.random_line_excluding(&path, &excluded).await?, Some((1, "a".to_string())));
+    }
+
+    excluded.exclude_lineno(1);
+    assert_eq!(cache.random_line_excluding(&path, &excluded).await?, None);
+    assert_eq!(cache.random_line_excluding("missing.txt", &ExclusionSet::new()).await?, None);
+
+    Ok(())
+}
+
+#[cfg(feature = "generate")]
+#[tokio::test]
+async fn test_markov_generation() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "the cat sat down\nthe dog sat up\n")?;
+
+    let model = cache.build_markov(&path, 1).await?;
+    assert!(!model.is_empty());
+    let text = model.generate(20, &mut rand::thread_rng());
+    let words: Vec<&str> = text.split(' ').collect();
+    assert_eq!(words.len(), 20);
+    // 每个词都来自语料，且 "sat" 之后只会是 "down" / "up" 或新的起点 "the"
+    // Every word comes from the corpus, and "sat" is only followed by "down" / "up" or a fresh start "the"
+    let vocab = ["the", "cat", "dog", "sat", "down", "up"];
+    assert!(words.iter().all(|w| vocab.contains(w)));
+    for pair in words.windows(2).filter(|p| p[0] == "sat") {
+        assert!(["down", "up"].contains(&pair[1]));
+    }
+
+    assert!(cache.build_markov("missing.txt", 2).await?.generate(5, &mut rand::thread_rng()).is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ngram_counts_memoized() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "a b a b\nb a\n")?;
+
+    let bigrams = cache.ngrams(&path, 2).await?;
+    assert_eq!(bigrams.get("a b"), Some(&2));
+    assert_eq!(bigrams.get("b a"), Some(&2)); // 不跨行 | never spans lines
+    assert_eq!(bigrams.len(), 2);
+
+    // 同一版本复用记忆结果 | the same version reuses the memoized result
+    assert!(std::sync::Arc::ptr_eq(&bigrams, &cache.ngrams(&path, 2).await?));
+
+    std::fs::write(&path, "c d\n")?;
+    let changed = cache.ngrams(&path, 2).await?;
+    assert_eq!(changed.get("c d"), Some(&1));
+    assert!(cache.ngrams(&path, 0).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_write_shuffled_in_memory_and_chunked() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    let original: Vec<String> = (0..1000).map(|i| format!("line {i}")).collect();
+    std::fs::write(&path, original.join("\n") + "\n")?;
+
+    let dir = tempfile::tempdir()?;
+    for (name, chunk_lines) in [("memory.txt", usize::MAX), ("chunked.txt", 64)] {
+        let dest = dir.path().join(name);
+        assert_eq!(cache.write_shuffled_in_chunks(&path, &dest, chunk_lines).await?, 1000);
+
+        // 同一组行的一个排列，末尾兼容空行不参与 | a permutation of the same lines, without the compatibility empty line
+        let written = std::fs::read_to_string(&dest)?;
+        let mut shuffled: Vec<&str> = written.lines().collect();
+        assert_ne!(shuffled, original);
+        shuffled.sort_unstable();
+        let mut expected: Vec<&str> = original.iter().map(String::as_str).collect();
+        expected.sort_unstable();
+        assert_eq!(shuffled, expected);
+    }
+    // 临时分块文件已被清理 | temporary chunk files are cleaned up
+    assert_eq!(std::fs::read_dir(dir.path())?.count(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lifecycle_hooks() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::{CacheHooks, FileEntry};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+    impl CacheHooks for Recorder {
+        fn on_load(&self, _filename: &str, entry: &FileEntry) {
+            self.0.lock().unwrap().push(format!("load:{}", entry.lines().len()));
+        }
+        fn on_hit(&self, _filename: &str) {
+            self.0.lock().unwrap().push("hit".into());
+        }
+        fn on_miss(&self, _filename: &str) {
+            self.0.lock().unwrap().push("miss".into());
+        }
+        fn on_invalidate(&self, _filename: &str) {
+            self.0.lock().unwrap().push("invalidate".into());
+        }
+        fn on_clear(&self) {
+            self.0.lock().unwrap().push("clear".into());
+        }
+    }
+
+    let recorder = Arc::new(Recorder::default());
+    let cache = AsyncLineCache::new_with_capacity(64 << 20).with_hooks(recorder.clone());
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "a\nb")?;
 
+    cache.get_line(&path, 1).await?;
+    cache.get_line(&path, 2).await?;
+    cache.invalidate(&path).await;
     cache.clear().await;
+
+    assert_eq!(*recorder.0.lock().unwrap(), ["miss", "load:2", "hit", "invalidate", "clear"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_namespaces_are_isolated() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "x".repeat(64 * 1024))?;
+
+    let tenant_a = cache.namespace_with_budget("tenant-a", 1024 * 1024);
+    let tenant_b = cache.namespace("tenant-b");
+    tenant_a.get_line(&path, 1).await?;
+
+    // 键互相隔离，同名获取返回同一个命名空间 | keys are isolated, and the same name returns the same namespace
+    assert!(tenant_a.contains(&path));
+    assert!(!tenant_b.contains(&path));
+    assert!(!cache.contains(&path));
+    assert!(cache.namespace("tenant-a").contains(&path));
+
+    tenant_a.run_pending_tasks().await;
+    let usage = cache.namespace_usage();
+    assert_eq!(usage.len(), 2);
+    assert_eq!(usage[0].0, "tenant-a");
+    assert!(usage[0].1 >= 64 * 1024);
+    assert_eq!(usage[1], ("tenant-b".to_string(), 0));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_prefix_quota_bounds_directory() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let logs = tempfile::tempdir()?;
+    let prefix = format!("{}/", logs.path().to_str().unwrap());
+    cache.set_quota(&format!("{prefix}*"), 100 * 1024);
+
+    let paths: Vec<String> = (0..3)
+        .map(|i| {
+            let path = logs.path().join(format!("{i}.log"));
+            std::fs::write(&path, "x".repeat(40 * 1024)).unwrap();
+            path.to_str().unwrap().to_string()
+        })
+        .collect();
+    for path in &paths {
+        assert_eq!(cache.get_line(path, 1).await?.map(|l| l.len()), Some(40 * 1024));
+    }
+
+    // 用量不超过配额，最新写入的文件仍在缓存中 | usage stays within the quota, and the latest file is still cached
+    let usage = cache.quota_usage();
+    assert_eq!(usage.len(), 1);
+    assert!(usage[0].1 <= 100 * 1024, "used {}", usage[0].1);
+    assert!(cache.contains(&paths[2]));
+    let cached = cached_count(&cache, &paths).await;
+    assert_eq!(cached, 2);
+
+    // 单个文件超出配额：照常返回但不缓存 | a file alone over the quota is returned but not cached
+    let huge = logs.path().join("huge.log");
+    std::fs::write(&huge, "y".repeat(200 * 1024))?;
+    let huge = huge.to_str().unwrap();
+    assert!(cache.get_content(huge).await?.is_some());
+    assert!(!cache.contains(huge));
+
+    assert!(cache.remove_quota(&prefix));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_prefix_quota_holds_under_concurrent_loads() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let logs = tempfile::tempdir()?;
+    let prefix = format!("{}/", logs.path().to_str().unwrap());
+    cache.set_quota(&prefix, 100 * 1024);
+    let paths: Vec<String> = (0..16)
+        .map(|i| {
+            let path = logs.path().join(format!("{i}.log"));
+            std::fs::write(&path, "x".repeat(40 * 1024)).unwrap();
+            path.to_str().unwrap().to_string()
+        })
+        .collect();
+    let loads = paths.iter().map(|path| {
+        let cache = cache.clone();
+        let path = path.clone();
+        tokio::spawn(async move { cache.get_line(&path, 1).await })
+    });
+    for load in loads.collect::<Vec<_>>() {
+        assert!(load.await??.is_some());
+    }
+    // 预留是原子的：并发加载不会一起越过配额 | reservations are atomic: concurrent loads never overrun together
+    cache.run_pending_tasks().await;
+    let used = cache.quota_usage()[0].1;
+    assert!(used <= 100 * 1024, "used {used}");
+    assert!(cached_count(&cache, &paths).await <= 2);
+    Ok(())
+}
+
+async fn cached_count(cache: &AsyncLineCache, paths: &[String]) -> usize {
+    let mut n = 0;
+    for path in paths {
+        if cache.contains(path) {
+            n += 1;
+        }
+    }
+    n
+}
+
+#[tokio::test]
+async fn test_child_scope_clears_only_its_keys() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let shared = NamedTempFile::new()?;
+    let scratch = NamedTempFile::new()?;
+    let shared_path = shared.path().to_str().unwrap().to_string();
+    let scratch_path = scratch.path().to_str().unwrap().to_string();
+    std::fs::write(&shared_path, "shared")?;
+    std::fs::write(&scratch_path, "scratch")?;
+
+    cache.get_line(&shared_path, 1).await?;
+    let child = cache.child();
+    // 子缓存命中父缓存已加载的数据，并与父缓存共享新加载的数据
+    // The child hits data the parent loaded, and shares what it loads with the parent
+    assert_eq!(child.get_line(&shared_path, 1).await?.as_deref(), Some("shared"));
+    child.get_line(&scratch_path, 1).await?;
+    assert!(cache.contains(&scratch_path));
+    // 子缓存重新加载父缓存已有的键并不会引入它 | reloading a key the parent already had does not introduce it
+    std::fs::write(&shared_path, "shared, edited")?;
+    child.updatecache(&shared_path).await?;
+
+    child.clear().await;
+    assert!(!cache.contains(&scratch_path));
+    assert!(cache.contains(&shared_path));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_apply_config_on_live_cache() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::CacheConfig;
+
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "one")?;
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("one"));
+
+    // 热更新为长 TTL：窗口内的变更暂不可见，已缓存的数据保留
+    // Hot-reload to a long TTL: changes inside the window stay hidden, and cached data is kept
+    let handle = cache.clone();
+    handle.apply_config(&CacheConfig::new().stat_ttl(Duration::from_secs(60)).load_memory_limit(1 << 20));
+    cache.get_line(&path, 1).await?;
+    std::fs::write(&path, "two!")?;
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("one"));
+
+    // 关闭微缓存后立即重新校验 | disabling the micro-cache revalidates at once
+    handle.apply_config(&CacheConfig::new().stat_ttl(Duration::ZERO).quota("/data/", 1024));
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("two!"));
+    assert_eq!(cache.quota_usage(), [("/data/".to_string(), 0, 1024)]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shutdown_delivers_pending_removals() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let dir = tempfile::tempdir()?;
+    let prefix = format!("{}/", dir.path().display());
+    cache.set_quota(&prefix, 1 << 20);
+    let path = dir.path().join("a.txt");
+    std::fs::write(&path, "alpha\nbeta\n")?;
+    let path = path.to_str().unwrap();
+
+    let tenant = cache.namespace("tenant");
+    cache.get_line(path, 1).await?;
+    tenant.get_line(path, 1).await?;
+    assert!(cache.quota_usage()[0].1 > 0);
+
+    // 批量清空的移除通知是延迟送达的，shutdown 返回后配额与占用都已归零
+    // Bulk-clear removal notices are delivered lazily; once shutdown returns, quota usage and memory are back to zero
+    cache.clear().await;
+    tenant.clear().await;
+    cache.shutdown().await;
+    assert_eq!(cache.quota_usage()[0].1, 0);
+    assert_eq!(cache.memory_usage(), 0);
+    assert_eq!(tenant.memory_usage(), 0);
+
+    // 关闭后缓存仍可使用 | the cache stays usable after shutdown
+    assert_eq!(cache.get_line(path, 2).await?.as_deref(), Some("beta"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_watermarks_evict_then_reject() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::WatermarkExceeded;
+
+    let dir = tempfile::tempdir()?;
+    let line = "x".repeat(1000);
+    let paths: Vec<String> = (0..3)
+        .map(|i| {
+            let path = dir.path().join(format!("{i}.txt"));
+            std::fs::write(&path, &line).unwrap();
+            path.to_str().unwrap().to_string()
+        })
+        .collect();
+
+    // 先测出单个文件的权重 | measure the weight of one file first
+    let probe = AsyncLineCache::new_with_capacity(64 << 20);
+    probe.get_line(&paths[0], 1).await?;
+    probe.shutdown().await;
+    let weight = probe.memory_usage();
+
+    // 软水位线容纳两个文件：第三个写入时同步驱逐一个旧条目
+    // The soft mark fits two files: the third insert synchronously evicts an older entry
+    let cache = AsyncLineCache::new_with_capacity(64 << 20).with_watermarks(2 * weight + weight / 2, 4 * weight);
+    for path in &paths {
+        cache.get_line(path, 1).await?;
+    }
+    assert_eq!(cached_count(&cache, &paths).await, 2);
+    assert!(cache.contains(&paths[2]));
+    // 被驱逐的是最久未访问的条目 | the least recently accessed entry is the one evicted
+    assert!(!cache.contains(&paths[0]));
+
+    // 单个文件就超过硬水位线：返回类型化错误，且不写入缓存
+    // One file alone above the hard mark: a typed error is returned and nothing is cached
+    let big = dir.path().join("big.txt");
+    std::fs::write(&big, "y".repeat(8000))?;
+    let big = big.to_str().unwrap();
+    let err = cache.get_line(big, 1).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::OutOfMemory);
+    let exceeded = err.get_ref().and_then(|e| e.downcast_ref::<WatermarkExceeded>()).unwrap();
+    assert_eq!(exceeded.filename(), big);
+    assert_eq!(exceeded.hard(), 4 * weight);
+    assert!(!cache.contains(big));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_list_dir_cached_and_refreshed() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().to_str().unwrap();
+    std::fs::write(dir.path().join("b.txt"), "bb")?;
+    std::fs::write(dir.path().join("a.txt"), "a")?;
+    std::fs::create_dir(dir.path().join("sub"))?;
+
+    let items = cache.list_dir(root).await?;
+    let names: Vec<_> = items.iter().map(|item| (item.name(), item.size(), item.is_dir())).collect();
+    assert_eq!(names[..2], [("a.txt", 1, false), ("b.txt", 2, false)]);
+    assert!(items[2].is_dir() && items[2].mtime().is_some());
+
+    // 未变更时复用同一份列表 | an unchanged directory reuses the same listing
+    assert!(std::sync::Arc::ptr_eq(&items, &cache.list_dir(root).await?));
+
+    // 手动失效后重新列出 | relisted after manual invalidation
+    std::fs::write(dir.path().join("c.txt"), "ccc")?;
+    cache.invalidate(root).await;
+    let items = cache.list_dir(root).await?;
+    assert_eq!(items.len(), 4);
+    assert_eq!(items[2].name(), "c.txt");
+
+    assert!(cache.list_dir(&format!("{root}/missing")).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_discover_with_filters_and_preload() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::DiscoverFilter;
+
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().to_str().unwrap();
+    std::fs::create_dir_all(dir.path().join("sub/deeper"))?;
+    std::fs::write(dir.path().join("a.txt"), "a")?;
+    std::fs::write(dir.path().join("b.log"), "b")?;
+    std::fs::write(dir.path().join("sub/c.TXT"), "c".repeat(100))?;
+    std::fs::write(dir.path().join("sub/deeper/corpus_1.txt"), "d")?;
+    let found = |names: &[&str]| -> Vec<String> { names.iter().map(|name| format!("{root}/{name}")).collect() };
+
+    let txt = DiscoverFilter::new().extension(".txt");
+    assert_eq!(cache.discover(root, &txt).await?, found(&["a.txt", "sub/c.TXT", "sub/deeper/corpus_1.txt"]));
+    assert_eq!(cache.discover(root, &txt.clone().max_size(10)).await?, found(&["a.txt", "sub/deeper/corpus_1.txt"]));
+    assert_eq!(cache.discover(root, &DiscoverFilter::new().min_size(10)).await?, found(&["sub/c.TXT"]));
+    let glob = DiscoverFilter::new().pattern("corpus_?.*");
+    assert_eq!(cache.discover(root, &glob).await?, found(&["sub/deeper/corpus_1.txt"]));
+
+    // 预加载在后台进行，调用立即返回 | preloading runs in the background and the call returns at once
+    let paths = cache.discover(root, &txt.preload(true)).await?;
+    for _ in 0..100 {
+        if cached_count(&cache, &paths).await == paths.len() {
+            break;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(cached_count(&cache, &paths).await, 3);
+    cache.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_discover_relative_subdirectory_under_root_dir() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::DiscoverFilter;
+
+    let (root, outside) = (tempfile::tempdir()?, tempfile::tempdir()?);
+    std::fs::create_dir_all(root.path().join("sub/deeper"))?;
+    std::fs::write(root.path().join("sub/a.txt"), "a")?;
+    std::fs::write(root.path().join("sub/deeper/b.txt"), "b")?;
+    let cache = AsyncLineCache::builder().capacity(1 << 20).root_dir(root.path()).build();
+
+    // 相对子目录按根目录解析，而不是当前工作目录 | the relative subdirectory resolves against the root, not the working directory
+    let found = cache.discover("sub", &DiscoverFilter::new()).await?;
+    assert_eq!(found, ["sub/a.txt", "sub/deeper/b.txt"]);
+    assert_eq!(cache.get_line(&found[1], 1).await?.as_deref(), Some("b"));
+    assert_eq!(cache.discover("missing", &DiscoverFilter::new()).await.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    let escaping = cache.discover(outside.path().to_str().unwrap(), &DiscoverFilter::new()).await.unwrap_err();
+    assert_eq!(escaping.kind(), std::io::ErrorKind::PermissionDenied);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_random_line_in_dir_uniform_over_lines() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().to_str().unwrap();
+    std::fs::create_dir(dir.path().join("sub"))?;
+    std::fs::write(dir.path().join("one.txt"), "solo")?;
+    std::fs::write(dir.path().join("empty.txt"), "")?;
+    std::fs::write(dir.path().join("sub/many.txt"), "m1\nm2\nm3\nm4\nm5\nm6\nm7\nm8\nm9")?;
+
+    // 非递归时只会抽到顶层文件 | non-recursive sampling only sees the top level
+    let (path, line) = cache.random_line_in_dir(root, false).await?.unwrap();
+    assert!(path.ends_with("one.txt"));
+    assert_eq!(line, "solo");
+
+    // 行数被记住后按行均匀：9 行的文件约占 90% | uniform per line once counts are known: the 9-line file gets about 90%
+    cache.get_line(&format!("{root}/sub/many.txt"), 1).await?;
+    let mut from_many = 0;
+    for _ in 0..2000 {
+        let (path, _) = cache.random_line_in_dir(root, true).await?.unwrap();
+        if path.ends_with("many.txt") {
+            from_many += 1;
+        }
+    }
+    assert!((1600..1990).contains(&from_many), "{from_many}");
+
+    assert_eq!(cache.random_line_in_dir(&format!("{root}/sub/../sub"), false).await?.map(|(_, l)| l.len()), Some(2));
+    let empty = tempfile::tempdir()?;
+    assert_eq!(cache.random_line_in_dir(empty.path().to_str().unwrap(), true).await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_line_hashes_and_shared_lines() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let a = NamedTempFile::new()?;
+    let b = NamedTempFile::new()?;
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+    std::fs::write(a, "apple\nbanana\ncherry\napple\n")?;
+    std::fs::write(b, "cherry\ndate\napple\n")?;
+
+    let hashes = cache.line_hashes(a).await?;
+    assert_eq!(hashes.len(), 5);
+    assert_eq!(hashes[0], hashes[3]);
+    assert_ne!(hashes[0], hashes[1]);
+    assert!(std::sync::Arc::ptr_eq(&hashes, &cache.line_hashes(a).await?));
+
+    // 重复行只计一次，兼容空行不计 | duplicates count once and the compatibility empty line is ignored
+    assert_eq!(cache.shared_lines(a, b).await?, 2);
+    assert_eq!(cache.shared_lines(a, "/nonexistent/file").await?, 0);
+
+    // {apple, banana, cherry} 与 {cherry, date, apple}：交集 2，并集 4
+    // {apple, banana, cherry} vs {cherry, date, apple}: 2 shared out of 4
+    assert!((cache.jaccard_similarity(a, b).await? - 0.5).abs() < 1e-9);
+    assert!((cache.jaccard_similarity(a, a).await? - 1.0).abs() < 1e-9);
+    assert!(cache.jaccard_similarity(a, "/nonexistent/file").await?.abs() < 1e-9);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_merged_view_numbering_and_propagation() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let a = NamedTempFile::new()?;
+    let b = NamedTempFile::new()?;
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+    std::fs::write(a, "a1\na2\n")?;
+    std::fs::write(b, "b1\nb2\nb3")?;
+
+    let view = cache.merged_view(&[a, b]);
+    assert_eq!(view.line_count().await?, 5);
+    assert_eq!(view.get_line(2).await?.as_deref(), Some("a2"));
+    assert_eq!(view.get_line(3).await?.as_deref(), Some("b1"));
+    assert_eq!(view.get_line(6).await?, None);
+    assert_eq!(view.get_line(0).await?, None);
+    let line = view.random_line().await?.unwrap();
+    assert!(["a1", "a2", "b1", "b2", "b3"].contains(&line.as_str()));
+
+    // 成员变更后行号随之移动 | numbering shifts after a member changes
+    sleep(Duration::from_millis(10)).await;
+    std::fs::write(a, "a1\n")?;
+    assert_eq!(view.get_line(2).await?.as_deref(), Some("b1"));
+    assert_eq!(view.line_count().await?, 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_filtered_view_follows_source() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "keep 1\nskip\nkeep 2\n")?;
+
+    let view = cache.filtered_view(path, |line| line.starts_with("keep"));
+    assert_eq!(view.line_count().await?, 2);
+    assert_eq!(view.get_line(2).await?.as_deref(), Some("keep 2"));
+    assert_eq!(view.get_line(3).await?, None);
+    assert!(view.random_line().await?.unwrap().starts_with("keep"));
+
+    // 源文件重新加载后视图自动同步 | the view follows the source after it reloads
+    sleep(Duration::from_millis(10)).await;
+    std::fs::write(path, "skip\nkeep 3\n")?;
+    assert_eq!(view.get_line(1).await?.as_deref(), Some("keep 3"));
+    assert_eq!(view.line_count().await?, 1);
+
+    let none = cache.filtered_view(path, |_| false);
+    assert_eq!(none.random_line().await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sorted_view_search_and_prefix() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "pear\napple\nplum\napricot\nbanana\n")?;
+
+    let view = cache.sorted_view(path);
+    assert_eq!(view.line_count().await?, 5);
+    assert_eq!(view.get_line(1).await?.as_deref(), Some("apple"));
+    assert_eq!(view.get_line(5).await?.as_deref(), Some("plum"));
+    assert_eq!(view.find("banana").await?, Some(5));
+    assert_eq!(view.find("cherry").await?, None);
+    assert_eq!(view.with_prefix("ap", 10).await?, ["apple", "apricot"]);
+    assert_eq!(view.with_prefix("p", 1).await?, ["pear"]);
+    assert!(view.with_prefix("z", 10).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_column_view_projects_fields() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "id\tword\tcount\n1\tcafé\t3\n2\tnaïve\n3\n")?;
+
+    let words = cache.column_view(path, '\t', 1);
+    assert_eq!(words.line_count().await?, 4);
+    assert_eq!(words.get_line(2).await?.as_deref(), Some("café"));
+    assert_eq!(words.get_line(3).await?.as_deref(), Some("naïve"));
+    // 缺少该列的行为空 | a line lacking the column reads as empty
+    assert_eq!(words.get_line(4).await?.as_deref(), Some(""));
+    assert_eq!(words.get_line(5).await?, None);
+
+    let counts = cache.column_view(path, '\t', 2);
+    assert_eq!(counts.get_line(2).await?.as_deref(), Some("3"));
+    assert!(["1", "2", "3", "id"].contains(&cache.column_view(path, '\t', 0).random_line().await?.unwrap().as_str()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_inverted_index_search_and_rebuild() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "The quick fox\nlazy dog\nquick brown DOG, quick!\n")?;
+    let weight = |cache: &AsyncLineCache| cache.list_cached_weighted().first().map(|(_, weight)| *weight);
+
+    // 未登记的文件临时建立索引，不改变条目权重 | unregistered files get a transient index, leaving the weight alone
+    assert_eq!(cache.search(path, "quick").await?, [1, 3]);
+    let plain = weight(&cache);
+    assert_eq!(cache.search(path, "dog").await?, [2, 3]);
+    assert_eq!(weight(&cache), plain);
+
+    // 登记后索引计入条目权重 | once registered, the index counts in the entry's weight
+    cache.index(path).await?;
+    assert!(weight(&cache) > plain);
+    assert_eq!(cache.search(path, "quick").await?, [1, 3]);
+    assert_eq!(cache.search(path, "dog").await?, [2, 3]);
+    assert_eq!(cache.search(path, "Quick dog").await?, [3]);
+    assert!(cache.search(path, "cat").await?.is_empty());
+    assert!(cache.search(path, "  ").await?.is_empty());
+
+    // 重新加载后索引随新内容重建 | the index follows the new content after a reload
+    sleep(Duration::from_millis(10)).await;
+    std::fs::write(path, "cat\nquick cat\n")?;
+    assert_eq!(cache.search(path, "cat").await?, [1, 2]);
+    assert!(cache.unindex(path));
+    assert!(!cache.unindex(path));
+    Ok(())
+}
+
+#[cfg(feature = "autocomplete")]
+#[tokio::test]
+async fn test_autocomplete_distinct_prefix_matches() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "rust\nruby\nrun\nrust\npython\nrustacean\n")?;
+
+    assert_eq!(cache.autocomplete(path, "ru", 10).await?, ["ruby", "run", "rust", "rustacean"]);
+    assert_eq!(cache.autocomplete(path, "rus", 1).await?, ["rust"]);
+    assert!(cache.autocomplete(path, "go", 10).await?.is_empty());
+    assert!(cache.autocomplete(path, "r", 0).await?.is_empty());
+    Ok(())
+}
+
+#[cfg(feature = "detect-language")]
+#[tokio::test]
+async fn test_detect_language_by_script() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::Script;
+
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let cases = [
+        ("hello world\nthe quick fox\n", Some(Script::Latin)),
+        ("привет мир\nбыстрая лиса\n", Some(Script::Cyrillic)),
+        ("你好世界\n快速的狐狸\n", Some(Script::Han)),
+        ("日本語のテキスト\n", Some(Script::Japanese)),
+        ("안녕하세요\n", Some(Script::Hangul)),
+        ("12345\n---\n", None),
+    ];
+    for (content, expected) in cases {
+        let file = NamedTempFile::new()?;
+        let path = file.path().to_str().unwrap();
+        std::fs::write(path, content)?;
+        assert_eq!(cache.detect_language(path).await?, expected, "{content}");
+    }
+    assert_eq!(cache.detect_language("/nonexistent/file").await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_line_metadata_sidecar() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("greetings.txt");
+    let path = path.to_str().unwrap();
+    std::fs::write(path, "hello\nbonjour\nhola\n")?;
+    std::fs::write(format!("{path}.meta.jsonl"), "{\"locale\":\"en\"}\n\n")?;
+
+    let (line, meta) = cache.get_line_with_meta(path, 1).await?.unwrap();
+    assert_eq!((line.as_str(), meta.as_deref()), ("hello", Some("{\"locale\":\"en\"}")));
+    assert_eq!(cache.get_line_with_meta(path, 2).await?, Some(("bonjour".to_string(), None)));
+    assert_eq!(cache.get_line_with_meta(path, 3).await?, Some(("hola".to_string(), None)));
+    assert_eq!(cache.get_line_with_meta(path, 9).await?, None);
+
+    // 主文件失效时旁路文件一并失效 | invalidating the main file drops the sidecar too
+    let sidecar = format!("{path}.meta.jsonl");
+    assert!(cache.contains(&sidecar));
+    cache.invalidate(path).await;
+    assert!(!cache.contains(&sidecar));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_invalidate_group_never_mixes_versions() -> Result<(), Box<dyn std::error::Error>> {
+    // 长 TTL：只有失效才会让新内容可见 | long TTL: only invalidation makes new content visible
+    let cache = AsyncLineCache::new_with_capacity(64 << 20).with_stat_ttl(Duration::from_secs(60));
+    let dir = tempfile::tempdir()?;
+    let a = dir.path().join("a.txt").to_str().unwrap().to_string();
+    let b = dir.path().join("b.txt").to_str().unwrap().to_string();
+
+    for version in 0..20 {
+        std::fs::write(&a, format!("{version}"))?;
+        std::fs::write(&b, format!("{version}"))?;
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let (cache, a, b) = (cache.clone(), a.clone(), b.clone());
+                tokio::spawn(async move {
+                    for _ in 0..50 {
+                        let seen_a = cache.get_line(&a, 1).await.unwrap().unwrap();
+                        let seen_b = cache.get_line(&b, 1).await.unwrap().unwrap();
+                        // 看到新的 A 之后，B 不可能还是旧的 | once the new A is seen, B cannot still be old
+                        assert!(seen_b.parse::<u32>().unwrap() >= seen_a.parse::<u32>().unwrap());
+                    }
+                })
+            })
+            .collect();
+        cache.invalidate_group(&[&a, &b]).await;
+        for reader in readers {
+            reader.await?;
+        }
+    }
+    cache.invalidate_group(&[&a, &b]).await;
+    assert_eq!(cache.get_line(&b, 1).await?.as_deref(), Some("19"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_generation_increases_on_reload() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "v1")?;
+
+    let first = cache.generation(path).await?.unwrap();
+    assert_eq!(cache.generation(path).await?, Some(first));
+
+    sleep(Duration::from_millis(10)).await;
+    std::fs::write(path, "v2!")?;
+    let second = cache.generation(path).await?.unwrap();
+    assert!(second > first);
+
+    // 失效后重新加载同样算一次新版本 | a reload after invalidation counts as a new version too
+    cache.invalidate(path).await;
+    assert!(cache.generation(path).await?.unwrap() > second);
+    assert_eq!(cache.generation("/nonexistent/file").await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_snapshot_pins_versions() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let a = NamedTempFile::new()?;
+    let b = NamedTempFile::new()?;
+    let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+    std::fs::write(a, "a-old\n")?;
+    std::fs::write(b, "b-old")?;
+
+    let snapshot = cache.snapshot(&[a, b, "/nonexistent/file"]).await?;
+    assert!(!snapshot.contains("/nonexistent/file"));
+
+    // 缓存重新加载新版本后，快照仍然读到旧版本 | the snapshot keeps the old versions after the cache reloads
+    sleep(Duration::from_millis(10)).await;
+    std::fs::write(a, "a-new!\n")?;
+    std::fs::write(b, "b-new!")?;
+    cache.invalidate_group(&[a, b]).await;
+    assert_eq!(cache.get_line(a, 1).await?.as_deref(), Some("a-new!"));
+    assert_eq!(snapshot.get_line(a, 1), Some("a-old"));
+    assert_eq!(snapshot.get_line(b, 1), Some("b-old"));
+    assert_eq!(snapshot.get_line(b, 0), None);
+    assert_eq!(snapshot.random_line(b), Some("b-old"));
+    assert!(snapshot.generation(a) < cache.generation(a).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_case_insensitive_lookups() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "Rust\nPython\nrustup\nÉcole\n")?;
+
+    assert!(cache.contains_line(path, "Rust").await?);
+    assert!(!cache.contains_line(path, "rust").await?);
+    assert!(cache.contains_line_ignore_case(path, "RUST").await?);
+    assert!(cache.contains_line_ignore_case(path, "école").await?);
+    assert!(!cache.contains_line_ignore_case(path, "go").await?);
+
+    let view = cache.sorted_view(path);
+    assert_eq!(view.find_ignore_case("python").await?, Some(2));
+    assert_eq!(view.with_prefix_ignore_case("RUS", 10).await?, ["Rust", "rustup"]);
+
+    // 只有登记的文件保留折叠副本，并计入条目权重 | only registered files keep a folded copy, counted in the entry's weight
+    let weight = |cache: &AsyncLineCache| cache.list_cached_weighted().first().map(|(_, weight)| *weight);
+    let plain = weight(&cache);
+    cache.fold_case(path).await?;
+    assert!(weight(&cache) > plain);
+    assert!(cache.contains_line_ignore_case(path, "RUSTUP").await?);
+    assert_eq!(view.find_ignore_case("école").await?, Some(4));
+    assert!(cache.unfold_case(path));
+    assert!(!cache.unfold_case(path));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_line_length_policies() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::LineLengthPolicy;
+
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "short\r\nabcdefghij\r\nééé\n")?;
+
+    let truncate = AsyncLineCache::new_with_capacity(64 << 20).with_max_line_length(4, LineLengthPolicy::Truncate);
+    assert_eq!(truncate.get_lines(path).await?.unwrap(), ["shor", "abcd", "éé", ""]);
+
+    let split = AsyncLineCache::new_with_capacity(64 << 20).with_max_line_length(4, LineLengthPolicy::SplitIntoChunks);
+    assert_eq!(split.get_lines(path).await?.unwrap(), ["shor", "t", "abcd", "efgh", "ij", "éé", "é", ""]);
+    // 切分后仍保留原来的行分隔符 | original separators survive the split
+    assert!(split.get_content(path).await?.unwrap().contains("t\r\nabcd\nefgh\nij\r\n"));
+
+    let error = AsyncLineCache::new_with_capacity(64 << 20).with_max_line_length(8, LineLengthPolicy::Error);
+    let err = error.get_line(path, 1).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(!error.contains(path));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sanitize_on_load_reports_counts() -> Result<(), Box<dyn std::error::Error>> {
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "\u{FEFF}zero\u{200B}width\nbell\u{7}\tkept\n")?;
+
+    let plain = AsyncLineCache::new_with_capacity(64 << 20);
+    assert_eq!(plain.load_stats(path).await?.unwrap().sanitized_chars(), 0);
+    assert_eq!(plain.get_line(path, 1).await?.as_deref(), Some("\u{FEFF}zero\u{200B}width"));
+
+    let cache = AsyncLineCache::new_with_capacity(64 << 20).with_sanitize(true);
+    assert_eq!(cache.get_line(path, 1).await?.as_deref(), Some("zerowidth"));
+    assert_eq!(cache.get_line(path, 2).await?.as_deref(), Some("bell\tkept"));
+    assert_eq!(cache.load_stats(path).await?.unwrap().sanitized_chars(), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dedupe_on_load_per_file() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let other = NamedTempFile::new()?;
+    let (path, other) = (file.path().to_str().unwrap(), other.path().to_str().unwrap());
+    std::fs::write(path, "a\nb\na\n\nc\n\nb")?;
+    std::fs::write(other, "a\na")?;
+
+    assert_eq!(cache.get_lines(path).await?.unwrap().len(), 7);
+    cache.set_dedupe_on_load(path, true).await;
+    // 保留首次出现；去掉的末行没有换行符时不多出兼容空行
+    // First occurrences are kept; dropping a final line without a newline adds no compatibility empty line
+    assert_eq!(cache.get_lines(path).await?.unwrap(), ["a", "b", "", "c"]);
+    assert_eq!(cache.load_stats(path).await?.unwrap().dropped_duplicates(), 3);
+    // 只作用于开启了去重的文件 | only the opted-in file is deduplicated
+    assert_eq!(cache.get_lines(other).await?.unwrap(), ["a", "a"]);
+
+    cache.set_dedupe_on_load(path, false).await;
+    assert_eq!(cache.get_lines(path).await?.unwrap().len(), 7);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_page_clamps_and_counts() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "1\n2\n3\n4\n5")?;
+
+    let (page, pages) = cache.page(path, 2, 2).await?;
+    assert_eq!((page.to_vec(), page.first_lineno(), pages), (vec!["3".to_string(), "4".to_string()], 3, 3));
+    // 超出末页返回末页，0 视为第 1 页 | past the end returns the last page, 0 means the first
+    assert_eq!(cache.page(path, 99, 2).await?.0.to_vec(), ["5"]);
+    assert_eq!(cache.page(path, 0, 2).await?.0.to_vec(), ["1", "2"]);
+
+    let (empty, pages) = cache.page(path, 1, 0).await?;
+    assert!(empty.is_empty());
+    assert_eq!(pages, 0);
+    assert_eq!(cache.page("/nonexistent/file", 1, 10).await?.1, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_builder_sets_budget_and_eviction() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::{CacheConfig, Eviction};
+
+    let cache = AsyncLineCache::builder()
+        .capacity(8 * 1024)
+        .eviction(Eviction::Lru)
+        .metadata_capacity(16)
+        .config(CacheConfig::new().stat_ttl(Duration::from_millis(100)))
+        .build();
+    let dir = tempfile::tempdir()?;
+    let line = "x".repeat(60);
+    let mut paths = Vec::new();
+    for i in 0..3 {
+        let path = dir.path().join(format!("{i}.txt"));
+        std::fs::write(&path, format!("{line}\n").repeat(60))?;
+        paths.push(path.to_str().unwrap().to_string());
+    }
+    for path in &paths {
+        assert_eq!(cache.get_line(path, 1).await?.as_deref(), Some(line.as_str()));
+    }
+    cache.run_pending_tasks().await;
+    // LRU 总是接纳最新的文件，并把占用压在预算以内 | LRU always admits the newest file and keeps usage within the budget
+    assert!(cache.memory_usage() <= 8 * 1024);
+    assert!(cache.contains(paths[2].as_str()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_least_served_line_cycles_through_lines() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "a\nb\nc\n")?;
+    cache.track_served(path, true);
+
+    let mut seen = HashSet::new();
+    for _ in 0..3 {
+        let (lineno, line) = cache.least_served_line(path).await?.unwrap();
+        assert_eq!(cache.get_line(path, lineno).await?, Some(line));
+        seen.insert(lineno);
+    }
+    assert_eq!(seen, HashSet::from([1, 2, 3]));
+
+    // 关闭统计后退化为随机抽取 | with counting off it falls back to a random pick
+    cache.track_served(path, false);
+    assert!(cache.least_served_line(path).await?.is_some());
+    assert!(cache.least_served_line("/nonexistent/file").await?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_repeated_failures_quarantine_a_file() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::LineLengthPolicy;
+
+    let cache = AsyncLineCache::new_with_capacity(64 << 20).with_max_line_length(8, LineLengthPolicy::Error);
+    let dir = tempfile::tempdir()?;
+    let bad = dir.path().join("bad.txt");
+    let good = dir.path().join("good.txt");
+    std::fs::write(&bad, "this line is far too long\n")?;
+    std::fs::write(&good, "ok")?;
+    let bad = bad.to_str().unwrap();
+
+    for _ in 0..3 {
+        assert!(cache.get_line(bad, 1).await.is_err());
+    }
+    assert_eq!(cache.quarantined(), [bad]);
+    // 目录抽样跳过隔离的文件 | directory sampling skips the quarantined file
+    for _ in 0..10 {
+        let (path, line) = cache.random_line_in_dir(dir.path().to_str().unwrap(), false).await?.unwrap();
+        assert!(path.ends_with("good.txt") && line == "ok");
+    }
+
+    // 修复后的一次成功加载解除隔离 | one successful load after the fix releases it
+    std::fs::write(bad, "short\n")?;
+    assert_eq!(cache.get_line(bad, 1).await?.as_deref(), Some("short"));
+    assert!(cache.quarantined().is_empty());
+    assert!(!cache.release_quarantine(bad).await);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_new_with_capacity() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 * 1024);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "first\nsecond")?;
+    assert_eq!(cache.get_line(path, 2).await?.as_deref(), Some("second"));
+    assert!(cache.memory_usage() <= 64 * 1024);
+    Ok(())
+}
+
+#[cfg(feature = "sysinfo")]
+#[test]
+fn test_memory_fraction_with_cap() {
+    let capped = AsyncLineCache::builder().memory_fraction(0.5).memory_cap(16 * 1024 * 1024).build();
+    assert_eq!(capped.memory_budget(), 16 * 1024 * 1024);
+    let fractional = AsyncLineCache::builder().memory_fraction(0.1).build();
+    assert!(fractional.memory_budget() < AsyncLineCache::builder().memory_fraction(0.2).build().memory_budget());
+    assert_eq!(AsyncLineCache::new_with_capacity(4096).memory_budget(), 4096);
+}
+
+#[tokio::test]
+async fn test_audit_log_records_mutations() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::AuditLog;
+    use std::sync::Arc;
+
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("audit.jsonl");
+    let data = dir.path().join("say \"hi\".txt");
+    std::fs::write(&data, "hello\nworld\n")?;
+    let data = data.to_str().unwrap();
+
+    let cache = AsyncLineCache::new_with_capacity(64 << 20).with_hooks(Arc::new(AuditLog::new(std::fs::File::create(&log)?)));
+    cache.get_line(data, 1).await?;
+    cache.invalidate(data).await;
+    cache.insert_virtual("<repl>", "x = 1").await?;
+    cache.insert_virtual(data, "patched").await?;
+    cache.clear().await;
+
+    let records = std::fs::read_to_string(&log)?;
+    let records: Vec<&str> = records.lines().collect();
+    assert_eq!(records.len(), 5);
+    assert!(records[0].starts_with("{\"ts_ms\":"));
+    assert!(records[0].contains("\"event\":\"load\""));
+    assert!(records[0].contains("say \\\"hi\\\".txt\",\"bytes\":12,\"source\":\"disk\""));
+    assert!(records[1].contains("\"event\":\"invalidate\""));
+    // 手动写入与遮蔽磁盘文件的写入各有来源 | manual inserts and those shadowing a disk file have their own sources
+    assert!(records[2].contains("\"file\":\"<repl>\",\"bytes\":5,\"source\":\"virtual\""));
+    assert!(records[3].contains("\"source\":\"override\""));
+    assert!(records[4].ends_with("\"event\":\"clear\"}"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_within_deadline() -> Result<(), Box<dyn std::error::Error>> {
+    use tokio::time::Instant;
+
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "line")?;
+
+    let soon = Instant::now() + Duration::from_millis(20);
+    let slow = async {
+        sleep(Duration::from_secs(5)).await;
+        cache.get_line(path, 1).await
+    };
+    let err = AsyncLineCache::within_deadline(soon, slow).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+    let later = Instant::now() + Duration::from_secs(5);
+    assert_eq!(AsyncLineCache::within_deadline(later, cache.get_line(path, 1)).await?.as_deref(), Some("line"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_revalidation_off_and_try_get_line() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20).with_revalidation(false);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "old\nlines")?;
+
+    assert_eq!(cache.try_get_line(path, 1), None);
+    assert_eq!(cache.get_line(path, 1).await?.as_deref(), Some("old"));
+    assert_eq!(cache.try_get_line(path, 2).as_deref(), Some("lines"));
+    assert_eq!(cache.try_get_line(path, 3), None);
+
+    // 关闭变更检测后一直使用缓存，直到手动失效 | without change detection the entry is used until invalidated
+    std::fs::write(path, "brand new")?;
+    assert_eq!(cache.get_line(path, 1).await?.as_deref(), Some("old"));
+    cache.invalidate(path).await;
+    assert_eq!(cache.get_line(path, 1).await?.as_deref(), Some("brand new"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_entry_bytes_serves_without_caching() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::CacheHooks;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Oversized(Mutex<Vec<(String, u64)>>);
+    impl CacheHooks for Oversized {
+        fn on_oversize(&self, filename: &str, bytes: u64) {
+            self.0.lock().unwrap().push((filename.to_string(), bytes));
+        }
+    }
+
+    let hooks = Arc::new(Oversized::default());
+    let cache = AsyncLineCache::new_with_capacity(64 << 20).with_hooks(hooks.clone()).with_max_entry_bytes(16);
+    let dir = tempfile::tempdir()?;
+    let small = dir.path().join("small.txt");
+    let big = dir.path().join("big.txt");
+    std::fs::write(&small, "tiny")?;
+    std::fs::write(&big, "a rather long line of text")?;
+    let (small, big) = (small.to_str().unwrap(), big.to_str().unwrap());
+
+    assert_eq!(cache.get_line(small, 1).await?.as_deref(), Some("tiny"));
+    assert_eq!(cache.get_line(big, 1).await?.as_deref(), Some("a rather long line of text"));
+    assert!(cache.contains(small));
+    assert!(!cache.contains(big));
+    assert_eq!(*hooks.0.lock().unwrap(), [(big.to_string(), 26)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_line_at_fraction() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "a\nb\nc\nd\ne\n")?;
+
+    assert_eq!(cache.line_at_fraction(path, 0.0).await?, Some((1, "a".to_string())));
+    assert_eq!(cache.line_at_fraction(path, 0.5).await?, Some((3, "c".to_string())));
+    assert_eq!(cache.line_at_fraction(path, 1.0).await?, Some((5, "e".to_string())));
+    assert_eq!(cache.line_at_fraction(path, 7.0).await?, Some((5, "e".to_string())));
+    assert_eq!(cache.line_at_fraction(path, f64::NAN).await?, None);
+    assert_eq!(cache.line_at_fraction("/nonexistent/file", 0.5).await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_eviction_candidates_least_recent_first() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let dir = tempfile::tempdir()?;
+    let mut paths = Vec::new();
+    for name in ["a", "b", "c"] {
+        let path = dir.path().join(name);
+        std::fs::write(&path, name)?;
+        let path = path.to_str().unwrap().to_string();
+        cache.get_line(&path, 1).await?;
+        paths.push(path);
+    }
+    // 再次访问 a，使 b 成为最久未访问的条目 | touching a again leaves b the least recent
+    cache.get_line(&paths[0], 1).await?;
+    assert_eq!(cache.eviction_candidates(2).await, [paths[1].clone(), paths[2].clone()]);
+    assert_eq!(cache.eviction_candidates(10).await.len(), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_builder_time_to_idle_expires_entries() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::builder().capacity(64 << 20).time_to_idle(Duration::from_millis(50)).build();
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap();
+    std::fs::write(path, "line")?;
+
+    let first = cache.generation(path).await?;
+    assert_eq!(cache.generation(path).await?, first);
+    sleep(Duration::from_millis(120)).await;
+    cache.run_pending_tasks().await;
+    assert!(!cache.contains(path));
+    // 过期后在下次访问时重新加载 | reloaded on the next access after expiring
+    assert!(cache.generation(path).await? > first);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_by_content_hash() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let dir = tempfile::tempdir()?;
+    let old = dir.path().join("old.txt");
+    let moved = dir.path().join("moved.txt");
+    std::fs::write(&old, "same content\n")?;
+    let (old, moved) = (old.to_str().unwrap(), moved.to_str().unwrap());
+
+    let hash = cache.content_hash(old).await?.unwrap();
+    assert_eq!(cache.get_by_content_hash(hash).await?.as_deref(), Some(old));
+
+    // 文件移动后，新路径接管同一内容 | after a move, the new path takes over the same content
+    std::fs::rename(old, moved)?;
+    assert_eq!(cache.content_hash(moved).await?, Some(hash));
+    assert_eq!(cache.get_by_content_hash(hash).await?.as_deref(), Some(moved));
+
+    std::fs::write(moved, "edited")?;
+    assert_eq!(cache.get_by_content_hash(hash).await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_per_file_options_override_globals() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::FileOptions;
+
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let dir = tempfile::tempdir()?;
+    let frozen = dir.path().join("dict.big");
+    let small = dir.path().join("app.conf");
+    std::fs::write(&frozen, "v1")?;
+    std::fs::write(&small, "short")?;
+    let (frozen, small) = (frozen.to_str().unwrap(), small.to_str().unwrap());
+
+    cache.set_file_options("*.big", FileOptions::new().revalidate(false));
+    cache.set_file_options(small, FileOptions::new().max_bytes(2));
+
+    // 关闭变更检测的文件继续使用缓存 | the frozen file keeps its cached version
+    assert_eq!(cache.get_line(frozen, 1).await?.as_deref(), Some("v1"));
+    std::fs::write(frozen, "version 2")?;
+    assert_eq!(cache.get_line(frozen, 1).await?.as_deref(), Some("v1"));
+
+    // 超过按文件设置的上限：照常返回但不缓存 | above its own limit: served but not cached
+    assert_eq!(cache.get_line(small, 1).await?.as_deref(), Some("short"));
+    assert!(!cache.contains(small));
+
+    assert!(cache.clear_file_options("*.big"));
+    assert_eq!(cache.get_line(frozen, 1).await?.as_deref(), Some("version 2"));
+    assert!(!cache.clear_file_options("*.big"));
+
+    // 按文件的 TTL 到期后即使文件未变也重新加载 | a per-file TTL reloads even an unchanged file
+    cache.set_file_options(frozen, FileOptions::new().ttl(Duration::from_millis(30)));
+    let first = cache.generation(frozen).await?;
+    sleep(Duration::from_millis(60)).await;
+    assert!(cache.generation(frozen).await? > first);
+    Ok(())
+}
+
+#[cfg(feature = "coding-cookie")]
+#[tokio::test]
+async fn test_per_file_encoding_override() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::FileOptions;
+
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    let dir = tempfile::tempdir()?;
+    let (legacy, unknown) = (dir.path().join("词典.txt"), dir.path().join("odd.txt"));
+    std::fs::write(&legacy, b"caf\xe9\n")?;
+    std::fs::write(&unknown, "ok\n")?;
+    let (legacy, unknown) = (legacy.to_str().unwrap(), unknown.to_str().unwrap());
+    assert!(cache.get_line(legacy, 1).await.is_err());
+
+    // 通配模式可以匹配多字节字符 | globs match multi-byte characters
+    cache.set_file_options("*/词?.txt", FileOptions::new().encoding("latin1"));
+    assert_eq!(cache.get_line(legacy, 1).await?.as_deref(), Some("café"));
+    cache.set_file_options(unknown, FileOptions::new().encoding("no-such-encoding"));
+    assert_eq!(cache.get_line(unknown, 1).await.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_validate_config_and_self_test() -> std::io::Result<()> {
+    use linecache::{CacheConfig, LineLengthPolicy};
+
+    assert!(AsyncLineCache::validate_config(&CacheConfig::new().watermarks(1 << 20, 2 << 20)).is_ok());
+    let bad = CacheConfig::new().watermarks(2 << 20, 1 << 20).max_entry_bytes(0).load_memory_limit(0);
+    let err = AsyncLineCache::validate_config(&bad).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("soft watermark") && err.to_string().contains("max_entry_bytes"));
+    assert!(err.to_string().contains("silently raised"));
+
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    let report = cache.self_test().await?;
+    assert!(report.load() > Duration::ZERO);
+    assert!(cache.list_cached().is_empty());
+
+    // 行长度上限过小时读回的内容不符 | a too-small line limit fails the read-back check
+    cache.apply_config(&CacheConfig::new().max_line_length(4, LineLengthPolicy::Truncate));
+    assert_eq!(cache.self_test().await.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    Ok(())
+}
+
+#[cfg(feature = "test-hooks")]
+#[tokio::test]
+async fn test_sync_hook_orders_invalidate_after_parked_load() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::SyncPoint;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::Notify;
+
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "v1")?;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (parked, release) = (Arc::new(Notify::new()), Arc::new(Notify::new()));
+    let (seen, at_load, gate) = (events.clone(), parked.clone(), release.clone());
+    let cache = AsyncLineCache::new_with_capacity(64 << 20).with_sync_hook(move |point, _| {
+        seen.lock().unwrap().push(point);
+        let (at_load, gate) = (at_load.clone(), gate.clone());
+        async move {
+            if point == SyncPoint::Load {
+                at_load.notify_one();
+                gate.notified().await;
+            }
+        }
+    });
+
+    let reader = tokio::spawn({
+        let (cache, path) = (cache.clone(), path.clone());
+        async move { cache.get_line(&path, 1).await }
+    });
+    parked.notified().await;
+
+    // 加载停在同步点时，失效必须等待加载锁 | while the load is parked, the invalidation waits for the load lock
+    let invalidator = tokio::spawn({
+        let (cache, path) = (cache.clone(), path.clone());
+        async move { cache.invalidate(&path).await }
+    });
+    sleep(Duration::from_millis(30)).await;
+    assert!(!invalidator.is_finished());
+
+    release.notify_one();
+    assert_eq!(reader.await??.as_deref(), Some("v1"));
+    invalidator.await?;
+    assert_eq!(*events.lock().unwrap(), [SyncPoint::Checked, SyncPoint::Load, SyncPoint::Invalidate]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_custom_weigher_counts_lines() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::builder()
+        .capacity(1 << 20)
+        .weigher(|_, lines| u32::try_from(lines.len()).unwrap_or(u32::MAX))
+        .build();
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "a\nb\nc")?;
+
+    cache.get_line(&path, 1).await?;
+    cache.run_pending_tasks().await;
+    assert_eq!(cache.memory_usage(), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_random_line_weighted_namespaces() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    let (a, b) = (NamedTempFile::new()?, NamedTempFile::new()?);
+    std::fs::write(a.path(), "alpha")?;
+    std::fs::write(b.path(), "beta")?;
+    cache.namespace("tenant-a").get_line(a.path().to_str().unwrap(), 1).await?;
+    cache.namespace("tenant-b").get_line(b.path().to_str().unwrap(), 1).await?;
+
+    let weights = [("tenant-a", 1.0), ("tenant-b", 0.0), ("unknown", 5.0)];
+    for _ in 0..20 {
+        let picked = tokio::spawn({
+            let cache = cache.clone();
+            async move { cache.random_line_weighted_namespaces(&weights).await }
+        })
+        .await??;
+        assert_eq!(picked, Some(("tenant-a".to_string(), "alpha".to_string())));
+    }
+    assert_eq!(cache.random_line_weighted_namespaces(&[("tenant-b", -1.0)]).await?, None);
+    assert_eq!(cache.namespace_usage().len(), 2);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_root_dir_rejects_escaping_paths() -> Result<(), Box<dyn std::error::Error>> {
+    let (root, outside) = (tempfile::tempdir()?, tempfile::tempdir()?);
+    std::fs::write(root.path().join("ok.txt"), "inside")?;
+    std::fs::write(outside.path().join("secret.txt"), "secret")?;
+    std::os::unix::fs::symlink(outside.path().join("secret.txt"), root.path().join("link.txt"))?;
+    let cache = AsyncLineCache::builder().capacity(1 << 20).root_dir(root.path()).build();
+    let inside = root.path().join("ok.txt");
+    assert_eq!(cache.get_line(inside.to_str().unwrap(), 1).await?.as_deref(), Some("inside"));
+
+    let traversal = format!("{}/../{}/secret.txt", root.path().display(), outside.path().file_name().unwrap().to_str().unwrap());
+    let link = root.path().join("link.txt");
+    for escaping in [traversal.as_str(), link.to_str().unwrap(), outside.path().to_str().unwrap()] {
+        let err = cache.get_line(escaping, 1).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    }
+    assert_eq!(cache.list_dir(outside.path().to_str().unwrap()).await.unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+    assert_eq!(cache.list_dir(root.path().to_str().unwrap()).await?.len(), 2);
+    // 相对路径按根目录解析，而不是当前工作目录 | relative paths resolve against the root, not the working directory
+    assert_eq!(cache.get_line("ok.txt", 1).await?.as_deref(), Some("inside"));
+    for _ in 0..3 {
+        assert_eq!(cache.get_line("link.txt", 1).await.unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+    }
+    // 沙箱拒绝与内容无关，不计入隔离 | sandbox rejections say nothing about the content and do not count towards quarantine
+    assert!(cache.quarantined().is_empty());
+    assert_eq!(cache.list_dir(".").await?.len(), 2);
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "cannot be canonicalized")]
+fn test_missing_root_dir_fails_to_build() {
+    let dir = tempfile::tempdir().unwrap();
+    let _ = AsyncLineCache::builder().capacity(1 << 20).root_dir(dir.path().join("missing")).build();
+}
+
+#[tokio::test]
+async fn test_dump_region_shows_offsets_and_escapes() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(64 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "one\nt\two\r\nthree\nfour\n")?;
+
+    let dump = cache.dump_region(&path, 2, 1).await?.unwrap();
+    let rows: Vec<&str> = dump.lines().collect();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0], "       1  0x00000000  6f 6e 65 0a  \"one\\n\"");
+    assert_eq!(rows[1], ">      2  0x00000004  74 09 77 6f 0d 0a  \"t\\two\\r\\n\"");
+    assert!(rows[2].starts_with("       3  0x0000000a "));
+
+    assert!(cache.dump_region(&path, 0, 1).await?.is_none());
+    assert!(cache.dump_region(&path, 99, 1).await?.is_none());
+
+    // 转储文件中的字节：无法解码的行与会被清理掉的字符都原样可见 | the file's bytes are dumped: undecodable lines and characters sanitizing would strip stay visible
+    let sanitized = AsyncLineCache::new_with_capacity(64 << 20).with_sanitize(true);
+    std::fs::write(&path, b"ok\n\xffz\xe2\x80\x8b\n")?;
+    assert!(sanitized.get_line(&path, 2).await.is_err());
+    let dump = sanitized.dump_region(&path, 2, 0).await?.unwrap();
+    assert_eq!(dump, ">      2  0x00000003  ff 7a e2 80 8b 0a  \"\\xffz\\u{200b}\\n\"\n");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stats_split_hits_revalidations_and_loads() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::{CacheConfig, LatencyHistogram};
+
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "a\nb")?;
+
+    cache.get_line(&path, 1).await?; // 冷加载 | cold load
+    cache.get_line(&path, 2).await?; // stat TTL 为 0：重新校验 | zero stat TTL: revalidation
+    cache.apply_config(&CacheConfig::new().revalidate(false));
+    cache.get_line(&path, 1).await?; // 不做变更检测：命中 | no change detection: hit
+    cache.get_line(&path, 2).await?;
+
+    let stats = cache.stats();
+    assert_eq!((stats.loads().count(), stats.revalidations().count(), stats.hits().count()), (1, 1, 2));
+    assert!(stats.loads().quantile(1.0).unwrap() >= stats.loads().mean().unwrap());
+    assert_eq!(stats.hits().buckets().len(), 32);
+    assert_eq!(LatencyHistogram::bucket_bound(3), Duration::from_micros(8));
+    assert!(cache.namespace("other").stats().loads().mean().is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_manual_clock_drives_stat_ttl_and_file_ttl() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::{CacheConfig, FileOptions, ManualClock};
+    use std::sync::Arc;
+
+    let clock = Arc::new(ManualClock::new());
+    let cache = AsyncLineCache::builder()
+        .capacity(1 << 20)
+        .clock(clock.clone())
+        .config(CacheConfig::new().stat_ttl(Duration::from_secs(60)))
+        .build();
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "v1")?;
+
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("v1"));
+    cache.get_line(&path, 1).await?; // 通过 stat 校验并记录时刻 | passes the stat check and records it
+    std::fs::write(&path, "version 2")?;
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("v1"));
+    clock.advance(Duration::from_secs(61));
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("version 2"));
+
+    cache.set_file_options(&path, FileOptions::new().ttl(Duration::from_secs(3600)));
+    let first = cache.generation(&path).await?;
+    clock.advance(Duration::from_secs(3600));
+    assert!(cache.generation(&path).await? > first);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_load_timeout_on_hanging_read() -> Result<(), Box<dyn std::error::Error>> {
+    // 没有写入方的 FIFO 在打开时一直阻塞，模拟挂起的网络文件系统 | a FIFO without a writer blocks on open, like a hung network mount
+    let dir = tempfile::tempdir()?;
+    let fifo = dir.path().join("hung");
+    assert!(std::process::Command::new("mkfifo").arg(&fifo).status()?.success());
+    let path = fifo.to_str().unwrap();
+
+    let cache = AsyncLineCache::builder().capacity(1 << 20).load_timeout(Duration::from_millis(50)).build();
+    let err = cache.get_line(path, 1).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+    let plain = AsyncLineCache::new_with_capacity(1 << 20);
+    let err = plain.get_line_timeout(path, 1, Duration::from_millis(50)).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+    // 放行后台仍阻塞着的打开调用 | release the opens still blocked in the background
+    drop(std::fs::OpenOptions::new().write(true).open(&fifo)?);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_retry_policy_rides_out_transient_errors() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::RetryPolicy;
+    use std::io::ErrorKind;
+
+    // 读取目录报 EISDIR，把它当作“瞬时”错误：path 先是目录，稍后换成文件
+    // Reading a directory fails with EISDIR, treated as "transient" here: the path is a directory at first and becomes a file shortly after
+    let dir = tempfile::tempdir()?;
+    let flaky = dir.path().join("flaky");
+    std::fs::create_dir(&flaky)?;
+    let path = flaky.to_str().unwrap().to_string();
+
+    let plain = AsyncLineCache::new_with_capacity(1 << 20);
+    assert_eq!(plain.get_line(&path, 1).await.unwrap_err().kind(), ErrorKind::IsADirectory);
+
+    let policy = RetryPolicy::new().attempts(5).backoff(Duration::from_millis(20)).retry_on(&[ErrorKind::IsADirectory], &[]);
+    let cache = AsyncLineCache::builder().capacity(1 << 20).retry(policy).build();
+    let swap = tokio::spawn({
+        let flaky = flaky.clone();
+        async move {
+            sleep(Duration::from_millis(10)).await;
+            std::fs::remove_dir(&flaky).unwrap();
+            std::fs::write(&flaky, "recovered").unwrap();
+        }
+    });
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("recovered"));
+    swap.await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_uncached_reads_never_insert() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "one\ntwo\n")?;
+
+    assert_eq!(cache.get_line_uncached(&path, 2).await?.as_deref(), Some("two"));
+    assert_eq!(cache.lines_uncached(&path).await?.unwrap().len(), 3);
+    assert!(!cache.contains(&path));
+    assert_eq!(cache.get_line_uncached("/nonexistent/linecache/file", 1).await?, None);
+
+    // 已缓存的条目被复用 | a cached entry is reused
+    cache.get_line(&path, 1).await?;
+    let cached = cache.peek_entry(&path).unwrap();
+    assert!(std::sync::Arc::ptr_eq(&cache.lines_uncached(&path).await?.unwrap(), cached.lines()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_max_capacity_shrinks_at_runtime() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    let dir = tempfile::tempdir()?;
+    let mut paths = Vec::new();
+    for i in 0..4 {
+        let path = dir.path().join(format!("{i}.txt"));
+        std::fs::write(&path, "x".repeat(4096))?;
+        let path = path.to_str().unwrap().to_string();
+        cache.get_line(&path, 1).await?;
+        paths.push(path);
+    }
+    cache.run_pending_tasks().await;
+    let per_entry = cache.memory_usage() / 4;
+
+    cache.set_max_capacity(per_entry * 2).await?;
+    assert_eq!(cache.memory_budget(), per_entry * 2);
+    cache.run_pending_tasks().await;
+    assert!(cache.memory_usage() <= per_entry * 2);
+    // 最久未访问的先被驱逐 | the least recently accessed go first
+    assert!(!cache.contains(&paths[0]) && cache.contains(&paths[3]));
+
+    // 之后的写入同样受新上限约束 | later inserts honor the new limit too
+    cache.get_line(&paths[0], 1).await?;
+    cache.run_pending_tasks().await;
+    assert!(cache.memory_usage() <= per_entry * 2);
+
+    // 超过构造时的预算被拒绝，上限保持不变 | above the construction budget is rejected, leaving the limit as it was
+    let err = cache.set_max_capacity(2 << 20).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(cache.memory_budget(), per_entry * 2);
+
+    cache.set_max_capacity(per_entry * 3).await?;
+    assert_eq!(cache.memory_budget(), per_entry * 3);
+    cache.set_max_capacity(1 << 20).await?;
+    assert_eq!(cache.memory_budget(), 1 << 20);
+    Ok(())
+}
+
+#[cfg(feature = "sysinfo")]
+#[tokio::test]
+async fn test_memory_pressure_watcher_shrinks_budget() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    // 阈值高于任何主机的可用内存：始终处于压力之下 | a threshold above any host's memory: always under pressure
+    cache.watch_memory_pressure(u64::MAX, Duration::from_millis(5), 0.25);
+    for _ in 0..100 {
+        if cache.memory_budget() < 1 << 20 {
+            break;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(cache.memory_budget(), 1 << 18);
+    // 压力期间调用方设置的上限单独保存，压力上限仍然生效 | a limit set during pressure is kept apart; the pressure limit still applies
+    cache.set_max_capacity(1 << 19).await?;
+    assert_eq!(cache.memory_budget(), 1 << 18);
+    cache.set_max_capacity(1 << 17).await?;
+    assert_eq!(cache.memory_budget(), 1 << 17);
+    cache.shutdown().await;
+    Ok(())
+}
+
+#[cfg(feature = "sysinfo")]
+#[tokio::test]
+async fn test_compat_free_functions_follow_python() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::compat;
+    use std::sync::Arc;
+
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "first\nsecond")?;
+    assert_eq!(compat::getline(&path, 1).await, "first\n");
+    assert_eq!(compat::getline(&path, 2).await, "second\n");
+    assert_eq!(compat::getline(&path, 3).await, "");
+    assert_eq!(compat::getline(&path, 0).await, "");
+    assert_eq!(compat::getlines("/definitely/not/here.py").await, Vec::<String>::new());
+
+    std::fs::write(&path, "changed\n")?;
+    compat::checkcache(Some(&path)).await;
+    assert_eq!(compat::getlines(&path).await, vec!["changed\n".to_string()]);
+
+    let virtual_name = format!("{path}.generated.py");
+    assert!(!compat::lazycache("<string>", Arc::new(|_: &str| Ok(None))));
+    assert!(compat::lazycache(&virtual_name, Arc::new(|_: &str| Ok(Some("x = 1\n".to_string())))));
+    assert_eq!(compat::getline(&virtual_name, 1).await, "x = 1\n");
+    assert!(!compat::lazycache(&virtual_name, Arc::new(|_: &str| Ok(None))));
+
+    compat::clearcache().await;
+    assert!(!compat::global().contains(&path));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_checkcache_drops_only_stale_entries() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(1 << 20).with_stat_ttl(Duration::from_secs(3600));
+    let dir = tempfile::tempdir()?;
+    let mut paths = Vec::new();
+    for name in ["changed", "removed", "same"] {
+        let path = dir.path().join(name).to_str().unwrap().to_string();
+        std::fs::write(&path, "one\n")?;
+        cache.get_line(&path, 1).await?;
+        paths.push(path);
+    }
+    std::fs::write(&paths[0], "one\ntwo\n")?;
+    std::fs::remove_file(&paths[1])?;
+
+    assert_eq!(cache.checkcache(Some(&paths[2])).await, 0);
+    assert_eq!(cache.checkcache(None).await, 2);
+    assert!(!cache.contains(&paths[0]) && !cache.contains(&paths[1]));
+    assert!(cache.contains(&paths[2]));
+    assert_eq!(cache.get_line(&paths[0], 2).await?.as_deref(), Some("two"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_register_lazy_loads_on_first_access() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    cache.register_lazy("<generated>", move || {
+        let counter = Arc::clone(&counter);
+        async move {
+            counter.fetch_add(1, Ordering::SeqCst);
+            tokio::task::yield_now().await;
+            Ok("def f():\n    return 1\n".to_string())
+        }
+    });
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    assert_eq!(cache.get_line("<generated>", 2).await?.as_deref(), Some("    return 1"));
+    assert_eq!(cache.get_line("<generated>", 1).await?.as_deref(), Some("def f():"));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    cache.invalidate("<generated>").await;
+    assert!(cache.get_line("<generated>", 1).await?.is_some());
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    assert!(cache.unregister_loader("<generated>"));
+    cache.invalidate("<generated>").await;
+    assert_eq!(cache.get_line("<generated>", 1).await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_insert_virtual_shadows_disk() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    cache.insert_virtual("<repl-1>", "x = 1\ny = 2").await?;
+    assert_eq!(cache.get_line("<repl-1>", 2).await?.as_deref(), Some("y = 2"));
+    assert_eq!(cache.get_content("<repl-1>").await?.as_deref(), Some("x = 1\ny = 2"));
+
+    // 同名磁盘文件的修改不影响虚拟条目 | edits of a disk file of the same name leave the virtual entry alone
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "on disk\n")?;
+    cache.insert_virtual(&path, "virtual\n").await?;
+    std::fs::write(&path, "edited on disk\n")?;
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("virtual"));
+    cache.invalidate(&path).await;
+    assert_eq!(cache.get_line(&path, 1).await?.as_deref(), Some("edited on disk"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_prefix_loaders_resolve_virtual_names() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::Arc;
+
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    cache.set_loader("mem://", Arc::new(|name: &str| Ok(Some(format!("from {name}\n")))));
+    cache.set_loader("mem://special/", Arc::new(|_: &str| Ok(Some("special\n".to_string()))));
+    cache.set_loader("<frozen ", Arc::new(|_: &str| Ok(Some("frozen source\n".to_string()))));
+
+    assert_eq!(cache.get_line("mem://a.py", 1).await?.as_deref(), Some("from mem://a.py"));
+    assert_eq!(cache.get_line("mem://special/b.py", 1).await?.as_deref(), Some("special"));
+    assert_eq!(cache.get_line("<frozen importlib._bootstrap>", 1).await?.as_deref(), Some("frozen source"));
+
+    // 按文件名注册的加载器优先 | loaders registered for the exact name win
+    cache.register_loader("mem://c.py", Arc::new(|_: &str| Ok(Some("exact\n".to_string()))));
+    assert_eq!(cache.get_line("mem://c.py", 1).await?.as_deref(), Some("exact"));
+
+    assert!(cache.unset_loader("<frozen "));
+    assert!(!cache.unset_loader("<frozen "));
+    assert_eq!(cache.get_line("<frozen zipimport>", 1).await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_lines_range_clamps_to_bounds() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    let content: String = (1..=60).map(|i| format!("line {i}\n")).collect();
+    std::fs::write(&path, content)?;
+
+    let context = cache.get_lines_range(&path, 40, 50).await?;
+    assert_eq!(context.len(), 11);
+    assert_eq!(context.first_lineno(), 40);
+    assert_eq!(context.get(0), Some("line 40"));
+    assert_eq!(context.get(10), Some("line 50"));
+
+    assert_eq!(cache.get_lines_range(&path, 0, 2).await?.to_vec(), vec!["line 1", "line 2"]);
+    assert_eq!(cache.get_lines_range(&path, 59, 1000).await?.len(), 3); // 含尾随空行 | the trailing empty line included
+    assert!(cache.get_lines_range(&path, 70, 80).await?.is_empty());
+    assert!(cache.get_lines_range(&path, 5, 4).await?.is_empty());
+    assert!(cache.get_lines_range("/definitely/not/here.txt", 1, 3).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_head_and_tail_skip_trailing_empty_line() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "a\nb\nc\nd\n")?;
+
+    assert_eq!(cache.head(&path, 2).await?.to_vec(), vec!["a", "b"]);
+    assert_eq!(cache.tail(&path, 2).await?.to_vec(), vec!["c", "d"]);
+    assert_eq!(cache.tail(&path, 2).await?.first_lineno(), 3);
+    assert_eq!(cache.head(&path, 10).await?.len(), 4);
+    assert_eq!(cache.tail(&path, 10).await?.len(), 4);
+    assert!(cache.tail(&path, 0).await?.is_empty());
+
+    // 没有末尾换行符时最后一行照常计入 | without a final newline the last line counts as usual
+    std::fs::write(&path, "a\nb\nc")?;
+    assert_eq!(cache.tail(&path, 1).await?.to_vec(), vec!["c"]);
+    assert!(cache.head("/definitely/not/here.log", 3).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_line_count_matches_get_line_bounds() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "a\nb\nc\n")?;
+
+    assert_eq!(cache.try_line_count(&path), None);
+    let count = cache.line_count(&path).await?;
+    assert_eq!(count, 4);
+    assert!(cache.get_line(&path, count).await?.is_some());
+    assert!(cache.get_line(&path, count + 1).await?.is_none());
+    assert_eq!(cache.try_line_count(&path), Some(4));
+    assert_eq!(cache.line_count("/definitely/not/here.txt").await?, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_contains_and_peek_never_load() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "old\n")?;
+
+    assert!(!cache.contains(&path));
+    assert!(cache.peek_lines(&path).is_none());
+    assert!(!cache.contains(&path));
+
+    cache.get_line(&path, 1).await?;
+    std::fs::write(&path, "newer\n")?;
+    assert!(cache.contains(&path));
+    // 不做变更检测：仍是缓存的旧版本 | no change detection: still the cached old version
+    assert_eq!(cache.peek_lines(&path).and_then(|lines| lines.get(0).map(String::from)).as_deref(), Some("old"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peek_counts_as_a_read_for_eviction() -> Result<(), Box<dyn std::error::Error>> {
+    use linecache::Eviction;
+
+    let dir = tempfile::tempdir()?;
+    let paths: Vec<String> = ["a", "b", "c"]
+        .iter()
+        .map(|name| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, "same size\n").unwrap();
+            path.to_str().unwrap().to_string()
+        })
+        .collect();
+    let probe = AsyncLineCache::new_with_capacity(64 << 20);
+    probe.get_line(&paths[0], 1).await?;
+    probe.run_pending_tasks().await;
+    let weight = probe.memory_usage();
+
+    // 容量只够两个文件 | room for two files only
+    let cache = AsyncLineCache::builder().capacity(2 * weight + weight / 2).eviction(Eviction::Lru).build();
+    cache.get_line(&paths[0], 1).await?;
+    cache.get_line(&paths[1], 1).await?;
+    cache.run_pending_tasks().await;
+    // 查看 a 刷新了它在 LRU 中的位置，于是 c 写入时驱逐的是 b | peeking at a refreshes its LRU position, so writing c evicts b
+    assert!(cache.peek_lines(&paths[0]).is_some());
+    cache.run_pending_tasks().await;
+    cache.get_line(&paths[2], 1).await?;
+    cache.run_pending_tasks().await;
+    assert!(cache.contains(&paths[0]));
+    assert!(!cache.contains(&paths[1]));
+    assert!(cache.contains(&paths[2]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_list_cached_reports_resident_paths() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = AsyncLineCache::new_with_capacity(1 << 20);
+    let dir = tempfile::tempdir()?;
+    let small = dir.path().join("a.txt").to_str().unwrap().to_string();
+    let large = dir.path().join("b.txt").to_str().unwrap().to_string();
+    std::fs::write(&small, "x\n")?;
+    std::fs::write(&large, "y\n".repeat(1000))?;
+    assert!(cache.list_cached().is_empty());
+
+    cache.get_line(&large, 1).await?;
+    cache.get_line(&small, 1).await?;
+    assert_eq!(cache.list_cached(), vec![small.clone(), large.clone()]);
+
+    let weighted = cache.list_cached_weighted();
+    assert_eq!(weighted.len(), 2);
+    assert!(weighted[1].1 > weighted[0].1);
+    cache.run_pending_tasks().await;
+    assert_eq!(weighted.iter().map(|(_, weight)| weight).sum::<u64>(), cache.memory_usage());
     Ok(())
 }
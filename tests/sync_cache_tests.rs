@@ -0,0 +1,25 @@
+#![cfg(feature = "sync")]
+
+//! 阻塞版 `LineCache` 不依赖 `async` 特性，单独成一个测试目标，以便在 `--no-default-features --features sync` 下运行
+//! The blocking `LineCache` does not depend on the `async` feature, so it is its own test target that runs under `--no-default-features --features sync`
+
+use linecache::LineCache;
+use tempfile::NamedTempFile;
+
+#[test]
+fn test_sync_line_cache_without_runtime() -> Result<(), Box<dyn std::error::Error>> {
+    let cache = LineCache::new_with_capacity(1 << 20);
+    let file = NamedTempFile::new()?;
+    let path = file.path().to_str().unwrap().to_string();
+    std::fs::write(&path, "alpha\nbeta\n")?;
+    assert_eq!(cache.get_line(&path, 2)?.as_deref(), Some("beta"));
+    assert_eq!(cache.get_line(&path, 3)?.as_deref(), Some(""));
+    assert_eq!(cache.get_lines(&path)?.map(|lines| lines.len()), Some(3));
+
+    std::fs::write(&path, "gamma\n")?;
+    assert_eq!(cache.get_line(&path, 1)?.as_deref(), Some("gamma"));
+    cache.invalidate(&path);
+    assert_eq!(cache.get_line(&path, 1)?.as_deref(), Some("gamma"));
+    assert_eq!(cache.get_lines("/definitely/not/here.txt")?, None);
+    Ok(())
+}